@@ -1,6 +1,7 @@
 use std::io;
 use std::io::{Cursor, Read, Seek};
 use byteorder::{ReadBytesExt, LE};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
 
 pub const DF_READ: u16 = 0x0001;
 pub const DF_WRITE: u16 = 0x0002;
@@ -12,6 +13,8 @@ pub const DF_0400: u16 =  0x0400;
 pub const DF_EXISTS: u16 = 0x8000;
 pub const DF_HIDDEN: u16 = 0x2000;
 
+pub const ENTRY_SIZE: usize = 512;
+
 #[derive(Debug, Copy, Clone)]
 pub struct DateTime {
     seconds: u8,
@@ -40,6 +43,40 @@ impl DateTime {
             years,
         }
     }
+
+    pub(crate) fn from_naive(time: NaiveDateTime) -> DateTime {
+        Self {
+            seconds: time.second() as u8,
+            minutes: time.minute() as u8,
+            hours: time.hour() as u8,
+            days: time.day() as u8,
+            months: time.month() as u8,
+            years: time.year() as u16,
+        }
+    }
+
+    /// Unset timestamps are common on cards formatted by homebrew, so they fall back to the epoch
+    pub(crate) fn to_naive(self) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(self.years as i32, self.months as u32, self.days as u32)
+            .and_then(|date| {
+                date.and_hms_opt(self.hours as u32, self.minutes as u32, self.seconds as u32)
+            })
+            .unwrap_or_default()
+    }
+
+    fn to_bytes(self) -> [u8; 8] {
+        let [year_low, year_high] = self.years.to_le_bytes();
+        [
+            0,
+            self.seconds,
+            self.minutes,
+            self.hours,
+            self.days,
+            self.months,
+            year_low,
+            year_high,
+        ]
+    }
 }
 
 #[derive(Debug, Copy, Clone)]
@@ -49,7 +86,7 @@ pub struct DirEntry {
     pub(crate) created: DateTime,
     pub cluster: u32,
     dir_entry: u32,
-    modified: DateTime,
+    pub(crate) modified: DateTime,
     attributes: u32,
     pub(crate) name: [u8; 32],
 }
@@ -89,6 +126,45 @@ impl DirEntry {
             name,
         })
     }
+
+    /// A fresh entry created and modified at `time`, `name` must fit in 31 bytes
+    pub(crate) fn new(
+        mode: u16,
+        length: u32,
+        cluster: u32,
+        dir_entry: u32,
+        name: &str,
+        time: DateTime,
+    ) -> DirEntry {
+        let mut name_bytes = [0; 32];
+        name_bytes[..name.len()].copy_from_slice(name.as_bytes());
+
+        DirEntry {
+            mode,
+            length,
+            created: time,
+            cluster,
+            dir_entry,
+            modified: time,
+            attributes: 0,
+            name: name_bytes,
+        }
+    }
+
+    pub(crate) fn to_bytes(self) -> [u8; ENTRY_SIZE] {
+        let mut bytes = [0; ENTRY_SIZE];
+        bytes[0..2].copy_from_slice(&self.mode.to_le_bytes());
+        bytes[4..8].copy_from_slice(&self.length.to_le_bytes());
+        bytes[8..16].copy_from_slice(&self.created.to_bytes());
+        bytes[16..20].copy_from_slice(&self.cluster.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.dir_entry.to_le_bytes());
+        bytes[24..32].copy_from_slice(&self.modified.to_bytes());
+        bytes[32..36].copy_from_slice(&self.attributes.to_le_bytes());
+        bytes[64..96].copy_from_slice(&self.name);
+
+        bytes
+    }
+
     pub fn is_empty(&self) -> bool {
         self.name[0] == 0x00
     }
@@ -104,6 +180,14 @@ impl DirEntry {
             .to_string()
     }
 
+    pub fn exists(&self) -> bool {
+        self.mode & DF_EXISTS != 0
+    }
+
+    pub fn is_file(&self) -> bool {
+        self.mode & DF_FILE != 0
+    }
+
     pub fn is_directory(&self) -> bool {
         self.mode & DF_DIRECTORY != 0 // Typical DOS attribute flag for directory
     }
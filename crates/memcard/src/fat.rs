@@ -1,7 +1,30 @@
-use crate::dir_entry::DirEntry;
+use crate::dir_entry::{
+    DateTime, DirEntry, DF_0400, DF_DIRECTORY, DF_EXECUTE, DF_EXISTS, DF_FILE, DF_HIDDEN, DF_READ,
+    DF_WRITE, ENTRY_SIZE,
+};
+use crate::save::Save;
 use byteorder::{ReadBytesExt, LE};
+use chrono::Local;
+use ps2_filetypes::{PSUEntry, PSUEntryKind, FILE_ID};
 use std::cmp::min;
-use std::io::{Cursor, Read, Seek};
+use std::collections::BTreeSet;
+use std::io;
+use std::io::{Cursor, ErrorKind, Read, Seek, Write};
+
+pub const MAGIC: &[u8; 28] = b"Sony PS2 Memory Card Format ";
+
+const CHAIN_END: u32 = 0x7FFFFFFF;
+const ALLOCATED: u32 = 0x80000000;
+/// Every 128 bytes of a page get 3 bytes of ECC in its spare area
+const ECC_CHUNK_SIZE: usize = 128;
+/// Longest name a directory entry can hold, they're stored in 32 bytes with a terminating zero
+pub const MAX_NAME_LENGTH: usize = 31;
+/// Clusters on a standard 8MB card
+pub const CLUSTERS_8MB: u32 = 8192;
+
+const DIRECTORY_MODE: u16 = DF_READ | DF_WRITE | DF_EXECUTE | DF_DIRECTORY | DF_0400 | DF_EXISTS;
+const PARENT_MODE: u16 = DF_WRITE | DF_EXECUTE | DF_DIRECTORY | DF_0400 | DF_HIDDEN | DF_EXISTS;
+const FILE_MODE: u16 = DF_READ | DF_WRITE | DF_EXECUTE | DF_FILE | DF_0400 | DF_EXISTS;
 
 #[derive(Debug)]
 pub struct Superblock {
@@ -73,28 +96,47 @@ pub struct Memcard {
     page_size: usize,
     pages_per_cluster: usize,
     ifc_list: [u32; 32],
-    pub(crate) rootdir_cluster: usize,
+    pub rootdir_cluster: usize,
     alloc_offset: usize,
+    alloc_end: usize,
     spare_size: usize,
     raw_page_size: usize,
     cluster_size: usize,
     fat_per_cluster: usize,
+    fat_clusters: Vec<u32>,
     fat_matrix: Vec<Vec<u32>>,
-    root_entry: Option<DirEntry>,
     entries_in_root: Vec<DirEntry>,
+    /// Pages that failed their ECC check when they were read
+    ecc_errors: BTreeSet<u32>,
 }
 
 impl Memcard {
-    pub fn new(file: Vec<u8>) -> Memcard {
+    /// Opens a card image, raw dumps with ECC data after every page and plain ones without
+    pub fn new(file: Vec<u8>) -> io::Result<Memcard> {
+        if !file.starts_with(MAGIC) {
+            return Err(invalid("Not a PS2 memory card image"));
+        }
+        let len = file.len();
         let mut c = Cursor::new(file);
-        let sb = read_superblock(&mut c).unwrap();
+        let sb = read_superblock(&mut c)?;
+        if sb.page_size == 0
+            || !(sb.page_size as usize).is_multiple_of(ECC_CHUNK_SIZE)
+            || sb.pages_per_cluster == 0
+            || sb.rootdir_cluster >= sb.alloc_end
+        {
+            return Err(invalid("Corrupted memory card superblock"));
+        }
 
         let page_size = sb.page_size as usize;
         let pages_per_cluster = sb.pages_per_cluster as usize;
         let ifc_list: [u32; 32] = sb.ifc_list;
         let rootdir_cluster = sb.rootdir_cluster as usize;
         let alloc_offset = sb.alloc_offset as usize;
+        let alloc_end = sb.alloc_end as usize;
+        // Dumps taken without the spare area have no ECC to check or write
         let spare_size = (page_size / 128) * 4;
+        let raw_len = sb.clusters_per_card as usize * pages_per_cluster * (page_size + spare_size);
+        let spare_size = if len >= raw_len { spare_size } else { 0 };
         let raw_page_size = page_size + spare_size;
         let cluster_size = page_size * pages_per_cluster;
         let fat_per_cluster = cluster_size / 4;
@@ -106,18 +148,23 @@ impl Memcard {
             ifc_list,
             rootdir_cluster,
             alloc_offset,
+            alloc_end,
             spare_size,
             raw_page_size,
             cluster_size,
             fat_per_cluster,
+            fat_clusters: vec![],
             fat_matrix: vec![],
-            root_entry: None,
             entries_in_root: vec![],
+            ecc_errors: BTreeSet::new(),
         };
 
         mc.build_fat_matrix();
+        if mc.fat_matrix.len() * fat_per_cluster < alloc_end {
+            return Err(invalid("Memory card FAT is smaller than the card"));
+        }
 
-        mc
+        Ok(mc)
     }
 
     fn build_matrix(&mut self, cluster_list: Vec<u32>) -> Vec<Vec<u32>> {
@@ -134,16 +181,17 @@ impl Memcard {
         matrix
     }
     fn build_fat_matrix(&mut self) {
-        let indirect_fat_matrix = self.build_matrix(self.ifc_list.to_vec());
-        let indirect_fat_matrix = Self::flatten_matrix(indirect_fat_matrix);
-
-        let indirect_fat_matrix = indirect_fat_matrix
-            .iter()
-            .filter(|f| **f != 0xFFFFFFFF)
-            .cloned()
-            .collect();
-
-        self.fat_matrix = self.build_matrix(indirect_fat_matrix);
+        // Only the clusters it takes to cover the allocatable area are listed, the rest of
+        // the indirect FAT is padding
+        let fat_clusters = self.alloc_end.div_ceil(self.fat_per_cluster);
+        let indirect_clusters = fat_clusters.div_ceil(self.fat_per_cluster);
+        let ifc_list = self.ifc_list[..indirect_clusters.min(32)].to_vec();
+        let indirect_fat_matrix = self.build_matrix(ifc_list);
+        let mut indirect_fat_matrix = Self::flatten_matrix(indirect_fat_matrix);
+        indirect_fat_matrix.truncate(fat_clusters);
+
+        self.fat_clusters = indirect_fat_matrix;
+        self.fat_matrix = self.build_matrix(self.fat_clusters.clone());
     }
 
     fn flatten_matrix(matrix: Vec<Vec<u32>>) -> Vec<u32> {
@@ -163,12 +211,39 @@ impl Memcard {
     fn read_page(&mut self, n: u32) -> Vec<u8> {
         let offset = self.raw_page_size * n as usize;
         self.c.set_position(offset as u64);
+        // A page past the end of a truncated dump reads as zeros
         let mut buffer = vec![0u8; self.page_size];
-        self.c.read(&mut buffer).unwrap();
+        let _ = self.c.read(&mut buffer).unwrap();
+
+        // Erased pages have a blank spare area, and some dumps leave it out entirely
+        let mut spare = vec![0u8; self.spare_size];
+        let spare_len = self.c.read(&mut spare).unwrap();
+        let ecc = page_ecc(&buffer);
+        if spare_len >= ecc.len() && spare.iter().any(|&b| b != 0xFF) && spare[..ecc.len()] != ecc {
+            self.ecc_errors.insert(n);
+        }
 
         buffer
     }
 
+    fn write_cluster(&mut self, n: u32, data: &[u8]) {
+        let mut buffer = data.to_vec();
+        buffer.resize(self.cluster_size, 0);
+
+        let page_index = n as usize * self.pages_per_cluster;
+        for (i, page) in buffer.chunks(self.page_size).enumerate() {
+            self.write_page((page_index + i) as u32, page);
+        }
+    }
+
+    fn write_page(&mut self, n: u32, data: &[u8]) {
+        let offset = self.raw_page_size * n as usize;
+        self.c.set_position(offset as u64);
+        self.c
+            .write_all(&raw_page(data, self.spare_size))
+            .expect("Failed to write page");
+    }
+
     pub fn read_entry_cluster(&mut self, cluster_offset: u32) -> Vec<DirEntry> {
         let buffer = self.read_cluster((cluster_offset as usize + self.alloc_offset) as u32);
 
@@ -221,9 +296,265 @@ impl Memcard {
         sub_entries
     }
 
+    /// Row of the FAT matrix, that is which FAT cluster, and the column within it holding the
+    /// entry for cluster `n`
+    fn fat_index(&self, n: u32) -> (usize, usize) {
+        let n = n as usize;
+        (n / self.fat_per_cluster, n % self.fat_per_cluster)
+    }
+
+    fn raw_fat_value(&self, n: u32) -> u32 {
+        let (row, column) = self.fat_index(n);
+        self.fat_matrix[row][column]
+    }
+
+    fn set_fat_value(&mut self, n: u32, value: u32, dirty_rows: &mut BTreeSet<usize>) {
+        let (row, column) = self.fat_index(n);
+        self.fat_matrix[row][column] = value;
+        dirty_rows.insert(row);
+    }
+
+    /// Marks the clusters allocated, each pointing at the next
+    fn link_chain(&mut self, chain: &[u32], dirty_rows: &mut BTreeSet<usize>) {
+        for (i, &cluster) in chain.iter().enumerate() {
+            let next = chain.get(i + 1).copied().unwrap_or(CHAIN_END);
+            self.set_fat_value(cluster, ALLOCATED | next, dirty_rows);
+        }
+    }
+
+    /// Follows a FAT chain, clusters are relative to the start of the allocatable area
+    fn chain(&self, start: u32) -> io::Result<Vec<u32>> {
+        let mut chain = vec![];
+        let mut cluster = start;
+        while cluster != CHAIN_END {
+            if cluster as usize >= self.alloc_end {
+                return Err(invalid("Cluster chain points outside the memory card"));
+            }
+            if chain.len() >= self.alloc_end {
+                return Err(invalid("Memory card contains a looping cluster chain"));
+            }
+            chain.push(cluster);
+            cluster = self.get_fat_value(cluster);
+        }
+
+        Ok(chain)
+    }
+
+    /// A directory's entries, the length comes from its "." entry when it isn't known
+    fn read_directory(&mut self, start: u32, length: Option<u32>) -> io::Result<Vec<DirEntry>> {
+        let mut entries = vec![];
+        for cluster in self.chain(start)? {
+            entries.extend(self.read_entry_cluster(cluster));
+        }
+
+        let length = length.or(entries.first().map(|entry| entry.length));
+        entries.truncate(length.unwrap_or_default() as usize);
+
+        Ok(entries)
+    }
+
+    fn read_file(&mut self, entry: &DirEntry) -> io::Result<Vec<u8>> {
+        let length = entry.length as usize;
+        // Empty files have no clusters
+        if length == 0 {
+            return Ok(vec![]);
+        }
+
+        let mut contents = vec![];
+        for cluster in self.chain(entry.cluster)? {
+            contents.extend(self.read_cluster(cluster + self.alloc_offset as u32));
+            if contents.len() >= length {
+                break;
+            }
+        }
+        if contents.len() < length {
+            return Err(invalid("File is longer than its cluster chain"));
+        }
+        contents.truncate(length);
+
+        Ok(contents)
+    }
+
+    /// Every save directory in the root of the card. Fails on a FAT chain that leaves the
+    /// card or loops, rather than reading garbage.
+    pub fn saves(&mut self) -> io::Result<Vec<Save>> {
+        let root = self.read_directory(self.rootdir_cluster as u32, None)?;
+
+        let mut saves = vec![];
+        for entry in root {
+            if !entry.exists() || !entry.is_directory() || entry.name[0] == b'.' {
+                continue;
+            }
+
+            let mut files = vec![];
+            for file in self.read_directory(entry.cluster, Some(entry.length))? {
+                if !file.exists() || !file.is_file() {
+                    continue;
+                }
+                files.push(PSUEntry {
+                    id: FILE_ID,
+                    size: file.length,
+                    created: file.created.to_naive(),
+                    sector: 0,
+                    modified: file.modified.to_naive(),
+                    contents: Some(self.read_file(&file)?),
+                    name: file.name_as_string(),
+                    kind: PSUEntryKind::File,
+                });
+            }
+
+            saves.push(Save {
+                name: entry.name_as_string(),
+                created: entry.created.to_naive(),
+                modified: entry.modified.to_naive(),
+                files,
+            });
+        }
+
+        Ok(saves)
+    }
+
+    /// Overwrites the `index`th entry of the directory stored in `chain`
+    fn write_entry(&mut self, chain: &[u32], index: usize, entry: &DirEntry) {
+        let entries_per_cluster = self.cluster_size / ENTRY_SIZE;
+        let cluster = chain[index / entries_per_cluster] + self.alloc_offset as u32;
+        let offset = index % entries_per_cluster * ENTRY_SIZE;
+
+        let mut buffer = self.read_cluster(cluster);
+        buffer[offset..offset + ENTRY_SIZE].copy_from_slice(&entry.to_bytes());
+        self.write_cluster(cluster, &buffer);
+    }
+
+    /// Adds a save directory holding `files` to the root of the card, allocating its clusters
+    /// and updating the FAT. Fails before anything is written if a name doesn't fit, the save
+    /// already exists or the card doesn't have enough free clusters.
+    pub fn write_save(&mut self, dir_name: &str, files: &[(String, Vec<u8>)]) -> io::Result<()> {
+        for name in std::iter::once(dir_name).chain(files.iter().map(|(name, _)| name.as_str())) {
+            if name.is_empty() || name.len() > MAX_NAME_LENGTH || name.contains('\0') {
+                return Err(io::Error::new(
+                    ErrorKind::InvalidInput,
+                    format!("{name:?} isn't a valid name, it must be 1 to {MAX_NAME_LENGTH} bytes"),
+                ));
+            }
+        }
+
+        let root_chain = self.chain(self.rootdir_cluster as u32)?;
+        let mut root = self.read_entry_cluster(root_chain[0])[0];
+        let root_entries = root_chain
+            .iter()
+            .flat_map(|&cluster| self.read_entry_cluster(cluster))
+            .take(root.length as usize)
+            .collect::<Vec<_>>();
+        if root_entries.iter().any(|entry| {
+            !entry.is_empty() && !entry.is_deleted() && entry.name_as_string() == dir_name
+        }) {
+            return Err(io::Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{dir_name} is already on the card"),
+            ));
+        }
+
+        // "." counts the root's entries, a full last cluster means the root has to grow
+        let entries_per_cluster = self.cluster_size / ENTRY_SIZE;
+        let root_index = root.length as usize;
+        let grow_root = root_index >= root_chain.len() * entries_per_cluster;
+        let dir_clusters = (files.len() + 2).div_ceil(entries_per_cluster);
+        let file_clusters = files
+            .iter()
+            .map(|(_, data)| data.len().div_ceil(self.cluster_size))
+            .collect::<Vec<_>>();
+        let needed = dir_clusters + file_clusters.iter().sum::<usize>() + grow_root as usize;
+
+        let free = (0..self.alloc_end as u32)
+            .filter(|&n| self.raw_fat_value(n) & ALLOCATED == 0)
+            .take(needed)
+            .collect::<Vec<_>>();
+        if free.len() < needed {
+            return Err(io::Error::new(
+                ErrorKind::StorageFull,
+                format!(
+                    "{dir_name} needs {needed} clusters but only {} are free",
+                    free.len()
+                ),
+            ));
+        }
+
+        let mut free = free.into_iter();
+        let mut dirty_rows = BTreeSet::new();
+        let now = DateTime::from_naive(Local::now().naive_local());
+
+        let mut entries = vec![];
+        for ((name, data), count) in files.iter().zip(file_clusters) {
+            let chain = free.by_ref().take(count).collect::<Vec<_>>();
+            self.link_chain(&chain, &mut dirty_rows);
+            for (&cluster, chunk) in chain.iter().zip(data.chunks(self.cluster_size)) {
+                self.write_cluster(cluster + self.alloc_offset as u32, chunk);
+            }
+            // Empty files have no clusters
+            let first = chain.first().copied().unwrap_or(CHAIN_END);
+            let length = data.len() as u32;
+            entries.push(DirEntry::new(FILE_MODE, length, first, 0, name, now));
+        }
+
+        let dir_chain = free.by_ref().take(dir_clusters).collect::<Vec<_>>();
+        self.link_chain(&dir_chain, &mut dirty_rows);
+        // "." points back at the save's own entry in the root
+        let dot = DirEntry::new(
+            DIRECTORY_MODE,
+            0,
+            self.rootdir_cluster as u32,
+            root_index as u32,
+            ".",
+            now,
+        );
+        let dot_dot = DirEntry::new(PARENT_MODE, 0, 0, 0, "..", now);
+        entries.splice(0..0, [dot, dot_dot]);
+        for (&cluster, chunk) in dir_chain.iter().zip(entries.chunks(entries_per_cluster)) {
+            let bytes = chunk
+                .iter()
+                .flat_map(|entry| entry.to_bytes())
+                .collect::<Vec<_>>();
+            self.write_cluster(cluster + self.alloc_offset as u32, &bytes);
+        }
+
+        let mut root_chain = root_chain;
+        if grow_root {
+            let cluster = free.next().expect("Counted with the other clusters");
+            root_chain.push(cluster);
+            self.link_chain(&root_chain, &mut dirty_rows);
+            self.write_cluster(cluster + self.alloc_offset as u32, &[]);
+        }
+        let save = DirEntry::new(
+            DIRECTORY_MODE,
+            entries.len() as u32,
+            dir_chain[0],
+            0,
+            dir_name,
+            now,
+        );
+        self.write_entry(&root_chain, root_index, &save);
+        root.length += 1;
+        self.write_entry(&root_chain, 0, &root);
+
+        for row in dirty_rows {
+            let bytes = self.fat_matrix[row]
+                .iter()
+                .flat_map(|value| value.to_le_bytes())
+                .collect::<Vec<_>>();
+            self.write_cluster(self.fat_clusters[row], &bytes);
+        }
+        self.entries_in_root.clear();
+
+        Ok(())
+    }
+
+    /// The card image with everything written so far
+    pub fn into_bytes(self) -> Vec<u8> {
+        self.c.into_inner()
+    }
+
     fn get_fat_value(&self, n: u32) -> u32 {
-        let value = self.fat_matrix[(n as usize / self.fat_per_cluster) % self.fat_per_cluster]
-            [n as usize % self.fat_per_cluster];
+        let value = self.raw_fat_value(n);
 
         if value & 0x80000000 > 0 {
             value ^ 0x80000000
@@ -232,6 +563,46 @@ impl Memcard {
         }
     }
 
+    /// Pages read so far whose data doesn't match the ECC in their spare area. The data is
+    /// still returned as stored, it's up to the caller to decide whether to trust it.
+    pub fn ecc_errors(&self) -> &BTreeSet<u32> {
+        &self.ecc_errors
+    }
+
+    /// Clusters files can be allocated in, the ones before `alloc_offset` hold the FAT itself
+    pub fn total_clusters(&self) -> usize {
+        self.alloc_end
+    }
+
+    /// Counts the FAT entries without the allocated bit set
+    pub fn free_clusters(&self) -> usize {
+        (0..self.alloc_end as u32)
+            .filter(|&n| self.raw_fat_value(n) & 0x80000000 == 0)
+            .count()
+    }
+
+    pub fn used_clusters(&self) -> usize {
+        self.total_clusters() - self.free_clusters()
+    }
+
+    pub fn free_bytes(&self) -> usize {
+        self.free_clusters() * self.cluster_size
+    }
+
+    pub fn total_bytes(&self) -> usize {
+        self.total_clusters() * self.cluster_size
+    }
+
+    pub fn print_space_summary(&self) {
+        println!(
+            "{} KB free of {} KB ({} of {} clusters used)",
+            self.free_bytes() / 1024,
+            self.total_bytes() / 1024,
+            self.used_clusters(),
+            self.total_clusters()
+        );
+    }
+
     pub fn print_allocation_table_recursive(&mut self) {
         // Load root entries if not yet loaded
         if self.entries_in_root.is_empty() {
@@ -277,3 +648,293 @@ impl Memcard {
     }
 
 }
+
+/// A blank card laid out the way the PS2 browser formats one: the superblock in the first
+/// erase block, then the indirect FAT, the FAT, the allocatable clusters and two erase blocks
+/// kept for backups. `clusters_per_card` has to be a whole number of erase blocks.
+pub fn format(clusters_per_card: u32) -> Vec<u8> {
+    const PAGE_SIZE: usize = 512;
+    const PAGES_PER_CLUSTER: usize = 2;
+    const PAGES_PER_BLOCK: usize = 16;
+    let cluster_size = PAGE_SIZE * PAGES_PER_CLUSTER;
+    let spare_size = PAGE_SIZE / ECC_CHUNK_SIZE * 4;
+    let clusters_per_block = (PAGES_PER_BLOCK / PAGES_PER_CLUSTER) as u32;
+    let blocks = clusters_per_card / clusters_per_block;
+
+    let ifc_cluster = clusters_per_block;
+    let fat_per_cluster = (cluster_size / 4) as u32;
+    let fat_clusters = clusters_per_card.div_ceil(fat_per_cluster);
+    let alloc_offset = ifc_cluster + 1 + fat_clusters;
+    let alloc_end = clusters_per_card - alloc_offset - 2 * clusters_per_block;
+
+    let mut superblock = vec![];
+    superblock.extend_from_slice(b"Sony PS2 Memory Card Format 1.2.0.0\0\0\0\0\0");
+    for value in [PAGE_SIZE, PAGES_PER_CLUSTER, PAGES_PER_BLOCK, 0xFF00] {
+        superblock.extend((value as u16).to_le_bytes());
+    }
+    let fields = [
+        clusters_per_card,
+        alloc_offset,
+        alloc_end,
+        0,
+        blocks - 1,
+        blocks - 2,
+        0,
+        0,
+    ];
+    let ifc_list = std::iter::once(ifc_cluster).chain([0; 31]);
+    let bad_block_list = [u32::MAX; 32];
+    for value in fields.into_iter().chain(ifc_list).chain(bad_block_list) {
+        superblock.extend(value.to_le_bytes());
+    }
+    // Card type and flags
+    superblock.extend([2, 0x52]);
+
+    let fat_cluster_list = (ifc_cluster + 1..alloc_offset).collect::<Vec<_>>();
+    let fat = (0..fat_clusters * fat_per_cluster)
+        .map(|n| match n {
+            // The root directory
+            0 => ALLOCATED | CHAIN_END,
+            n if n < alloc_end => CHAIN_END,
+            _ => u32::MAX,
+        })
+        .collect::<Vec<_>>();
+
+    let now = DateTime::from_naive(Local::now().naive_local());
+    let root = [
+        DirEntry::new(DIRECTORY_MODE, 2, 0, 0, ".", now),
+        DirEntry::new(PARENT_MODE, 0, 0, 0, "..", now),
+    ];
+
+    // Erased flash reads back as all ones, spare area included
+    let raw_page_size = PAGE_SIZE + spare_size;
+    let mut card = vec![0xFF; clusters_per_card as usize * PAGES_PER_CLUSTER * raw_page_size];
+    let mut write_cluster = |cluster: u32, mut data: Vec<u8>| {
+        data.resize(cluster_size, 0xFF);
+        for (i, page) in data.chunks(PAGE_SIZE).enumerate() {
+            let offset = (cluster as usize * PAGES_PER_CLUSTER + i) * raw_page_size;
+            card[offset..offset + raw_page_size].copy_from_slice(&raw_page(page, spare_size));
+        }
+    };
+    write_cluster(0, superblock);
+    write_cluster(ifc_cluster, to_bytes(&fat_cluster_list));
+    for (&cluster, values) in fat_cluster_list
+        .iter()
+        .zip(fat.chunks(fat_per_cluster as usize))
+    {
+        write_cluster(cluster, to_bytes(values));
+    }
+    write_cluster(
+        alloc_offset,
+        root.iter().flat_map(|entry| entry.to_bytes()).collect(),
+    );
+
+    card
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(ErrorKind::InvalidData, message)
+}
+
+fn to_bytes(values: &[u32]) -> Vec<u8> {
+    values
+        .iter()
+        .flat_map(|value| value.to_le_bytes())
+        .collect()
+}
+
+/// A page followed by its spare area, which holds the ECC
+fn raw_page(page: &[u8], spare_size: usize) -> Vec<u8> {
+    let mut raw = page.to_vec();
+    raw.extend(page_ecc(page));
+    raw.resize(page.len() + spare_size, 0);
+
+    raw
+}
+
+/// The ECC for every chunk of a page, in the order it's stored in the spare area
+fn page_ecc(page: &[u8]) -> Vec<u8> {
+    page.chunks(ECC_CHUNK_SIZE).flat_map(chunk_ecc).collect()
+}
+
+/// Hamming code over 128 bytes: column parity first, then the parity of the line numbers
+/// of bytes with odd parity, once inverted and once as is
+fn chunk_ecc(chunk: &[u8]) -> [u8; 3] {
+    let mut column_parity = 0x77;
+    let mut line_parity_0 = 0x7F;
+    let mut line_parity_1 = 0x7F;
+    for (i, &b) in chunk.iter().enumerate() {
+        column_parity ^= column_parity_mask(b);
+        if b.count_ones() % 2 == 1 {
+            line_parity_0 ^= !(i as u8);
+            line_parity_1 ^= i as u8;
+        }
+    }
+
+    [column_parity, line_parity_0 & 0x7F, line_parity_1]
+}
+
+/// Which of the six column groups of a byte have odd parity. Bit 3 is unused and always 0,
+/// which is what the 0x77 seed in `chunk_ecc` expects.
+fn column_parity_mask(b: u8) -> u8 {
+    [0x55, 0x33, 0x0F, 0x00, 0xAA, 0xCC, 0xF0]
+        .iter()
+        .enumerate()
+        .fold(0, |mask, (bit, &columns)| {
+            mask | (((b & columns).count_ones() as u8 & 1) << bit)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(files: &[(&str, usize)]) -> Vec<(String, Vec<u8>)> {
+        files
+            .iter()
+            .map(|&(name, size)| (name.to_string(), (0..size).map(|i| i as u8).collect()))
+            .collect()
+    }
+
+    fn reread(card: Memcard) -> Vec<Save> {
+        Memcard::new(card.into_bytes()).unwrap().saves().unwrap()
+    }
+
+    #[test]
+    fn written_save_reads_back() {
+        let mut card = Memcard::new(format(64)).unwrap();
+        let save = files(&[("BOOT.ELF", 3000), ("icon.sys", 964), ("empty", 0)]);
+
+        card.write_save("SAVE", &save).unwrap();
+
+        // Every page written has to pass its ECC check when it's read again
+        let mut reopened = Memcard::new(card.into_bytes()).unwrap();
+        let saves = reopened.saves().unwrap();
+        assert!(reopened.ecc_errors().is_empty());
+        assert_eq!(saves.len(), 1);
+        assert_eq!(saves[0].name, "SAVE");
+        let read = saves[0]
+            .files
+            .iter()
+            .map(|file| (file.name.clone(), file.contents.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(read, save);
+    }
+
+    #[test]
+    fn root_grows_past_its_first_cluster() {
+        let mut card = Memcard::new(format(64)).unwrap();
+        let entries_per_cluster = card.cluster_size / ENTRY_SIZE;
+
+        for name in ["FIRST", "SECOND", "THIRD"] {
+            card.write_save(name, &files(&[("DATA", 100)])).unwrap();
+        }
+
+        // "." and ".." plus the three saves
+        let root_entries: usize = 2 + 3;
+        assert_eq!(
+            card.chain(card.rootdir_cluster as u32).unwrap().len(),
+            root_entries.div_ceil(entries_per_cluster)
+        );
+        let names = reread(card)
+            .into_iter()
+            .map(|save| save.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, ["FIRST", "SECOND", "THIRD"]);
+    }
+
+    #[test]
+    fn full_card_is_left_untouched() {
+        let blank = format(64);
+        let mut card = Memcard::new(blank.clone()).unwrap();
+        let free = card.free_bytes();
+
+        let err = card
+            .write_save("BIG", &files(&[("DATA", free + 1)]))
+            .unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::StorageFull);
+        assert_eq!(card.into_bytes(), blank);
+    }
+
+    #[test]
+    fn save_that_exists_is_not_written_twice() {
+        let mut card = Memcard::new(format(64)).unwrap();
+        card.write_save("SAVE", &files(&[("DATA", 10)])).unwrap();
+
+        let err = card
+            .write_save("SAVE", &files(&[("OTHER", 10)]))
+            .unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(reread(card).len(), 1);
+    }
+
+    #[test]
+    fn free_clusters_count_what_saves_take() {
+        let mut card = Memcard::new(format(64)).unwrap();
+        // 64 clusters less the superblock's erase block, the FATs and two backup blocks,
+        // the root directory takes the first
+        assert_eq!(card.total_clusters(), 38);
+        assert_eq!(card.free_clusters(), 37);
+
+        // Three data clusters, two for the save's four entries and one more for the root
+        let save = files(&[("DATA", 3000), ("empty", 0)]);
+        card.write_save("SAVE", &save).unwrap();
+
+        assert_eq!(card.free_clusters(), 31);
+        assert_eq!(card.used_clusters(), 7);
+        assert_eq!(card.free_bytes(), 31 * 1024);
+        let reopened = Memcard::new(card.into_bytes()).unwrap();
+        assert_eq!(reopened.free_clusters(), 31);
+    }
+
+    #[test]
+    fn dump_without_ecc_reads_the_same() {
+        let mut card = Memcard::new(format(64)).unwrap();
+        let save = files(&[("DATA", 3000)]);
+        card.write_save("SAVE", &save).unwrap();
+        let raw_page_size = card.raw_page_size;
+        let page_size = card.page_size;
+        let plain = card
+            .into_bytes()
+            .chunks(raw_page_size)
+            .flat_map(|page| page[..page_size].to_vec())
+            .collect::<Vec<_>>();
+
+        let saves = Memcard::new(plain).unwrap().saves().unwrap();
+
+        assert_eq!(saves[0].files[0].contents.as_ref(), Some(&save[0].1));
+    }
+
+    #[test]
+    fn looping_chain_is_an_error() {
+        let mut card = Memcard::new(format(64)).unwrap();
+        card.write_save("SAVE", &files(&[("DATA", 10)])).unwrap();
+        let root = card.rootdir_cluster as u32;
+        card.set_fat_value(root, ALLOCATED | root, &mut BTreeSet::new());
+
+        let err = card.saves().unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+        assert!(matches!(
+            Memcard::new(b"not a card".to_vec()),
+            Err(err) if err.kind() == ErrorKind::InvalidData
+        ));
+    }
+
+    // Reference values from mymc's ps2mc_ecc.py
+    #[test]
+    fn chunk_ecc_matches_reference() {
+        assert_eq!(chunk_ecc(&[0; ECC_CHUNK_SIZE]), [0x77, 0x7F, 0x7F]);
+
+        let mut single_bit = [0; ECC_CHUNK_SIZE];
+        single_bit[5] = 0x80;
+        assert_eq!(chunk_ecc(&single_bit), [0x07, 0x05, 0x7A]);
+
+        let pattern = (0..ECC_CHUNK_SIZE)
+            .map(|i| (i * 37 + 11) as u8)
+            .collect::<Vec<_>>();
+        assert_eq!(chunk_ecc(&pattern), [0x07, 0x5E, 0x21]);
+    }
+}
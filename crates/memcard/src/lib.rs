@@ -0,0 +1,3 @@
+pub mod dir_entry;
+pub mod fat;
+pub mod save;
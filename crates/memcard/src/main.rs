@@ -1,10 +1,32 @@
-mod dir_entry;
-mod fat;
+use memcard::fat;
+use std::path::PathBuf;
 
 fn main() -> std::io::Result<()> {
-    let data = std::fs::read("../NewCard.ps2").expect("cannot read file");
+    let mut args = std::env::args().skip(1);
+    let card = args.next().unwrap_or("../NewCard.ps2".to_string());
+    let data = std::fs::read(&card).expect("cannot read file");
 
-    let mut mc = fat::Memcard::new(data);
+    let mut mc = fat::Memcard::new(data)?;
+
+    // A save folder after the card gets copied onto it
+    if let Some(folder) = args.next().map(PathBuf::from) {
+        let mut files = vec![];
+        for entry in std::fs::read_dir(&folder)? {
+            let path = entry?.path();
+            if path.is_file() {
+                let name = path.file_name().unwrap().to_string_lossy().to_string();
+                files.push((name, std::fs::read(&path)?));
+            }
+        }
+        files.sort();
+
+        let name = folder.file_name().unwrap().to_string_lossy();
+        mc.write_save(&name, &files)?;
+        print_ecc_errors(&mc);
+        mc.print_space_summary();
+        std::fs::write(&card, mc.into_bytes())?;
+        return Ok(());
+    }
 
     let folders = mc.read_entry_cluster(mc.rootdir_cluster as u32);
     let root = folders[0];
@@ -12,6 +34,8 @@ fn main() -> std::io::Result<()> {
     eprintln!("{:#?}", root);
 
     mc.print_allocation_table_recursive();
+    print_ecc_errors(&mc);
+    mc.print_space_summary();
     // let folders = mc.find_sub_entries(&root);
 
     // for folder in folders {
@@ -36,3 +60,9 @@ fn main() -> std::io::Result<()> {
     // }
     Ok(())
 }
+
+fn print_ecc_errors(mc: &fat::Memcard) {
+    for page in mc.ecc_errors() {
+        eprintln!("Warning: page {page} fails its ECC check, its data may be corrupted");
+    }
+}
@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use ps2_filetypes::{PSUEntry, PSUEntryKind, DIR_ID, PSU};
+
+/// A save directory in the root of a card, with its files read in full
+#[derive(Debug, Clone)]
+pub struct Save {
+    pub name: String,
+    pub created: NaiveDateTime,
+    pub modified: NaiveDateTime,
+    pub files: Vec<PSUEntry>,
+}
+
+impl Save {
+    pub fn size(&self) -> u64 {
+        self.files.iter().map(|file| file.size as u64).sum()
+    }
+
+    /// Lays the save out the same way psu-packer does, so it can be written with `PSUWriter`
+    pub fn to_psu(&self) -> PSU {
+        let directory = |name: &str, size: u32| PSUEntry {
+            id: DIR_ID,
+            size,
+            created: self.created,
+            sector: 0,
+            modified: self.modified,
+            name: name.to_string(),
+            kind: PSUEntryKind::Directory,
+            contents: None,
+        };
+
+        let mut entries = vec![
+            directory(&self.name, self.files.len() as u32 + 2),
+            directory(".", 0),
+            directory("..", 0),
+        ];
+        entries.extend(self.files.iter().cloned());
+
+        PSU { entries }
+    }
+}
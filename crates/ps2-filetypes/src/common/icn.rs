@@ -64,6 +64,35 @@ pub struct Frame {
     pub keys: Vec<Key>,
 }
 
+impl Frame {
+    /// Linearly interpolates the weight of this frame's shape at time `t`
+    pub fn evaluate(&self, t: f32) -> f32 {
+        let (Some(first), Some(last)) = (self.keys.first(), self.keys.last()) else {
+            return 0.0;
+        };
+        if t <= first.time {
+            return first.value;
+        }
+        if t >= last.time {
+            return last.value;
+        }
+
+        for pair in self.keys.windows(2) {
+            let (k0, k1) = (pair[0], pair[1]);
+            if k0.time <= t && t < k1.time {
+                let dt = k1.time - k0.time;
+                if dt == 0.0 {
+                    return k0.value;
+                }
+                let alpha = (t - k0.time) / dt;
+                return (1.0 - alpha) * k0.value + alpha * k1.value;
+            }
+        }
+
+        last.value
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub struct AnimationHeader {
     pub tag: u32,
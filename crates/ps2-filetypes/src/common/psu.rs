@@ -1,8 +1,25 @@
 use std::io::Cursor;
 
+/// Entry ids are the memory card's mode bits, these are the values official saves use
 pub const DIR_ID: u16 = 0x8427;
 pub const FILE_ID: u16 = 0x8497;
 
+pub const MODE_READ: u16 = 0x0001;
+pub const MODE_WRITE: u16 = 0x0002;
+pub const MODE_EXECUTE: u16 = 0x0004;
+/// The browser refuses to copy the entry
+pub const MODE_COPY_PROTECTED: u16 = 0x0008;
+pub const MODE_FILE: u16 = 0x0010;
+pub const MODE_DIRECTORY: u16 = 0x0020;
+/// Set once a file has been closed after writing, half-written files lack it
+pub const MODE_CLOSED: u16 = 0x0080;
+/// Marks a PS2 entry, as opposed to a PocketStation or PS1 one
+pub const MODE_PS2: u16 = 0x0400;
+pub const MODE_PS1: u16 = 0x1000;
+/// Hidden from the browser
+pub const MODE_HIDDEN: u16 = 0x2000;
+pub const MODE_EXISTS: u16 = 0x8000;
+
 pub const PAGE_SIZE: u32 = 0x400;
 
 
@@ -30,7 +47,7 @@ pub struct PSUEntry {
     pub contents: Option<Vec<u8>>,
 }
 
-pub(crate) struct PSUParser {
-    pub(crate) c: Cursor<Vec<u8>>,
+pub struct PSUParser<'a> {
+    pub(crate) c: Cursor<&'a [u8]>,
     pub(crate) len: u64,
 }
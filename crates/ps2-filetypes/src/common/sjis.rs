@@ -23,43 +23,175 @@ pub fn decode_sjis(input: &[u8]) -> String {
     let mut str_out = vec![0u8; input.len()];
 
     for (i, pair) in input.chunks_exact(2).enumerate() {
-        str_out[i] = match pair[0] {
-            0x00 => {
-                if pair[1] == 0x00 {
-                    b'\0'
-                } else {
-                    b'?'
-                }
-            }
-            0x0D => {
-                if pair[1] == 0x0A {
-                    // TODO: Technically this should be \r\n, but for now it works
-                    b'\n'
-                } else {
-                    b'?'
-                }
-            }
-            0x81 => match pair[1] {
-                0x40 => b' ',
-                0x46 => b':',
-                0x5E => b'/',
-                0x69 => b'(',
-                0x6A => b')',
-                0x6D => b'[',
-                0x6E => b']',
-                0x6F => b'{',
-                0x70 => b'}',
-                _ => b'?',
-            },
-            0x82 => match pair[1] {
-                0x4f..=0x7A => pair[1] - 31,
-                0x81..=0x99 => pair[1] - 32,
-                0x3F => b' ',
-                _ => b'?',
-            },
-            _ => b'?',
-        };
+        str_out[i] = decode_pair(pair).unwrap_or(b'?');
     }
 
     String::from_utf8_lossy(&str_out).to_string()
 }
+
+/// Whether every pair before the terminator is one `decode_sjis` knows, rather than one it
+/// falls back to `?` for
+pub fn decodes_cleanly(input: &[u8]) -> bool {
+    input
+        .chunks_exact(2)
+        .take_while(|pair| *pair != [0, 0])
+        .all(|pair| decode_pair(pair).is_some())
+}
+
+fn decode_pair(pair: &[u8]) -> Option<u8> {
+    Some(match pair[0] {
+        0x00 if pair[1] == 0x00 => b'\0',
+        // TODO: Technically this should be \r\n, but for now it works
+        0x0D if pair[1] == 0x0A => b'\n',
+        // The space `encode_sjis` writes
+        0x80 if pair[1] == 0x3F => b' ',
+        0x81 => match pair[1] {
+            0x40 => b' ',
+            0x46 => b':',
+            0x5E => b'/',
+            0x69 => b'(',
+            0x6A => b')',
+            0x6D => b'[',
+            0x6E => b']',
+            0x6F => b'{',
+            0x70 => b'}',
+            _ => return None,
+        },
+        0x82 => match pair[1] {
+            0x4f..=0x7A => pair[1] - 31,
+            0x81..=0x99 => pair[1] - 32,
+            0x3F => b' ',
+            _ => return None,
+        },
+        _ => return None,
+    })
+}
+
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TextEncoding {
+    Utf8,
+    Utf8Bom,
+    ShiftJis,
+}
+
+impl std::fmt::Display for TextEncoding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TextEncoding::Utf8 => write!(f, "UTF-8"),
+            TextEncoding::Utf8Bom => write!(f, "UTF-8 with BOM"),
+            TextEncoding::ShiftJis => write!(f, "Shift-JIS"),
+        }
+    }
+}
+
+/// Decodes a text file, stripping a UTF-8 BOM and falling back to Shift-JIS when the contents
+/// aren't valid UTF-8. Returns None if neither encoding fits.
+pub fn decode_text(bytes: &[u8]) -> Option<(String, TextEncoding)> {
+    if let Some(stripped) = bytes.strip_prefix(UTF8_BOM) {
+        return String::from_utf8(stripped.to_vec())
+            .ok()
+            .map(|text| (text, TextEncoding::Utf8Bom));
+    }
+
+    if let Ok(text) = std::str::from_utf8(bytes) {
+        return Some((text.to_string(), TextEncoding::Utf8));
+    }
+
+    let (text, had_errors) = encoding_rs::SHIFT_JIS.decode_without_bom_handling(bytes);
+    (!had_errors).then(|| (text.into_owned(), TextEncoding::ShiftJis))
+}
+
+/// Encodes text back into the encoding it was loaded with. Returns None if the text contains
+/// characters that can't be represented in Shift-JIS.
+pub fn encode_text(text: &str, encoding: TextEncoding) -> Option<Vec<u8>> {
+    match encoding {
+        TextEncoding::Utf8 => Some(text.as_bytes().to_vec()),
+        TextEncoding::Utf8Bom => Some([UTF8_BOM, text.as_bytes()].concat()),
+        TextEncoding::ShiftJis => {
+            let (bytes, _, had_errors) = encoding_rs::SHIFT_JIS.encode(text);
+            (!had_errors).then(|| bytes.into_owned())
+        }
+    }
+}
+
+/// How lines in a text file end. Editors work in LF, files are written back with this.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    /// The ending of the first line, files that don't have one count as LF
+    pub fn detect(text: &str) -> Self {
+        match text.find('\n') {
+            Some(index) if text[..index].ends_with('\r') => LineEnding::CrLf,
+            _ => LineEnding::Lf,
+        }
+    }
+
+    /// Rewrites every line ending in `text` as this one
+    pub fn apply(self, text: &str) -> String {
+        let text = text.replace("\r\n", "\n");
+        match self {
+            LineEnding::Lf => text,
+            LineEnding::CrLf => text.replace('\n', "\r\n"),
+        }
+    }
+}
+
+impl std::fmt::Display for LineEnding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LineEnding::Lf => write!(f, "LF"),
+            LineEnding::CrLf => write!(f, "CRLF"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SJIS_TITLE: &[u8] = b"title=\x83\x68\x83\x89\x83\x53\x83\x93";
+
+    #[test]
+    fn bom_is_stripped_and_restored() {
+        let bytes = [UTF8_BOM, "title=ドラゴン".as_bytes()].concat();
+
+        let (text, encoding) = decode_text(&bytes).unwrap();
+
+        assert_eq!(text, "title=ドラゴン");
+        assert_eq!(encoding, TextEncoding::Utf8Bom);
+        assert_eq!(encode_text(&text, encoding).unwrap(), bytes);
+    }
+
+    #[test]
+    fn text_that_is_not_utf8_is_read_as_shift_jis() {
+        assert_eq!(
+            decode_text(b"title=Save"),
+            Some(("title=Save".to_string(), TextEncoding::Utf8))
+        );
+
+        let (text, encoding) = decode_text(SJIS_TITLE).unwrap();
+
+        assert_eq!(text, "title=ドラゴン");
+        assert_eq!(encoding, TextEncoding::ShiftJis);
+        assert_eq!(encode_text(&text, encoding).unwrap(), SJIS_TITLE);
+        assert_eq!(encode_text("title=😀", TextEncoding::ShiftJis), None);
+    }
+
+    #[test]
+    fn line_endings_are_detected_from_the_first_line() {
+        assert_eq!(LineEnding::detect("a\r\nb\n"), LineEnding::CrLf);
+        assert_eq!(LineEnding::detect("a\nb\r\n"), LineEnding::Lf);
+        assert_eq!(LineEnding::detect("title=Save"), LineEnding::Lf);
+
+        let text = "title=Save\r\nboot=BOOT.ELF\r\n";
+        let lf = LineEnding::Lf.apply(text);
+        assert_eq!(lf, "title=Save\nboot=BOOT.ELF\n");
+        assert_eq!(LineEnding::CrLf.apply(&lf), text);
+    }
+}
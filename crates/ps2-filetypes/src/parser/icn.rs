@@ -1,14 +1,129 @@
 use crate::color::Color;
 use crate::{
     AnimationHeader, AnimationShape, BinReader, Frame, ICNHeader, IcnTexture, Key, Normal, Vertex,
-    ICN, ICN_MAGIC, TEXTURE_SIZE, UV,
+    ICN, ICN_MAGIC, TEXTURE_HEIGHT, TEXTURE_SIZE, TEXTURE_WIDTH, UV,
 };
 use byteorder::{ReadBytesExt, LE};
 use image::codecs::png::PngEncoder;
-use image::{EncodableLayout, RgbaImage};
+use image::{imageops, EncodableLayout, RgbaImage};
 use std::io::Cursor;
 
 impl ICN {
+    /// Builds a single upright square textured with `image`, for saves that only have a logo.
+    /// The image is scaled to the 128x128 texture size.
+    pub fn flat_plane(image: &RgbaImage) -> ICN {
+        const HALF_WIDTH: i16 = 6144;
+        const HEIGHT: i16 = -12288;
+
+        let corners = [
+            (-HALF_WIDTH, HEIGHT, 0, 0),
+            (HALF_WIDTH, HEIGHT, 4096, 0),
+            (-HALF_WIDTH, 0, 0, 4096),
+            (HALF_WIDTH, 0, 4096, 4096),
+        ];
+        let (vertices, uvs): (Vec<_>, Vec<_>) = [0, 2, 1, 1, 2, 3]
+            .into_iter()
+            .map(|i| {
+                let (x, y, u, v) = corners[i];
+                (Vertex::new(x, y, 0, 0), UV::new(u, v))
+            })
+            .unzip();
+        let vertex_count = vertices.len();
+
+        let image = imageops::resize(
+            image,
+            TEXTURE_WIDTH as u32,
+            TEXTURE_HEIGHT as u32,
+            imageops::FilterType::Triangle,
+        );
+        let mut texture = IcnTexture {
+            pixels: [0; TEXTURE_SIZE],
+        };
+        for (pixel, rgba) in texture.pixels.iter_mut().zip(image.pixels()) {
+            *pixel = Color::new(rgba.0[0], rgba.0[1], rgba.0[2], 255).into();
+        }
+
+        ICN {
+            header: ICNHeader {
+                animation_shape_count: 1,
+                texture_type: 0x07,
+                vertex_count: vertex_count as u32,
+            },
+            animation_shapes: vec![vertices],
+            normals: vec![Normal::new(0, 0, -4096, 0); vertex_count],
+            uvs,
+            colors: vec![Color::new(0x80, 0x80, 0x80, 0x80); vertex_count],
+            texture,
+            animation_header: AnimationHeader {
+                tag: 0x01,
+                frame_length: 1,
+                anim_speed: 1.0,
+                play_offset: 0,
+                frame_count: 1,
+            },
+            frames: vec![Frame {
+                shape_id: 0,
+                keys: vec![Key {
+                    time: 1.0,
+                    value: 1.0,
+                }],
+            }],
+        }
+    }
+
+    /// Whether the icon has more than one frame to play back
+    pub fn is_animated(&self) -> bool {
+        self.animation_header.frame_length > 1 && !self.frames.is_empty()
+    }
+
+    /// Normalized blend weight of every animation shape at the given frame.
+    /// Icons without animation data always show the first shape.
+    pub fn shape_weights(&self, frame: f32) -> Vec<f32> {
+        let mut weights = vec![0.0; self.animation_shapes.len()];
+
+        for (i, animation_frame) in self.frames.iter().enumerate() {
+            let Some(weight) = weights.get_mut(animation_frame.shape_id as usize) else {
+                continue;
+            };
+            // The first shape is fully visible until its first key
+            *weight += match animation_frame.keys.first() {
+                Some(_) if i == 0 && frame <= 0.0 => 1.0,
+                Some(key) if i == 0 && frame < key.time => {
+                    1.0 + (key.value - 1.0) * frame / key.time
+                }
+                _ => animation_frame.evaluate(frame),
+            };
+        }
+
+        let sum = weights.iter().sum::<f32>();
+        if sum > 0.0 {
+            weights.iter_mut().for_each(|weight| *weight /= sum);
+        } else if let Some(first) = weights.first_mut() {
+            *first = 1.0;
+        }
+
+        weights
+    }
+
+    /// Vertex positions of the model at the given frame, blended between the animation shapes
+    pub fn interpolated_shape(&self, frame: f32) -> Vec<[f32; 3]> {
+        let weights = self.shape_weights(frame);
+        let mut vertices = vec![[0.0; 3]; self.header.vertex_count as usize];
+
+        for (shape, weight) in self.animation_shapes.iter().zip(weights) {
+            if weight == 0.0 {
+                continue;
+            }
+            for (vertex, v) in vertices.iter_mut().zip(shape) {
+                vertex[0] += v.x as f32 * weight;
+                vertex[1] += v.y as f32 * weight;
+                vertex[2] += v.z as f32 * weight;
+            }
+        }
+
+        vertices
+    }
+
     pub fn export_obj(&self) -> String {
         let mut output = String::new();
         let shape = self.animation_shapes[0].clone();
@@ -50,23 +165,177 @@ impl ICN {
         output
     }
 
-    pub fn export_png(&self) -> Vec<u8> {
-        let mut png_data = Vec::new();
-        let mut img = RgbaImage::new(128, 128);
+    /// Decodes the 16-bit texture into RGBA.
+    ///
+    /// Pixels are stored as `ABBBBBGGGGGRRRRR`, each 5-bit channel is widened by bit replication so
+    /// that 31 maps to 255. No gamma correction is applied, the values are passed through as-is.
+    /// The top bit is the GS "STP" flag, which only enables semi-transparency and isn't a real
+    /// alpha channel; the browser draws icons opaque either way, so every pixel gets full alpha.
+    pub fn decode_texture(&self) -> RgbaImage {
+        let mut img = RgbaImage::new(TEXTURE_WIDTH as u32, TEXTURE_HEIGHT as u32);
+
+        for (pixel, &value) in img.pixels_mut().zip(self.texture.pixels.iter()) {
+            pixel.0 = [
+                expand_5bit(value),
+                expand_5bit(value >> 5),
+                expand_5bit(value >> 10),
+                255,
+            ];
+        }
 
-        for (i, pixel) in img.pixels_mut().enumerate() {
-            let color: Color = self.texture.pixels[i].into();
+        img
+    }
 
-            pixel.0 = color.into();
-        }
+    pub fn export_png(&self) -> Vec<u8> {
+        let mut png_data = Vec::new();
+        let img = self.decode_texture();
 
         let encoder = PngEncoder::new(&mut png_data);
         img.write_with_encoder(encoder)
             .expect("Failed to write PNG data");
         png_data
     }
+
+    /// Draws the first frame of the model front-on into a transparent `size`x`size` image,
+    /// scaled to fit. It's rasterized in software at twice the size and scaled down to smooth
+    /// the edges, so thumbnails can be made without a GPU. The lighting is a fixed key light
+    /// rather than the one in icon.sys, which only the 3D viewer reproduces.
+    pub fn render(&self, size: u32) -> RgbaImage {
+        const SUPERSAMPLE: u32 = 2;
+        const MARGIN: f32 = 0.05;
+        const AMBIENT: f32 = 0.4;
+        let light = normalize([-0.4, 0.5, 0.8]);
+
+        let canvas = size * SUPERSAMPLE;
+        let mut image = RgbaImage::new(canvas, canvas);
+        let texture = self.decode_texture();
+
+        // Same axes as the viewer, y is up and z points at the camera
+        let shape = self
+            .interpolated_shape(0.0)
+            .into_iter()
+            .map(|[x, y, z]| [x / 4096.0, -y / 4096.0, -z / 4096.0])
+            .collect::<Vec<_>>();
+        let count = shape.len().min(self.uvs.len()).min(self.normals.len()) / 3 * 3;
+        if count == 0 {
+            return RgbaImage::new(size, size);
+        }
+
+        let (mut min, mut max) = ([f32::MAX; 2], [f32::MIN; 2]);
+        for [x, y, _] in &shape[..count] {
+            min = [min[0].min(*x), min[1].min(*y)];
+            max = [max[0].max(*x), max[1].max(*y)];
+        }
+        let extent = (max[0] - min[0]).max(max[1] - min[1]).max(f32::EPSILON);
+        let scale = canvas as f32 * (1.0 - 2.0 * MARGIN) / extent;
+        let center = [(min[0] + max[0]) / 2.0, (min[1] + max[1]) / 2.0];
+        let half = canvas as f32 / 2.0;
+        let points = shape[..count]
+            .iter()
+            .map(|[x, y, z]| {
+                [
+                    (x - center[0]) * scale + half,
+                    (center[1] - y) * scale + half,
+                    *z,
+                ]
+            })
+            .collect::<Vec<_>>();
+
+        let mut depth = vec![f32::NEG_INFINITY; (canvas * canvas) as usize];
+        for first in (0..count).step_by(3) {
+            let corners = [first, first + 1, first + 2];
+            let [a, b, c] = corners.map(|i| points[i]);
+            let area = edge(a, b, c);
+            if area.abs() < f32::EPSILON {
+                continue;
+            }
+
+            let x0 = a[0].min(b[0]).min(c[0]).floor().max(0.0) as u32;
+            let y0 = a[1].min(b[1]).min(c[1]).floor().max(0.0) as u32;
+            let x1 = (a[0].max(b[0]).max(c[0]).ceil() as u32).min(canvas - 1);
+            let y1 = (a[1].max(b[1]).max(c[1]).ceil() as u32).min(canvas - 1);
+            for y in y0..=y1 {
+                for x in x0..=x1 {
+                    let p = [x as f32 + 0.5, y as f32 + 0.5, 0.0];
+                    // Dividing by the signed area makes this work for either winding
+                    let weights = [
+                        edge(b, c, p) / area,
+                        edge(c, a, p) / area,
+                        edge(a, b, p) / area,
+                    ];
+                    if weights.iter().any(|&weight| weight < 0.0) {
+                        continue;
+                    }
+                    let blend = |value: &dyn Fn(usize) -> f32| -> f32 {
+                        corners
+                            .iter()
+                            .zip(weights)
+                            .map(|(&i, weight)| value(i) * weight)
+                            .sum()
+                    };
+
+                    let z = blend(&|i| points[i][2]);
+                    let offset = (y * canvas + x) as usize;
+                    if z <= depth[offset] {
+                        continue;
+                    }
+                    depth[offset] = z;
+
+                    let u = blend(&|i| self.uvs[i].u as f32 / 4096.0);
+                    let v = blend(&|i| self.uvs[i].v as f32 / 4096.0);
+                    let texel = texture.get_pixel(
+                        ((u * TEXTURE_WIDTH as f32) as u32).min(TEXTURE_WIDTH as u32 - 1),
+                        ((v * TEXTURE_HEIGHT as f32) as u32).min(TEXTURE_HEIGHT as u32 - 1),
+                    );
+                    let normal = normalize([
+                        blend(&|i| self.normals[i].x as f32),
+                        blend(&|i| -self.normals[i].y as f32),
+                        blend(&|i| -self.normals[i].z as f32),
+                    ]);
+                    let diffuse =
+                        (normal[0] * light[0] + normal[1] * light[1] + normal[2] * light[2])
+                            .max(0.0);
+                    let shade = AMBIENT + (1.0 - AMBIENT) * diffuse;
+
+                    image.put_pixel(
+                        x,
+                        y,
+                        image::Rgba([
+                            (texel.0[0] as f32 * shade) as u8,
+                            (texel.0[1] as f32 * shade) as u8,
+                            (texel.0[2] as f32 * shade) as u8,
+                            255,
+                        ]),
+                    );
+                }
+            }
+        }
+
+        imageops::resize(&image, size, size, imageops::FilterType::Triangle)
+    }
+}
+
+/// Twice the signed area of the triangle `a`, `b`, `p` in screen space
+fn edge(a: [f32; 3], b: [f32; 3], p: [f32; 3]) -> f32 {
+    (p[0] - a[0]) * (b[1] - a[1]) - (p[1] - a[1]) * (b[0] - a[0])
 }
 
+fn normalize([x, y, z]: [f32; 3]) -> [f32; 3] {
+    let length = (x * x + y * y + z * z).sqrt();
+    if length == 0.0 {
+        return [0.0; 3];
+    }
+    [x / length, y / length, z / length]
+}
+
+fn expand_5bit(value: u16) -> u8 {
+    let channel = (value & 0x1F) as u8;
+    (channel << 3) | (channel >> 2)
+}
+
+/// The animation shapes and the normal, UV and color of every vertex
+type ShapeData = (Vec<AnimationShape>, Vec<Normal>, Vec<UV>, Vec<Color>);
+
 pub struct ICNParser {
     c: Cursor<Vec<u8>>,
 }
@@ -116,10 +385,7 @@ impl ICNParser {
         })
     }
 
-    fn parse_animation_shapes(
-        &mut self,
-        icnheader: &ICNHeader,
-    ) -> std::io::Result<(Vec<AnimationShape>, Vec<Normal>, Vec<UV>, Vec<Color>)> {
+    fn parse_animation_shapes(&mut self, icnheader: &ICNHeader) -> std::io::Result<ShapeData> {
         let mut shapes: Vec<AnimationShape> =
             Vec::with_capacity(icnheader.animation_shape_count as usize);
         let mut normals: Vec<Normal> = Vec::with_capacity(icnheader.vertex_count as usize);
@@ -288,3 +554,47 @@ impl ICNParser {
         Ok(Color { r, g, b, a })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn icn(pixels: [u16; TEXTURE_SIZE]) -> ICN {
+        ICN {
+            header: ICNHeader {
+                animation_shape_count: 0,
+                vertex_count: 0,
+                texture_type: 0,
+            },
+            animation_shapes: vec![],
+            normals: vec![],
+            uvs: vec![],
+            colors: vec![],
+            texture: IcnTexture { pixels },
+            animation_header: AnimationHeader {
+                tag: 0,
+                frame_length: 0,
+                anim_speed: 0.0,
+                play_offset: 0,
+                frame_count: 0,
+            },
+            frames: vec![],
+        }
+    }
+
+    #[test]
+    fn texture_pixels_widen_to_eight_bits() {
+        let mut pixels = [0; TEXTURE_SIZE];
+        pixels[0] = 0x001F;
+        // Green at half intensity with the STP bit set, at (1, 2)
+        pixels[2 * TEXTURE_WIDTH + 1] = 0x8000 | (16 << 5);
+        pixels[TEXTURE_SIZE - 1] = 0x7FFF;
+
+        let image = icn(pixels).decode_texture();
+
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+        assert_eq!(image.get_pixel(1, 2).0, [0, 132, 0, 255]);
+        assert_eq!(image.get_pixel(127, 127).0, [255, 255, 255, 255]);
+        assert_eq!(image.get_pixel(5, 5).0, [0, 0, 0, 255]);
+    }
+}
@@ -1,10 +1,12 @@
 use std::io::{Cursor, Read, Result};
 use crate::color::Color;
-use crate::sjis::{decode_sjis, encode_sjis};
+use crate::sjis::{decode_sjis, decodes_cleanly, encode_sjis};
 use crate::util::parse_cstring;
 use byteorder::{ReadBytesExt, LE};
 
-#[derive(Clone, Copy, Debug)]
+pub const TITLE_SIZE: usize = 68;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct ColorF {
     pub r: f32,
     pub g: f32,
@@ -13,6 +15,10 @@ pub struct ColorF {
 }
 
 impl ColorF {
+    pub const fn new(r: f32, g: f32, b: f32, a: f32) -> Self {
+        Self { r, g, b, a }
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         vec![
             f32::to_le_bytes(self.r),
@@ -24,7 +30,7 @@ impl ColorF {
     }
 }
 
-#[derive(Clone, Copy, Debug)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct Vector {
     pub x: f32,
     pub y: f32,
@@ -33,6 +39,10 @@ pub struct Vector {
 }
 
 impl Vector {
+    pub const fn new(x: f32, y: f32, z: f32, w: f32) -> Self {
+        Self { x, y, z, w }
+    }
+
     pub fn to_bytes(&self) -> Vec<u8> {
         vec![
             f32::to_le_bytes(self.x),
@@ -72,6 +82,42 @@ pub struct IconSys {
     pub icon_file: String,
     pub icon_copy_file: String,
     pub icon_delete_file: String,
+    /// False when the title had code points the Shift-JIS decoder doesn't know, which come
+    /// out as `?` and would be written back that way
+    pub title_decoded_cleanly: bool,
+}
+
+impl Default for IconSys {
+    /// A plain dark blue background with the lighting most official saves ship with
+    fn default() -> Self {
+        Self {
+            flags: 0,
+            linebreak_pos: 0,
+            background_transparency: 0,
+            background_colors: [
+                Color::new(0, 0, 64, 128),
+                Color::new(0, 0, 64, 128),
+                Color::new(0, 0, 16, 128),
+                Color::new(0, 0, 16, 128),
+            ],
+            light_directions: [
+                Vector::new(0.5, 0.5, 0.5, 0.0),
+                Vector::new(0.0, -0.4, -0.1, 0.0),
+                Vector::new(-0.5, -0.5, 0.5, 0.0),
+            ],
+            light_colors: [
+                ColorF::new(0.5, 0.5, 0.5, 0.0),
+                ColorF::new(0.3, 0.3, 0.3, 0.0),
+                ColorF::new(0.2, 0.2, 0.2, 0.0),
+            ],
+            ambient_color: ColorF::new(0.3, 0.3, 0.3, 0.0),
+            title: String::new(),
+            icon_file: "icon.icn".to_string(),
+            icon_copy_file: "icon.icn".to_string(),
+            icon_delete_file: "icon.icn".to_string(),
+            title_decoded_cleanly: true,
+        }
+    }
 }
 
 impl IconSys {
@@ -79,6 +125,14 @@ impl IconSys {
         parse_icon_sys(bytes).unwrap()
     }
 
+    pub fn try_new(bytes: Vec<u8>) -> Result<Self> {
+        parse_icon_sys(bytes)
+    }
+
+    pub fn title_lines(&self) -> (String, String) {
+        split_title(&self.title, self.linebreak_pos)
+    }
+
     pub fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
         let mut bytes = Vec::new();
         bytes.extend_from_slice(b"PS2D");
@@ -104,16 +158,16 @@ impl IconSys {
 
         let title_bytes = encode_sjis(&self.title);
         let title_len = title_bytes.len();
-        if title_len > 68 {
+        if title_len > TITLE_SIZE {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::InvalidInput,
-                "Title length exceeds 68 bytes",
+                format!("Title length exceeds {TITLE_SIZE} bytes"),
             ));
         }
 
         bytes.extend_from_slice(&title_bytes);
-        if title_len < 68 {
-            bytes.extend(vec![0; 68 - title_len]);
+        if title_len < TITLE_SIZE {
+            bytes.extend(vec![0; TITLE_SIZE - title_len]);
         }
 
         bytes.extend_from_slice(self.icon_file.as_bytes());
@@ -140,6 +194,46 @@ impl IconSys {
     }
 }
 
+/**
+ * Splits a title into the two lines shown in the browser.
+ *
+ * `linebreak_pos` is meant to be a byte offset into the Shift-JIS encoded title, but some
+ * tools write a character index instead. If the byte offset doesn't land on a character
+ * boundary it is treated as a character index.
+ */
+pub fn split_title(title: &str, linebreak_pos: u16) -> (String, String) {
+    let position = linebreak_pos as usize;
+    let chars = title.chars().collect::<Vec<_>>();
+
+    let mut offset = 0;
+    let mut index = None;
+    for (i, c) in chars.iter().enumerate() {
+        if offset >= position {
+            index = (offset == position).then_some(i);
+            break;
+        }
+        offset += sjis_width(*c);
+    }
+    if index.is_none() && offset == position {
+        index = Some(chars.len());
+    }
+
+    let index = index.unwrap_or(position.min(chars.len()));
+
+    (
+        chars[..index].iter().collect(),
+        chars[index..].iter().collect(),
+    )
+}
+
+/// Bytes `c` takes up in a title, `encode_sjis` only knows the full-width ASCII forms
+fn sjis_width(c: char) -> usize {
+    if c.is_ascii() {
+        return encode_sjis(&c.to_string()).len();
+    }
+    encoding_rs::SHIFT_JIS.encode(&c.to_string()).0.len()
+}
+
 #[expect(unused)]
 struct IconSysParser {
     c: Cursor<Vec<u8>>,
@@ -178,7 +272,7 @@ fn parse_icon_sys(bytes: Vec<u8>) -> Result<IconSys> {
 
     let ambient_color = parse_colorf(&mut c)?;
 
-    let mut title_buf = vec![0u8; 68];
+    let mut title_buf = vec![0u8; TITLE_SIZE];
     c.read_exact(&mut title_buf)?;
 
     let mut icon_file_buf = vec![0u8; 64];
@@ -197,6 +291,7 @@ fn parse_icon_sys(bytes: Vec<u8>) -> Result<IconSys> {
         light_colors,
         ambient_color,
         title: parse_sjis_string(&title_buf),
+        title_decoded_cleanly: decodes_cleanly(&title_buf),
         icon_file: parse_cstring(&icon_file_buf),
         icon_copy_file: parse_cstring(&icon_copy_file_buf),
         icon_delete_file: parse_cstring(&icon_delete_file_buf),
@@ -235,3 +330,45 @@ fn parse_direction(c: &mut Cursor<Vec<u8>>) -> Result<Vector> {
 
     Ok(Vector { x, y, z, w })
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(first: &str, second: &str) -> (String, String) {
+        (first.to_string(), second.to_string())
+    }
+
+    // Every character below is stored as two bytes
+    #[test]
+    fn linebreak_on_a_character_boundary_is_a_byte_offset() {
+        assert_eq!(split_title("ドラゴン", 4), lines("ドラ", "ゴン"));
+        assert_eq!(split_title("ドラゴン", 8), lines("ドラゴン", ""));
+        assert_eq!(split_title("ABドラゴン", 6), lines("ABド", "ラゴン"));
+    }
+
+    #[test]
+    fn linebreak_inside_a_character_is_a_character_index() {
+        assert_eq!(split_title("ドラゴン", 3), lines("ドラゴ", "ン"));
+        assert_eq!(split_title("ABドラゴン", 5), lines("ABドラゴ", "ン"));
+    }
+
+    #[test]
+    fn title_bytes_outside_the_decoded_range_are_flagged() {
+        let icon_sys = IconSys {
+            title: "SAVE".to_string(),
+            ..Default::default()
+        };
+        let mut bytes = icon_sys.to_bytes().unwrap();
+        let read = IconSys::try_new(bytes.clone()).unwrap();
+        assert_eq!(read.title, "SAVE");
+        assert!(read.title_decoded_cleanly);
+
+        // "ドラ" as a real Shift-JIS encoder writes it, which the title decoder doesn't map
+        let title = encode_sjis("SAVE");
+        let offset = bytes.windows(title.len()).position(|w| w == title).unwrap();
+        bytes[offset..offset + 4].copy_from_slice(&[0x83, 0x68, 0x83, 0x89]);
+
+        assert!(!IconSys::try_new(bytes).unwrap().title_decoded_cleanly);
+    }
+}
@@ -1,6 +1,5 @@
 mod icn;
 mod icon_sys;
-mod mcd;
 mod psu;
 mod title_cfg;
 
@@ -8,5 +7,4 @@ mod title_cfg;
 pub use psu::*;
 pub use icn::*;
 pub use icon_sys::*;
-pub use mcd::*;
 pub use title_cfg::*;
\ No newline at end of file
@@ -1,8 +1,14 @@
+use std::fs::File;
 use std::io::{Cursor, Read, Seek};
+use std::ops::{Deref, Range};
+use std::path::Path;
 
 use byteorder::{ReadBytesExt, LE};
-use crate::{PSUEntry, PSUEntryKind, PSUParser, DIR_ID, FILE_ID, PAGE_SIZE, PSU};
-use crate::util::parse_cstring;
+use memmap2::Mmap;
+use crate::{
+    BinReader, PSUEntry, PSUEntryKind, PSUParser, MODE_DIRECTORY, MODE_FILE, PAGE_SIZE, PSU,
+};
+use crate::util::{decompress, is_gzip, parse_cstring};
 
 impl PSU {
     pub fn entries(&self) -> Vec<PSUEntry> {
@@ -12,22 +18,128 @@ impl PSU {
 
 impl PSU {
     pub fn new(bytes: Vec<u8>) -> Self {
-        Self {
-            entries: PSUParser::new(bytes).parse().unwrap(),
+        PSUParser::read(&bytes).unwrap()
+    }
+
+    /// Memory-maps the file instead of reading it, see `MappedPSU`
+    pub fn open(path: &Path) -> std::io::Result<MappedPSU> {
+        MappedPSU::open(path)
+    }
+}
+
+/// A PSU backed by a memory-mapped file. Only the entry headers are parsed up front,
+/// file contents stay on disk until they're sliced with `contents`.
+/// Gzip-compressed saves can't be mapped and are inflated into memory instead.
+pub struct MappedPSU {
+    map: PSUData,
+    entries: Vec<PSUEntry>,
+    ranges: Vec<Option<Range<usize>>>,
+}
+
+enum PSUData {
+    Mapped(Mmap),
+    Inflated(Vec<u8>),
+}
+
+impl Deref for PSUData {
+    type Target = [u8];
+
+    fn deref(&self) -> &[u8] {
+        match self {
+            PSUData::Mapped(map) => map,
+            PSUData::Inflated(data) => data,
         }
     }
 }
 
-impl PSUParser {
-    fn new(bytes: Vec<u8>) -> PSUParser {
-        let len = bytes.len() as u64;
+impl MappedPSU {
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let file = File::open(path)?;
+        // SAFETY: the map is read-only, a save being rewritten while it's open shows up as
+        // garbled contents rather than undefined behaviour in the parser, which bounds-checks
+        // every entry against the map's length
+        let map = unsafe { Mmap::map(&file)? };
+        let map = if is_gzip(&map) {
+            PSUData::Inflated(decompress(&map)?.into_owned())
+        } else {
+            PSUData::Mapped(map)
+        };
+        let (entries, ranges) = PSUParser::new(&map).parse_layout()?.into_iter().unzip();
+
+        Ok(Self {
+            map,
+            entries,
+            ranges,
+        })
+    }
+
+    /// Entry headers in archive order, `contents` is always `None`
+    pub fn entries(&self) -> &[PSUEntry] {
+        &self.entries
+    }
+
+    /// The contents of the entry at `index`, or `None` for directories
+    pub fn contents(&self, index: usize) -> Option<&[u8]> {
+        let range = self.ranges.get(index)?.clone()?;
+        Some(&self.map[range])
+    }
+
+    /// Iterates the entries alongside their contents
+    pub fn iter(&self) -> impl Iterator<Item = (&PSUEntry, Option<&[u8]>)> {
+        self.entries
+            .iter()
+            .enumerate()
+            .map(|(index, entry)| (entry, self.contents(index)))
+    }
+
+    /// Copies every file's contents into an owned `PSU`
+    pub fn to_psu(&self) -> PSU {
+        PSU {
+            entries: self
+                .iter()
+                .map(|(entry, contents)| PSUEntry {
+                    contents: contents.map(<[u8]>::to_vec),
+                    ..entry.clone()
+                })
+                .collect(),
+        }
+    }
+}
+
+/// An entry's header and the span of the data holding its contents
+type EntryLayout = (PSUEntry, Option<Range<usize>>);
+
+impl BinReader<PSU> for PSUParser<'_> {
+    /// Also reads gzip-compressed saves
+    fn read(data: &[u8]) -> std::io::Result<PSU> {
+        Ok(PSU {
+            entries: PSUParser::new(&decompress(data)?).parse()?,
+        })
+    }
+}
+
+impl<'a> PSUParser<'a> {
+    fn new(bytes: &'a [u8]) -> Self {
         Self {
             c: Cursor::new(bytes),
-            len,
+            len: bytes.len() as u64,
         }
     }
 
     fn parse(&mut self) -> Result<Vec<PSUEntry>, std::io::Error> {
+        let data = *self.c.get_ref();
+        Ok(self
+            .parse_layout()?
+            .into_iter()
+            .map(|(entry, range)| PSUEntry {
+                contents: range.map(|range| data[range].to_vec()),
+                ..entry
+            })
+            .collect())
+    }
+
+    /// Reads every header, returning where each file's contents are instead of copying them
+    fn parse_layout(&mut self) -> Result<Vec<EntryLayout>, std::io::Error> {
         let mut result = vec![];
         while self.c.position() < self.len {
             let entry = self.read_entry()?;
@@ -37,7 +149,7 @@ impl PSUParser {
         Ok(result)
     }
 
-    fn read_entry(&mut self) -> Result<PSUEntry, std::io::Error> {
+    fn read_entry(&mut self) -> Result<EntryLayout, std::io::Error> {
         let id = self.c.read_u16::<LE>()?;
         let _ = self.c.read_u16::<LE>()?;
         let size = self.c.read_u32::<LE>()?;
@@ -51,33 +163,41 @@ impl PSUParser {
         let mut name = [0; 448];
         self.c.read_exact(&mut name)?;
 
-        let contents = if id == FILE_ID {
-            let mut contents = vec![0; size as usize];
-            self.c.read_exact(&mut contents)?;
+        let range = if id & MODE_FILE != 0 {
+            if size as u64 > self.len - self.c.position() {
+                return Err(std::io::Error::new(
+                    std::io::ErrorKind::UnexpectedEof,
+                    format!("Entry {} is larger than the remaining file", parse_cstring(&name)),
+                ));
+            }
+            let start = self.c.position() as usize;
+            self.c.seek_relative(size as i64)?;
             let rem = 1024 - (size % 1024);
 
             self.c
                 .seek_relative(if rem == PAGE_SIZE { 0 } else { rem as i64 })?;
 
-            Some(contents)
+            Some(start..start + size as usize)
         } else {
             None
         };
 
-        Ok(PSUEntry {
+        let entry = PSUEntry {
             id,
             size,
             created,
             sector,
             modified,
             name: parse_cstring(&name),
-            kind: if id == DIR_ID {
+            kind: if id & MODE_DIRECTORY != 0 {
                 PSUEntryKind::Directory
             } else {
                 PSUEntryKind::File
             },
-            contents,
-        })
+            contents: None,
+        };
+
+        Ok((entry, range))
     }
 
     fn read_timestamp(&mut self) -> Result<chrono::NaiveDateTime, std::io::Error> {
@@ -89,10 +209,92 @@ impl PSUParser {
         let months = self.c.read_u8()?;
         let year = self.c.read_u16::<LE>()?;
 
-        let date = chrono::NaiveDate::from_ymd_opt(year as i32, months as u32, days as u32)
-            .unwrap()
-            .and_hms_opt(hours as u32, minutes as u32, seconds as u32)
-            .unwrap();
-        Ok(date)
+        chrono::NaiveDate::from_ymd_opt(year as i32, months as u32, days as u32)
+            .and_then(|date| date.and_hms_opt(hours as u32, minutes as u32, seconds as u32))
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "Invalid timestamp",
+            ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{PSUWriter, DIR_ID, FILE_ID, MODE_COPY_PROTECTED, MODE_HIDDEN};
+    use chrono::NaiveDateTime;
+    use flate2::write::GzEncoder;
+    use flate2::Compression;
+    use std::borrow::Cow;
+    use std::io::Write;
+
+    fn entry(id: u16, size: u32, name: &str, contents: Option<&[u8]>) -> PSUEntry {
+        PSUEntry {
+            id,
+            size,
+            created: NaiveDateTime::default(),
+            sector: 0,
+            modified: NaiveDateTime::default(),
+            name: name.to_string(),
+            kind: if id & MODE_FILE != 0 {
+                PSUEntryKind::File
+            } else {
+                PSUEntryKind::Directory
+            },
+            contents: contents.map(<[u8]>::to_vec),
+        }
+    }
+
+    #[test]
+    fn gzipped_psu_reads_like_the_plain_one() {
+        let contents = vec![0x5A; 1500];
+        let plain = PSUWriter::new(PSU {
+            entries: vec![
+                entry(DIR_ID, 3, "BASLUS-20001", None),
+                entry(DIR_ID, 0, ".", None),
+                entry(DIR_ID, 0, "..", None),
+                entry(FILE_ID, 1500, "DATA.BIN", Some(&contents)),
+            ],
+        })
+        .to_bytes()
+        .unwrap();
+        let mut encoder = GzEncoder::new(vec![], Compression::default());
+        encoder.write_all(&plain).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        assert_eq!(decompress(&compressed).unwrap(), plain.as_slice());
+        assert!(matches!(decompress(&plain).unwrap(), Cow::Borrowed(_)));
+        let names_and_contents = |psu: PSU| {
+            psu.entries
+                .into_iter()
+                .map(|entry| (entry.name, entry.contents))
+                .collect::<Vec<_>>()
+        };
+        let read = names_and_contents(PSUParser::read(&compressed).unwrap());
+        assert_eq!(read, names_and_contents(PSUParser::read(&plain).unwrap()));
+        assert_eq!(read[3], ("DATA.BIN".to_string(), Some(contents)));
+    }
+
+    #[test]
+    fn custom_modes_round_trip() {
+        let contents = b"hidden".as_slice();
+        let psu = PSU {
+            entries: vec![
+                entry(DIR_ID | MODE_COPY_PROTECTED, 3, "BASLUS-20001", None),
+                entry(DIR_ID, 0, ".", None),
+                entry(DIR_ID, 0, "..", None),
+                entry(FILE_ID | MODE_HIDDEN, 6, "SECRET.BIN", Some(contents)),
+            ],
+        };
+
+        let read = PSUParser::read(&PSUWriter::new(psu).to_bytes().unwrap()).unwrap();
+
+        let save = &read.entries[0];
+        assert_eq!(save.id, DIR_ID | MODE_COPY_PROTECTED);
+        assert!(matches!(save.kind, PSUEntryKind::Directory));
+        let file = &read.entries[3];
+        assert_eq!(file.id, FILE_ID | MODE_HIDDEN);
+        assert!(matches!(file.kind, PSUEntryKind::File));
+        assert_eq!(file.contents.as_deref(), Some(contents));
     }
 }
@@ -42,6 +42,17 @@ impl TitleCfg {
         self.index_map = string_to_index_map(self.contents.clone());
     }
 
+    /// One-based numbers of the lines in `contents` that aren't blank or `key=value`.
+    /// Syncing them to the index map would drop them.
+    pub fn invalid_lines(&self) -> Vec<usize> {
+        self.contents
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.contains('='))
+            .map(|(index, _)| index + 1)
+            .collect()
+    }
+
     pub fn has_mandatory_fields(&self) -> bool {
         for (_, key) in MANDATORY_KEYS.iter().enumerate() {
             if !self.index_map.contains_key(key.to_owned()) {
@@ -59,6 +70,36 @@ impl TitleCfg {
         }
         self
     }
+
+    /// Dumps the fields as `{"fields": {...}, "duplicates": [...]}`, keeping the file's key order.
+    /// Keys that appear more than once keep their last value, the same as the parser,
+    /// and are listed under `duplicates`.
+    pub fn to_json(&self) -> String {
+        let mut seen = IndexMap::<&str, usize>::new();
+        let keys = self
+            .contents
+            .lines()
+            .filter_map(|line| line.split_once('='));
+        for (key, _) in keys {
+            *seen.entry(key).or_default() += 1;
+        }
+        let duplicates = seen
+            .into_iter()
+            .filter(|(_, count)| *count > 1)
+            .map(|(key, _)| key)
+            .collect::<Vec<_>>();
+
+        let fields = self
+            .index_map
+            .iter()
+            .map(|(key, value)| (key.clone(), value.clone().into()))
+            .collect::<serde_json::Map<_, _>>();
+        let json = serde_json::json!({
+            "fields": fields,
+            "duplicates": duplicates,
+        });
+        serde_json::to_string_pretty(&json).expect("title.cfg fields are always valid JSON")
+    }
 }
 
 impl Display for TitleCfg {
@@ -71,14 +112,35 @@ impl Display for TitleCfg {
     }
 }
 
+/// Lines that aren't blank or `key=value` are left out, `invalid_lines` points them out
 fn string_to_index_map(contents: String) -> IndexMap<String, String> {
     let mut index_map: IndexMap<String, String> = IndexMap::new();
 
-    let lines = contents.lines();
-    for line in lines {
-        let pair = line.split('=').collect::<Vec<&str>>();
-        index_map.insert(pair[0].to_string(), pair[1].to_string());
+    let pairs = contents.lines().filter_map(|line| line.split_once('='));
+    for (key, value) in pairs {
+        index_map.insert(key.to_string(), value.to_string());
     }
 
     index_map
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn json_round_trips_with_duplicates_noted() {
+        let cfg = TitleCfg::new("title=Old\nboot=BOOT.ELF\nVersion=1.0\ntitle=New\n".to_string());
+
+        let json: serde_json::Value = serde_json::from_str(&cfg.to_json()).unwrap();
+
+        assert_eq!(json["duplicates"], serde_json::json!(["title"]));
+        let fields = json["fields"].as_object().unwrap();
+        let rebuilt = fields
+            .iter()
+            .map(|(key, value)| format!("{key}={}\n", value.as_str().unwrap()))
+            .collect::<String>();
+        assert_eq!(rebuilt, "title=New\nboot=BOOT.ELF\nVersion=1.0\n");
+        assert_eq!(TitleCfg::new(rebuilt).index_map, cfg.index_map);
+    }
+}
@@ -1,3 +1,7 @@
+use flate2::read::GzDecoder;
+use std::borrow::Cow;
+use std::io::Read;
+
 pub fn parse_cstring(input: &[u8]) -> String {
     let mut result = input.to_vec();
     if let Some(first) = result.iter().position(|&b| b == 0) {
@@ -5,3 +9,21 @@ pub fn parse_cstring(input: &[u8]) -> String {
     }
     String::from_utf8_lossy(&result).to_string()
 }
+
+const GZIP_MAGIC: [u8; 2] = [0x1F, 0x8B];
+
+pub fn is_gzip(data: &[u8]) -> bool {
+    data.starts_with(&GZIP_MAGIC)
+}
+
+/// Inflates gzip-compressed data, such as a `.psu.gz`, and passes anything else through as is
+pub fn decompress(data: &[u8]) -> std::io::Result<Cow<'_, [u8]>> {
+    if !is_gzip(data) {
+        return Ok(Cow::Borrowed(data));
+    }
+
+    let mut inflated = vec![];
+    GzDecoder::new(data).read_to_end(&mut inflated)?;
+
+    Ok(Cow::Owned(inflated))
+}
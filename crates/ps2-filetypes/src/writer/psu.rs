@@ -1,7 +1,7 @@
-use crate::{PSUEntry, FILE_ID, PAGE_SIZE, PSU};
+use crate::{PSUEntry, MODE_FILE, PAGE_SIZE, PSU};
 use byteorder::{WriteBytesExt, LE};
 use chrono::{Datelike, NaiveDateTime, Timelike};
-use std::io::Write;
+use std::io::{Read, Write};
 
 pub struct PSUWriter {
     psu: PSU
@@ -36,7 +36,7 @@ impl PSUWriter {
         Ok(data) 
     }
     
-    fn write_entry(&self, entry: &PSUEntry) -> std::io::Result<Vec<u8>> {
+    fn write_header(&self, entry: &PSUEntry) -> std::io::Result<Vec<u8>> {
         let mut data: Vec<u8> = vec![];
         data.write_u16::<LE>(entry.id)?;
         data.write_u16::<LE>(0)?;
@@ -50,26 +50,62 @@ impl PSUWriter {
         data.write_all(&padding)?;
         data.write_all(&self.write_string(entry.name.clone())?)?;
 
-        if entry.id == FILE_ID {
-            data.write_all(&entry.contents.clone().unwrap())?;
-            let rem = 1024 - (entry.size % 1024);
+        Ok(data)
+    }
 
-            let rem = if rem == PAGE_SIZE { 0 } else { rem as i64 };
-            for _ in 0..rem {
-                data.write_u8(0)?;
-            }
-        }
+    fn write_padding<W: Write>(&self, writer: &mut W, size: u32) -> std::io::Result<()> {
+        let rem = 1024 - (size % 1024);
+        let rem = if rem == PAGE_SIZE { 0 } else { rem as usize };
 
-        Ok(data) 
+        writer.write_all(&vec![0u8; rem])
     }
 
     pub fn to_bytes(&self) -> std::io::Result<Vec<u8>> {
         let mut data = vec![];
+        self.write_to(&mut data)?;
+
+        Ok(data)
+    }
 
+    /// Writes the archive entry by entry, every file entry must have its contents loaded
+    pub fn write_to<W: Write>(&self, writer: W) -> std::io::Result<()> {
+        self.write_with(writer, |entry| {
+            Err::<&[u8], _>(std::io::Error::new(
+                std::io::ErrorKind::InvalidInput,
+                format!("{} has no contents", entry.name),
+            ))
+        })
+    }
+
+    /// Like `write_to`, but file entries without contents are streamed from the reader `open`
+    /// returns for them, so large saves never have to be held in memory
+    pub fn write_with<W, R, F>(&self, mut writer: W, mut open: F) -> std::io::Result<()>
+    where
+        W: Write,
+        R: Read,
+        F: FnMut(&PSUEntry) -> std::io::Result<R>,
+    {
         for entry in &self.psu.entries {
-            data.extend(self.write_entry(entry)?);
+            writer.write_all(&self.write_header(entry)?)?;
+            if entry.id & MODE_FILE == 0 {
+                continue;
+            }
+
+            match &entry.contents {
+                Some(contents) => writer.write_all(contents)?,
+                None => {
+                    let copied = std::io::copy(&mut open(entry)?, &mut writer)?;
+                    if copied != entry.size as u64 {
+                        return Err(std::io::Error::new(
+                            std::io::ErrorKind::UnexpectedEof,
+                            format!("{} is {copied} bytes, expected {}", entry.name, entry.size),
+                        ));
+                    }
+                }
+            }
+            self.write_padding(&mut writer, entry.size)?;
         }
 
-        Ok(data)
+        writer.flush()
     }
-}
\ No newline at end of file
+}
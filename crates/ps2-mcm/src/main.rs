@@ -1,10 +1,15 @@
-use eframe::egui::{Align, Align2, Color32, Context, Id, LayerId, Order, TextStyle};
+use eframe::egui::{Align2, Color32, Context, Grid, Id, LayerId, Order, TextStyle, Ui};
 use eframe::{egui, Frame};
-use ps2_filetypes::{PSUEntry, PSU};
+use ps2_filetypes::{MappedPSU, PSUEntry, PSUEntryKind, PSU};
+use psu_packer::logger::{Logger, Verbosity};
+use psu_packer::{extract, save_folder_name, Extracted};
 use rfd::FileDialog;
 use std::fs::File;
-use std::io::{Read, Write};
-use std::path::Path;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const RECENT_FILES_KEY: &str = "recent_files";
+const MAX_RECENT_FILES: usize = 10;
 
 fn main() {
     let options = eframe::NativeOptions {
@@ -16,67 +21,209 @@ fn main() {
     eframe::run_native(
         "PS2 MCM",
         options,
-        Box::new(|_cc| Ok(Box::<MCM>::default())),
+        Box::new(|cc| Ok(Box::new(MCM::new(cc)))),
     )
     .unwrap()
 }
 
+#[derive(Copy, Clone, PartialEq)]
+enum SortColumn {
+    Name,
+    Size,
+    Kind,
+}
+
 #[derive(Default)]
 struct MCM {
-    files: Vec<PSUEntry>,
+    psu: Option<MappedPSU>,
+    recent_files: Vec<PathBuf>,
+    sort_column: Option<SortColumn>,
+    sort_descending: bool,
+    hide_dot_entries: bool,
+    export_summary: Option<Result<Extracted, String>>,
+    /// Why the last file picked couldn't be opened, the previous save stays open
+    load_error: Option<String>,
 }
 
 impl MCM {
+    fn new(cc: &eframe::CreationContext<'_>) -> Self {
+        Self {
+            recent_files: cc
+                .storage
+                .and_then(|s| eframe::get_value(s, RECENT_FILES_KEY))
+                .unwrap_or_default(),
+            ..Default::default()
+        }
+    }
+
     fn load_file<P>(&mut self, path: P)
     where
         P: AsRef<Path>,
     {
-        let mut psu_raw = File::open(path).unwrap();
-        let mut contents = vec![];
-        psu_raw.read_to_end(&mut contents).unwrap();
-        let psu = PSU::new(contents);
-        self.files = psu.entries;
-
-        // for file in self.files.iter() {
-        //     if file.name == "list.icn" {
-        //         let icn = ICN::new(file.contents.clone().unwrap());
-        //         let mut output = File::create("/Users/<user>/Downloads/list.obj")
-        //             .expect("Failed to open");
-        //         output
-        //             .write_all(icn.export_obj().as_bytes())
-        //             .expect("Failed to write");
-        // let mut image = RgbImage::new(128, 128);
-        // for y in 0..128 {
-        //     for x in 0..128 {
-        //         let pixel = icn.texture.pixels[y * 128 + x];
-        //         let r = ((pixel & 0x3F) * 255 / 31) as u8;
-        //         let g = (((pixel >> 5) & 0x3F) * 255 / 31) as u8;
-        //         let b = (((pixel >> 10) & 0x3F) * 255 / 31) as u8;
-        //         image.put_pixel(x as u32, y as u32, Rgb([r,g,b]));
-        //     }
-        // }
-        // image.save("/Users/<user>/Downloads/list.png").unwrap();
-        //
-        //         break;
-        //     }
-        // }
+        // Mapped rather than read, so large saves open instantly
+        match PSU::open(path.as_ref()) {
+            Ok(psu) => {
+                self.psu = Some(psu);
+                self.export_summary = None;
+                self.load_error = None;
+                self.add_recent_file(path.as_ref().to_path_buf());
+            }
+            Err(err) => {
+                self.load_error = Some(format!("Couldn't open {}: {err}", path.as_ref().display()));
+            }
+        }
+    }
+
+    fn add_recent_file(&mut self, path: PathBuf) {
+        let path = path.canonicalize().unwrap_or(path);
+        self.recent_files.retain(|p| *p != path);
+        self.recent_files.insert(0, path);
+        self.recent_files.truncate(MAX_RECENT_FILES);
+    }
+
+    /// Recreates the save's folder inside `folder` the way `psu-packer unpack` does, so
+    /// exporting reproduces what was packed
+    fn export_all(&self, folder: &Path) -> Result<Extracted, String> {
+        let Some(psu) = &self.psu else {
+            return Err("No save is open".to_string());
+        };
+        let psu = psu.to_psu();
+        let root = match save_folder_name(&psu) {
+            Some(name) => folder.join(name),
+            None => folder.to_path_buf(),
+        };
+
+        extract(&psu, &root, &Logger::new(Verbosity::Quiet))
+            .map_err(|err| format!("{}: {err}", root.display()))
+    }
+
+    fn entries(&self) -> &[PSUEntry] {
+        self.psu
+            .as_ref()
+            .map(MappedPSU::entries)
+            .unwrap_or_default()
+    }
+
+    fn contents(&self, index: usize) -> Option<&[u8]> {
+        self.psu.as_ref()?.contents(index)
+    }
+
+    /// Entries to list alongside their index in the archive
+    fn visible_entries(&self) -> Vec<(usize, &PSUEntry)> {
+        let mut entries = self
+            .entries()
+            .iter()
+            .enumerate()
+            .filter(|(_, file)| {
+                !(self.hide_dot_entries && matches!(file.name.as_str(), "." | ".."))
+            })
+            .collect::<Vec<_>>();
+
+        // Without a sort column the entries stay in archive order
+        if let Some(column) = self.sort_column {
+            entries.sort_by(|(_, a), (_, b)| match column {
+                SortColumn::Name => a.name.cmp(&b.name),
+                SortColumn::Size => a.size.cmp(&b.size),
+                SortColumn::Kind => (a.kind as u8).cmp(&(b.kind as u8)),
+            });
+            if self.sort_descending {
+                entries.reverse();
+            }
+        }
+
+        entries
+    }
+
+    fn sort_header(&mut self, ui: &mut Ui, column: SortColumn, label: &str) {
+        let selected = self.sort_column == Some(column);
+        let text = match (selected, self.sort_descending) {
+            (true, false) => format!("{label} ⬆"),
+            (true, true) => format!("{label} ⬇"),
+            (false, _) => label.to_string(),
+        };
+
+        if ui.selectable_label(selected, text).clicked() {
+            if selected {
+                self.sort_descending = !self.sort_descending;
+            } else {
+                self.sort_column = Some(column);
+                self.sort_descending = false;
+            }
+        }
+    }
+
+    fn open_recent_file(&mut self, path: PathBuf) {
+        if path.exists() {
+            self.load_file(path);
+        } else {
+            self.recent_files.retain(|p| *p != path);
+        }
     }
 }
 
 impl eframe::App for MCM {
     fn update(&mut self, ctx: &Context, _frame: &mut Frame) {
         egui::CentralPanel::default().show(ctx, |ui| {
-            if ui.button("Load File").clicked() {
-                // self.load_file("/Users/simonhochrein/Downloads/EMU_PICODRIVE-201.psu")
-                self.load_file("WLE.PSU")
+            ui.horizontal(|ui| {
+                if ui.button("Load File").clicked() {
+                    if let Some(path) = FileDialog::new()
+                        .add_filter("PS2 Save Files", &["psu", "gz"])
+                        .pick_file()
+                    {
+                        self.load_file(path);
+                    }
+                }
+                ui.add_enabled_ui(!self.recent_files.is_empty(), |ui| {
+                    ui.menu_button("Recent", |ui| {
+                        for path in self.recent_files.clone() {
+                            if ui.button(path.display().to_string()).clicked() {
+                                self.open_recent_file(path);
+                                ui.close_menu();
+                            }
+                        }
+                    });
+                });
+                ui.add_enabled_ui(!self.entries().is_empty(), |ui| {
+                    if ui.button("Export All").clicked() {
+                        if let Some(folder) = FileDialog::new().pick_folder() {
+                            self.export_summary = Some(self.export_all(&folder));
+                        }
+                    }
+                });
+                ui.checkbox(&mut self.hide_dot_entries, "Hide . and ..");
+            });
+            if let Some(error) = &self.load_error {
+                ui.colored_label(Color32::RED, error);
+            }
+            match &self.export_summary {
+                Some(Ok(extracted)) => {
+                    ui.label(format!(
+                        "Exported {} file(s), {} skipped",
+                        extracted.written,
+                        extracted.skipped.len()
+                    ));
+                    for name in &extracted.skipped {
+                        ui.colored_label(Color32::RED, format!("{name}: unsafe file name"));
+                    }
+                }
+                Some(Err(err)) => {
+                    ui.colored_label(Color32::RED, format!("Export failed, {err}"));
+                }
+                None => {}
             }
             egui::ScrollArea::vertical().show(ui, |ui| {
-                ui.with_layout(
-                    egui::Layout::top_down_justified(Align::LEFT).with_cross_justify(true),
-                    |ui| {
-                        for file in &self.files {
+                Grid::new("entries")
+                    .num_columns(3)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        self.sort_header(ui, SortColumn::Name, "Name");
+                        self.sort_header(ui, SortColumn::Size, "Size");
+                        self.sort_header(ui, SortColumn::Kind, "Kind");
+                        ui.end_row();
+
+                        for (index, file) in self.visible_entries() {
                             ui.selectable_label(false, &file.name).context_menu(|ui| {
-                                if let Some(contents) = &file.contents {
+                                if let Some(contents) = self.contents(index) {
                                     if ui.button("Export").clicked() {
                                         if let Some(path) =
                                             FileDialog::new().set_file_name(&file.name).save_file()
@@ -91,9 +238,19 @@ impl eframe::App for MCM {
                                     }
                                 }
                             });
+                            match file.kind {
+                                PSUEntryKind::File => {
+                                    ui.label(file.size.to_string());
+                                    ui.label("File");
+                                }
+                                PSUEntryKind::Directory => {
+                                    ui.label("");
+                                    ui.label("Directory");
+                                }
+                            }
+                            ui.end_row();
                         }
-                    },
-                );
+                    });
             });
         });
 
@@ -120,4 +277,80 @@ impl eframe::App for MCM {
             }
         })
     }
+
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        eframe::set_value(storage, RECENT_FILES_KEY, &self.recent_files);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ps2_filetypes::{PSUWriter, DIR_ID, FILE_ID};
+
+    fn entry(id: u16, name: &str, contents: Option<&[u8]>) -> PSUEntry {
+        PSUEntry {
+            id,
+            size: contents.map_or(0, |contents| contents.len() as u32),
+            created: Default::default(),
+            sector: 0,
+            modified: Default::default(),
+            name: name.to_string(),
+            kind: if id == FILE_ID {
+                PSUEntryKind::File
+            } else {
+                PSUEntryKind::Directory
+            },
+            contents: contents.map(<[u8]>::to_vec),
+        }
+    }
+
+    #[test]
+    fn export_all_rebuilds_the_save_folder() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("save.psu");
+        let mut root = entry(DIR_ID, "BASLUS-20001", None);
+        root.size = 4;
+        let psu = PSU {
+            entries: vec![
+                root,
+                entry(DIR_ID, ".", None),
+                entry(DIR_ID, "..", None),
+                entry(FILE_ID, "BOOT.ELF", Some(b"boot")),
+                entry(FILE_ID, "DATA.BIN", Some(&[7; 1500])),
+            ],
+        };
+        std::fs::write(&path, PSUWriter::new(psu).to_bytes().unwrap()).unwrap();
+        let mut mcm = MCM::default();
+        mcm.load_file(&path);
+        assert!(mcm.load_error.is_none());
+
+        let export = temp_dir.path().join("export");
+        let extracted = mcm.export_all(&export).unwrap();
+
+        assert_eq!(extracted.written, 2);
+        assert!(extracted.skipped.is_empty());
+        let folder = export.join("BASLUS-20001");
+        let mut names = std::fs::read_dir(&folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["BOOT.ELF", "DATA.BIN"]);
+        assert_eq!(std::fs::read(folder.join("DATA.BIN")).unwrap(), [7; 1500]);
+    }
+
+    #[test]
+    fn corrupt_file_is_reported_instead_of_opened() {
+        let temp_dir = tempfile::tempdir().unwrap();
+        let path = temp_dir.path().join("broken.psu");
+        std::fs::write(&path, [0x27, 0x84, 0, 0, 9]).unwrap();
+        let mut mcm = MCM::default();
+
+        mcm.load_file(&path);
+
+        assert!(mcm.psu.is_none());
+        assert!(mcm.load_error.is_some());
+        assert!(mcm.recent_files.is_empty());
+    }
 }
@@ -0,0 +1,108 @@
+use ps2_filetypes::sjis::encode_sjis;
+
+/// Spells a file name in ASCII for the memory card, which can't display anything else.
+/// Accented Latin letters lose their accents, every other non-ASCII character becomes `_`.
+pub fn ascii_name(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii() {
+                c.to_string()
+            } else {
+                transliterate(c).to_string()
+            }
+        })
+        .collect()
+}
+
+/// Rewrites an icon.sys title in the characters `encode_sjis` supports, so it can be packed
+/// while staying legible. Accented Latin letters lose their accents, like in `ascii_name`,
+/// and anything else it can't encode becomes a space.
+pub fn sjis_title(title: &str) -> String {
+    title
+        .chars()
+        .map(|c| {
+            if encodable(c) {
+                return c.to_string();
+            }
+            let replacement = transliterate(c);
+            if replacement.chars().all(encodable) {
+                replacement.to_string()
+            } else {
+                " ".to_string()
+            }
+        })
+        .collect()
+}
+
+fn encodable(c: char) -> bool {
+    c.is_ascii() && encode_sjis(&c.to_string()) != [0, 0]
+}
+
+fn transliterate(c: char) -> &'static str {
+    match c {
+        'À'..='Å' | 'Ā' | 'Ă' | 'Ą' => "A",
+        'à'..='å' | 'ā' | 'ă' | 'ą' => "a",
+        'Æ' => "AE",
+        'æ' => "ae",
+        'Ç' | 'Ć' | 'Č' => "C",
+        'ç' | 'ć' | 'č' => "c",
+        'Ð' | 'Ď' => "D",
+        'ð' | 'ď' => "d",
+        'È'..='Ë' | 'Ē' | 'Ę' | 'Ě' => "E",
+        'è'..='ë' | 'ē' | 'ę' | 'ě' => "e",
+        'Ì'..='Ï' | 'Ī' => "I",
+        'ì'..='ï' | 'ī' => "i",
+        'Ł' => "L",
+        'ł' => "l",
+        'Ñ' | 'Ń' | 'Ň' => "N",
+        'ñ' | 'ń' | 'ň' => "n",
+        'Ò'..='Ö' | 'Ø' | 'Ő' => "O",
+        'ò'..='ö' | 'ø' | 'ő' => "o",
+        'Œ' => "OE",
+        'œ' => "oe",
+        'Ř' => "R",
+        'ř' => "r",
+        'Ś' | 'Š' => "S",
+        'ś' | 'š' => "s",
+        'ß' => "ss",
+        'Ť' => "T",
+        'ť' => "t",
+        'Þ' => "TH",
+        'þ' => "th",
+        'Ù'..='Ü' | 'Ů' | 'Ű' => "U",
+        'ù'..='ü' | 'ů' | 'ű' => "u",
+        'Ý' | 'Ÿ' => "Y",
+        'ý' | 'ÿ' => "y",
+        'Ź' | 'Ż' | 'Ž' => "Z",
+        'ź' | 'ż' | 'ž' => "z",
+        _ => "_",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn accents_are_dropped_and_other_characters_replaced() {
+        assert_eq!(ascii_name("résumé.txt"), "resume.txt");
+        assert_eq!(ascii_name("Ærøskøbing ß.dat"), "AEroskobing ss.dat");
+        assert_eq!(ascii_name("セーブ.bin"), "___.bin");
+        assert_eq!(ascii_name("BOOT.ELF"), "BOOT.ELF");
+    }
+
+    #[test]
+    fn accented_titles_become_encodable() {
+        for (title, transliterated) in [
+            ("Pokémon Édition", "Pokemon Edition"),
+            ("Café: Noël", "Cafe: Noel"),
+            ("Straße!", "Strasse "),
+            ("Ñandú (Ø)", "Nandu (O)"),
+            ("セーブ", "   "),
+        ] {
+            let result = sjis_title(title);
+            assert_eq!(result, transliterated, "{title:?}");
+            assert!(result.chars().all(encodable));
+        }
+    }
+}
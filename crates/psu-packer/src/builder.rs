@@ -0,0 +1,364 @@
+use crate::checksum::{Checksums, HashingWriter};
+use crate::logger::{Logger, Verbosity};
+use crate::{
+    check_config, check_icons_packed, check_name, check_output_not_included, check_output_writable,
+    layout_psu, pack_hash, pack_hash_path, resolve_files, write_psu, Config, Error,
+    MAX_NAME_LENGTH,
+};
+use chrono::NaiveDateTime;
+use colored::Colorize;
+use ps2_filetypes::{IconSys, PSUWriter};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+/// Assembles a [`Config`] in code instead of reading it from psu.toml
+///
+/// ```no_run
+/// use psu_packer::PsuBuilder;
+///
+/// let checksums = PsuBuilder::new("APP_HELLO")
+///     .include(["BOOT.ELF", "icon.sys", "icon.icn"])
+///     .timestamp("2024-10-10T10:30:00".parse().unwrap())
+///     .pack("hello", "APP_HELLO.psu")?;
+/// println!("{checksums}");
+/// # Ok::<(), psu_packer::Error>(())
+/// ```
+pub struct PsuBuilder {
+    config: Config,
+    icon_sys: Option<IconSys>,
+}
+
+impl PsuBuilder {
+    /// `name` is the folder name on the memory card
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            config: Config {
+                name: name.into(),
+                ..Default::default()
+            },
+            icon_sys: None,
+        }
+    }
+
+    /// Starts from an existing config, such as one read from psu.toml
+    pub fn from_config(config: Config) -> Self {
+        Self {
+            config,
+            icon_sys: None,
+        }
+    }
+
+    /// Only packs the listed files, can be called more than once
+    pub fn include<I, S>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .include
+            .get_or_insert_with(Vec::new)
+            .extend(files.into_iter().map(Into::into));
+        self
+    }
+
+    /// Packs every file in the folder except the listed ones, can be called more than once
+    pub fn exclude<I, S>(mut self, files: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        self.config
+            .exclude
+            .get_or_insert_with(Vec::new)
+            .extend(files.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn timestamp(mut self, timestamp: NaiveDateTime) -> Self {
+        self.config.timestamp = Some(timestamp);
+        self
+    }
+
+    pub fn directory_mode(mut self, mode: u16) -> Self {
+        self.config.directory_mode = Some(mode);
+        self
+    }
+
+    pub fn file_mode(mut self, file: impl Into<String>, mode: u16) -> Self {
+        self.config.file_modes.insert(file.into(), mode);
+        self
+    }
+
+    /// Stores non-ASCII file names transliterated instead of failing to pack them
+    pub fn ascii_names(mut self, ascii_names: bool) -> Self {
+        self.config.ascii_names = ascii_names;
+        self
+    }
+
+    /// Packs `.psu` files in the folder that would otherwise be left out as earlier exports
+    pub fn pack_psu_files(mut self, pack_psu_files: bool) -> Self {
+        self.config.pack_psu_files = pack_psu_files;
+        self
+    }
+
+    /// Packs this icon.sys instead of the one in the folder, which is left untouched
+    pub fn icon_sys(mut self, icon_sys: IconSys) -> Self {
+        self.icon_sys = Some(icon_sys);
+        self
+    }
+
+    pub fn config(&self) -> &Config {
+        &self.config
+    }
+
+    /// Packs `folder` into `output` without printing anything
+    pub fn pack(
+        self,
+        folder: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+    ) -> Result<Checksums, Error> {
+        self.pack_with_log(folder, output, &Logger::new(Verbosity::Quiet))
+    }
+
+    pub fn pack_with_log(
+        self,
+        folder: impl AsRef<Path>,
+        output: impl AsRef<Path>,
+        log: &Logger,
+    ) -> Result<Checksums, Error> {
+        let (folder, output) = (folder.as_ref(), output.as_ref());
+        let Some(icon_sys) = &self.icon_sys else {
+            return pack_with_config(folder, &self.config, output, log);
+        };
+
+        check_pack_config(&self.config)?;
+        check_output_not_included(folder, &self.config, output)?;
+        check_output_writable(output)?;
+        let icon_sys_bytes = icon_sys.to_bytes()?;
+        let files = resolve_files(folder, &without_icon_sys(&self.config), log)?;
+        check_icons_packed(icon_sys, &files, log)?;
+
+        let psu = layout_psu(
+            &self.config,
+            &files,
+            &[("icon.sys", &icon_sys_bytes)],
+            true,
+            log,
+        )?;
+        let mut writer = HashingWriter::new(BufWriter::new(File::create(output)?));
+        writer.write_all(&PSUWriter::new(psu).to_bytes()?)?;
+        let checksums = writer.finish()?;
+        // The hash only covers files on disk, so it can't vouch for a generated icon.sys
+        remove_pack_hash(output)?;
+
+        Ok(checksums)
+    }
+}
+
+/// The same selection of files minus the folder's own icon.sys
+fn without_icon_sys(config: &Config) -> Config {
+    let mut config = config.clone();
+    match (&mut config.include, &mut config.exclude) {
+        (Some(include), _) => include.retain(|file| file != "icon.sys"),
+        (None, Some(exclude)) => exclude.push("icon.sys".to_string()),
+        (None, None) => config.exclude = Some(vec!["icon.sys".to_string()]),
+    }
+
+    config
+}
+
+/// Packs `folder` into `output` with a config that's already been assembled
+pub fn pack_with_config(
+    folder: &Path,
+    config: &Config,
+    output: &Path,
+    log: &Logger,
+) -> Result<Checksums, Error> {
+    check_pack_config(config)?;
+    check_output_not_included(folder, config, output)?;
+    check_output_writable(output)?;
+    let files = resolve_files(folder, config, log)?;
+
+    // The hash [`pack_if_changed`] left must not vouch for a pack it didn't make
+    remove_pack_hash(output)?;
+
+    write_psu(config, &files, output, log)
+}
+
+/// Like [`pack_with_config`], but returns `None` without writing anything when `output`
+/// exists and nothing that goes into it changed since it was packed. Records the
+/// [`pack_hash`] next to the output to tell.
+pub fn pack_if_changed(
+    folder: &Path,
+    config: &Config,
+    output: &Path,
+    log: &Logger,
+) -> Result<Option<Checksums>, Error> {
+    check_pack_config(config)?;
+    let files = resolve_files(folder, config, &Logger::new(Verbosity::Quiet))?;
+    let hash = pack_hash(config, &files)?;
+    if output.exists()
+        && std::fs::read_to_string(pack_hash_path(output)).is_ok_and(|previous| previous == hash)
+    {
+        log.info(format!(
+            "No changes, {} up to date",
+            output.display().to_string().green()
+        ));
+        return Ok(None);
+    }
+
+    let checksums = pack_with_config(folder, config, output, log)?;
+    std::fs::write(pack_hash_path(output), hash)?;
+
+    Ok(Some(checksums))
+}
+
+fn remove_pack_hash(output: &Path) -> Result<(), Error> {
+    match std::fs::remove_file(pack_hash_path(output)) {
+        Err(err) if err.kind() != std::io::ErrorKind::NotFound => Err(err.into()),
+        _ => Ok(()),
+    }
+}
+
+/// Everything `pack_with_config` checks before touching the disk
+fn check_pack_config(config: &Config) -> Result<(), Error> {
+    check_config(config)?;
+    if !check_name(&config.name) || config.name.len() > MAX_NAME_LENGTH {
+        return Err(Error::NameError);
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ps2_filetypes::{BinReader, PSUParser};
+
+    fn project() -> tempfile::TempDir {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("icon.icn"), [0; 16]).unwrap();
+        std::fs::write(folder.path().join("icon.sys"), b"original").unwrap();
+        folder
+    }
+
+    #[test]
+    fn icon_sys_is_packed_without_touching_the_folder() {
+        let folder = project();
+        let output = folder.path().join("out.psu");
+        let icon_sys = IconSys {
+            title: "BUILT".to_string(),
+            ..Default::default()
+        };
+
+        PsuBuilder::new("BUILT")
+            .include(["icon.icn"])
+            .icon_sys(icon_sys.clone())
+            .pack(folder.path(), &output)
+            .unwrap();
+
+        assert_eq!(
+            std::fs::read(folder.path().join("icon.sys")).unwrap(),
+            b"original"
+        );
+        let psu = PSUParser::read(&std::fs::read(&output).unwrap()).unwrap();
+        let packed = psu
+            .entries
+            .iter()
+            .find(|entry| entry.name == "icon.sys")
+            .and_then(|entry| entry.contents.clone());
+        assert_eq!(packed, Some(icon_sys.to_bytes().unwrap()));
+        assert_eq!(psu.entries[0].size, 4);
+    }
+
+    #[test]
+    fn missing_delete_icon_is_packed_with_a_warning() {
+        let folder = project();
+        let output = folder.path().join("out.psu");
+        let icon_sys = IconSys {
+            icon_delete_file: "delete.icn".to_string(),
+            ..Default::default()
+        };
+
+        PsuBuilder::new("BUILT")
+            .include(["icon.icn"])
+            .icon_sys(icon_sys)
+            .pack(folder.path(), &output)
+            .unwrap();
+
+        assert!(output.exists());
+    }
+
+    #[test]
+    fn invalid_config_fails_before_writing_anything() {
+        let folder = project();
+        let output = folder.path().join("out.psu");
+
+        let result = PsuBuilder::new("not/valid")
+            .icon_sys(IconSys::default())
+            .pack(folder.path(), &output);
+
+        assert!(matches!(result, Err(Error::NameError)));
+        assert!(!output.exists());
+        assert_eq!(
+            std::fs::read(folder.path().join("icon.sys")).unwrap(),
+            b"original"
+        );
+    }
+
+    #[test]
+    fn second_identical_pack_is_a_no_op() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("BOOT.ELF"), [0; 16]).unwrap();
+        let out_dir = tempfile::tempdir().unwrap();
+        let output = out_dir.path().join("out.psu");
+        let config = Config {
+            name: "SAVE".to_string(),
+            ..Default::default()
+        };
+        let log = Logger::new(Verbosity::Quiet);
+
+        assert!(pack_if_changed(folder.path(), &config, &output, &log)
+            .unwrap()
+            .is_some());
+        assert!(pack_hash_path(&output).exists());
+        let packed = std::fs::metadata(&output).unwrap().modified().unwrap();
+        assert!(pack_if_changed(folder.path(), &config, &output, &log)
+            .unwrap()
+            .is_none());
+        assert_eq!(
+            std::fs::metadata(&output).unwrap().modified().unwrap(),
+            packed
+        );
+
+        std::fs::write(folder.path().join("BOOT.ELF"), [1; 16]).unwrap();
+        assert!(pack_if_changed(folder.path(), &config, &output, &log)
+            .unwrap()
+            .is_some());
+    }
+
+    #[test]
+    fn hash_next_to_the_output_is_not_packed() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("BOOT.ELF"), [0; 16]).unwrap();
+        let output = folder.path().join("SAVE.psu");
+        let config = Config {
+            name: "SAVE".to_string(),
+            ..Default::default()
+        };
+        let log = Logger::new(Verbosity::Quiet);
+
+        pack_if_changed(folder.path(), &config, &output, &log).unwrap();
+        assert!(pack_hash_path(&output).exists());
+        pack_with_config(folder.path(), &config, &output, &log).unwrap();
+        assert!(!pack_hash_path(&output).exists());
+        pack_if_changed(folder.path(), &config, &output, &log).unwrap();
+
+        let psu = PSUParser::read(&std::fs::read(&output).unwrap()).unwrap();
+        assert!(psu
+            .entries
+            .iter()
+            .all(|entry| !entry.name.ends_with(".hash")));
+    }
+}
@@ -0,0 +1,134 @@
+use crate::logger::Logger;
+use crate::{layout_psu, resolve_config, resolve_files, ConfigOverrides, Error};
+use memcard::fat::{format, Memcard, CLUSTERS_8MB, MAGIC};
+use ps2_filetypes::{BinReader, PSUEntryKind, PSUParser, PSU};
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+
+/// How one save fared when it was written to a card
+pub struct CardSave {
+    pub source: PathBuf,
+    /// Folder name on the card, `None` if the save couldn't be read
+    pub name: Option<String>,
+    /// Total size of the save's files
+    pub size: u64,
+    pub result: Result<(), Error>,
+}
+
+impl CardSave {
+    /// The save was fine but the card didn't have room left for it
+    pub fn did_not_fit(&self) -> bool {
+        matches!(&self.result, Err(Error::IOError(err)) if err.kind() == ErrorKind::StorageFull)
+    }
+}
+
+/// Opens the card image at `path` to add saves to, or a blank 8MB card if there's none yet
+pub fn open_card(path: &Path) -> Result<Memcard, Error> {
+    let data = if path.exists() {
+        std::fs::read(path)?
+    } else {
+        format(CLUSTERS_8MB)
+    };
+    if !data.starts_with(MAGIC) {
+        return Err(Error::IOError(std::io::Error::new(
+            ErrorKind::InvalidData,
+            format!("{} isn't a PS2 memory card image", path.display()),
+        )));
+    }
+
+    Ok(Memcard::new(data)?)
+}
+
+/// Writes each PSU or project folder in `sources` to the card, in order. Saves that can't be
+/// read or don't fit in the space that's left are skipped, the rest are still written.
+pub fn build_card(card: &mut Memcard, sources: &[PathBuf], log: &Logger) -> Vec<CardSave> {
+    sources
+        .iter()
+        .map(|source| match read_save(source, log) {
+            Ok((name, files)) => CardSave {
+                source: source.clone(),
+                size: files.iter().map(|(_, data)| data.len() as u64).sum(),
+                result: card.write_save(&name, &files).map_err(Error::from),
+                name: Some(name),
+            },
+            Err(err) => CardSave {
+                source: source.clone(),
+                name: None,
+                size: 0,
+                result: Err(err),
+            },
+        })
+        .collect()
+}
+
+/// A save's folder name, and the name and contents of each of its files
+type SaveFiles = (String, Vec<(String, Vec<u8>)>);
+
+/// A project folder is laid out the same way packing it would, a file is read as a PSU
+fn read_save(source: &Path, log: &Logger) -> Result<SaveFiles, Error> {
+    let psu = if source.is_dir() {
+        let config = resolve_config(source, &ConfigOverrides::default(), log)?;
+        let files = resolve_files(source, &config, log)?;
+        layout_psu(&config, &files, &[], true, log)?
+    } else {
+        PSUParser::read(&std::fs::read(source)?)?
+    };
+
+    Ok(save_files(psu))
+}
+
+fn save_files(psu: PSU) -> SaveFiles {
+    let mut name = String::new();
+    let mut files = vec![];
+    for entry in psu.entries {
+        match entry.kind {
+            PSUEntryKind::Directory if name.is_empty() => name = entry.name,
+            PSUEntryKind::File => files.push((entry.name, entry.contents.unwrap_or_default())),
+            _ => {}
+        }
+    }
+
+    (name, files)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::Verbosity;
+
+    fn project(parent: &Path, name: &str, size: usize) -> PathBuf {
+        let folder = parent.join(name);
+        std::fs::create_dir(&folder).unwrap();
+        let config = format!("[config]\nname = \"{name}\"\nexclude = [\"psu.toml\"]\n");
+        std::fs::write(folder.join("psu.toml"), config).unwrap();
+        std::fs::write(folder.join("DATA.BIN"), vec![name.len() as u8; size]).unwrap();
+        folder
+    }
+
+    #[test]
+    fn builds_a_card_with_two_saves_and_reports_the_one_that_does_not_fit() {
+        let parent = tempfile::tempdir().unwrap();
+        // 38 allocatable clusters of 1KB
+        let mut card = Memcard::new(format(64)).unwrap();
+        let sources = [
+            project(parent.path(), "FIRST", 3000),
+            project(parent.path(), "SECOND_SAVE", 5000),
+            project(parent.path(), "TOO_BIG", 64 * 1024),
+        ];
+
+        let saves = build_card(&mut card, &sources, &Logger::new(Verbosity::Quiet));
+
+        assert!(saves[0].result.is_ok() && saves[1].result.is_ok());
+        assert!(saves[2].did_not_fit());
+        assert_eq!(saves[2].size, 64 * 1024);
+        let saves = Memcard::new(card.into_bytes()).unwrap().saves().unwrap();
+        let written = saves
+            .iter()
+            .map(|save| (save.name.as_str(), save.files[0].contents.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            written,
+            [("FIRST", vec![5; 3000]), ("SECOND_SAVE", vec![11; 5000])]
+        );
+    }
+}
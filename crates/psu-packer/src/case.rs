@@ -0,0 +1,94 @@
+use crate::{Config, Error};
+use std::path::Path;
+use toml_edit::DocumentMut;
+
+/// Include and exclude entries whose case differs from the file on disk, as `(listed, actual)`.
+///
+/// Case-insensitive filesystems still find the file, but the PSU stores the name as listed
+/// and the PS2 doesn't ignore case. Exclude entries never match such files at all.
+pub fn case_mismatches(folder: &Path, config: &Config) -> Vec<(String, String)> {
+    let on_disk = std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+
+    config
+        .include
+        .iter()
+        .chain(config.exclude.iter())
+        .flatten()
+        .filter(|listed| !on_disk.contains(listed))
+        .filter_map(|listed| {
+            let actual = on_disk
+                .iter()
+                .find(|actual| actual.eq_ignore_ascii_case(listed))?;
+            Some((listed.clone(), actual.clone()))
+        })
+        .collect()
+}
+
+/// Rewrites the include and exclude entries in psu.toml to match the case on disk,
+/// leaving the rest of the file untouched. Returns how many entries changed.
+pub fn correct_case(folder: &Path, config: &Config) -> Result<usize, Error> {
+    let mismatches = case_mismatches(folder, config);
+    if mismatches.is_empty() {
+        return Ok(0);
+    }
+
+    let config_file = folder.join("psu.toml");
+    let mut document = std::fs::read_to_string(&config_file)?
+        .parse::<DocumentMut>()
+        .map_err(std::io::Error::other)?;
+
+    let mut corrected = 0;
+    for key in ["include", "exclude"] {
+        let Some(files) = document["config"][key].as_array_mut() else {
+            continue;
+        };
+        for value in files.iter_mut() {
+            let Some((_, actual)) = mismatches
+                .iter()
+                .find(|(listed, _)| value.as_str() == Some(listed))
+            else {
+                continue;
+            };
+            // Keeps the whitespace and trailing comment around the old value
+            let decor = value.decor().clone();
+            *value = actual.as_str().into();
+            *value.decor_mut() = decor;
+            corrected += 1;
+        }
+    }
+    std::fs::write(&config_file, document.to_string())?;
+
+    Ok(corrected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::read_config;
+
+    #[test]
+    fn differently_cased_entry_is_reported_and_corrected() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("boot.elf"), []).unwrap();
+        std::fs::write(folder.path().join("DATA.BIN"), []).unwrap();
+        let toml = "[config]\nname = \"SAVE\"\ninclude = [\"BOOT.ELF\", \"DATA.BIN\"] # both\n";
+        std::fs::write(folder.path().join("psu.toml"), toml).unwrap();
+        let (_, config) = read_config(folder.path()).unwrap().unwrap();
+
+        assert_eq!(
+            case_mismatches(folder.path(), &config),
+            [("BOOT.ELF".to_string(), "boot.elf".to_string())]
+        );
+
+        assert_eq!(correct_case(folder.path(), &config).unwrap(), 1);
+        assert_eq!(
+            std::fs::read_to_string(folder.path().join("psu.toml")).unwrap(),
+            "[config]\nname = \"SAVE\"\ninclude = [\"boot.elf\", \"DATA.BIN\"] # both\n"
+        );
+    }
+}
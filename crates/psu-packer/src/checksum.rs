@@ -0,0 +1,80 @@
+use sha2::{Digest, Sha256};
+use std::fmt::Write as _;
+use std::io::Write;
+
+/// Checksums to publish alongside a PSU
+pub struct Checksums {
+    pub crc32: u32,
+    pub sha256: [u8; 32],
+}
+
+impl std::fmt::Display for Checksums {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let sha256 = self.sha256.iter().fold(String::new(), |mut hex, byte| {
+            let _ = write!(hex, "{byte:02x}");
+            hex
+        });
+        write!(f, "CRC32   {:08x}\nSHA-256 {sha256}", self.crc32)
+    }
+}
+
+/// Hashes everything written through it, so the checksums match the bytes on disk
+pub struct HashingWriter<W: Write> {
+    inner: W,
+    crc32: crc32fast::Hasher,
+    sha256: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub fn new(inner: W) -> Self {
+        Self {
+            inner,
+            crc32: crc32fast::Hasher::new(),
+            sha256: Sha256::new(),
+        }
+    }
+
+    pub fn finish(mut self) -> std::io::Result<Checksums> {
+        self.inner.flush()?;
+
+        Ok(Checksums {
+            crc32: self.crc32.finalize(),
+            sha256: self.sha256.finalize().into(),
+        })
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.crc32.update(&buf[..written]);
+        self.sha256.update(&buf[..written]);
+
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // The check values both algorithms are specified with
+    #[test]
+    fn known_vectors_match_across_writes() {
+        let mut writer = HashingWriter::new(Vec::new());
+        writer.write_all(b"1234").unwrap();
+        writer.write_all(b"56789").unwrap();
+
+        let checksums = writer.finish().unwrap();
+
+        assert_eq!(
+            checksums.to_string(),
+            "CRC32   cbf43926\n\
+             SHA-256 15e2b0d3c33891ebb0f1ef609ec419420c20e320ce94c65fbc8c3312448eb225"
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::logger::{Logger, Verbosity};
+use crate::{
+    check_name, check_output_writable, read_config, resolve_files, write_psu, Config, Error,
+    MAX_NAME_LENGTH,
+};
+use ps2_filetypes::{decompress, BinReader, PSUEntryKind, PSUParser};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// What a delta PSU changes about its baseline, written next to it as `<output>.delta.toml`.
+/// Only `changed` and `added` are in the delta, so it has to be copied over the baseline
+/// save on the memory card, after which the `removed` files should be deleted.
+#[derive(Debug, Default, Deserialize, Serialize)]
+pub struct Delta {
+    /// Folder name of the baseline save the delta applies to
+    pub baseline: String,
+    pub changed: Vec<String>,
+    pub added: Vec<String>,
+    pub removed: Vec<String>,
+}
+
+impl Delta {
+    pub fn is_empty(&self) -> bool {
+        self.changed.is_empty() && self.added.is_empty() && self.removed.is_empty()
+    }
+}
+
+/// Packs the files in `folder` whose contents differ from the `baseline` PSU, or that it
+/// doesn't have, into `output`. Files are picked by psu.toml when the folder has one and
+/// compared under the names they'd be stored as.
+pub fn pack_delta(baseline: &Path, folder: &Path, output: &Path) -> Result<Delta, Error> {
+    let log = Logger::new(Verbosity::Quiet);
+    let baseline = PSUParser::read(&decompress(&std::fs::read(baseline)?)?)?;
+    let baseline_name = baseline
+        .entries
+        .iter()
+        .find(|entry| matches!(entry.kind, PSUEntryKind::Directory))
+        .map(|entry| entry.name.clone())
+        .unwrap_or_default();
+    let config = match read_config(folder)? {
+        Some((_, config)) => config,
+        None => Config {
+            name: baseline_name.clone(),
+            ..Default::default()
+        },
+    };
+    if !check_name(&config.name) || config.name.len() > MAX_NAME_LENGTH {
+        return Err(Error::NameError);
+    }
+    check_output_writable(output)?;
+
+    let mut delta = Delta {
+        baseline: baseline_name,
+        ..Default::default()
+    };
+    let mut packed = Vec::<PathBuf>::new();
+    let files = resolve_files(folder, &config, &log)?;
+    for file in &files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        let entry_name = config.entry_name(&name);
+        let original = baseline
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.kind, PSUEntryKind::File))
+            .find(|entry| entry.name == entry_name);
+        let contents = std::fs::read(file)?;
+        match original {
+            Some(entry) if entry.contents.as_ref() == Some(&contents) => continue,
+            Some(_) => delta.changed.push(entry_name),
+            None => delta.added.push(entry_name),
+        }
+        packed.push(file.clone());
+    }
+    delta.removed = baseline
+        .entries
+        .iter()
+        .filter(|entry| matches!(entry.kind, PSUEntryKind::File))
+        .filter(|entry| {
+            !files.iter().any(|file| {
+                let name = file.file_name().unwrap_or_default().to_string_lossy();
+                config.entry_name(&name) == entry.name
+            })
+        })
+        .map(|entry| entry.name.clone())
+        .collect();
+
+    // The files were already checked as a whole, the icons icon.sys references may well be
+    // unchanged and only in the baseline
+    let delta_config = Config {
+        file_modes: config
+            .file_modes
+            .iter()
+            .filter(|(file, _)| packed.iter().any(|path| path.ends_with(file)))
+            .map(|(file, &mode)| (file.clone(), mode))
+            .collect(),
+        ..config
+    };
+    write_psu(&delta_config, &packed, output, &log)?;
+    std::fs::write(
+        delta_manifest_path(output),
+        toml::to_string(&delta).expect("Delta is always representable as TOML"),
+    )?;
+
+    Ok(delta)
+}
+
+/// Where `pack_delta` writes the manifest for `output`
+pub fn delta_manifest_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".delta.toml");
+    PathBuf::from(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pack_with_config;
+
+    #[test]
+    fn only_the_changed_file_is_packed() {
+        let folder = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("BOOT.ELF"), b"boot").unwrap();
+        std::fs::write(folder.path().join("DATA.BIN"), b"first").unwrap();
+        let config = Config {
+            name: "BASLUS-20001".to_string(),
+            ..Default::default()
+        };
+        let baseline = output.path().join("baseline.psu");
+        pack_with_config(
+            folder.path(),
+            &config,
+            &baseline,
+            &Logger::new(Verbosity::Quiet),
+        )
+        .unwrap();
+
+        std::fs::write(folder.path().join("DATA.BIN"), b"second").unwrap();
+        let delta_path = output.path().join("delta.psu");
+        let delta = pack_delta(&baseline, folder.path(), &delta_path).unwrap();
+
+        assert_eq!(delta.baseline, "BASLUS-20001");
+        assert_eq!(delta.changed, ["DATA.BIN"]);
+        assert!(delta.added.is_empty() && delta.removed.is_empty());
+        let psu = PSUParser::read(&std::fs::read(&delta_path).unwrap()).unwrap();
+        let files = psu
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.kind, PSUEntryKind::File))
+            .map(|entry| (entry.name.as_str(), entry.contents.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(files, [("DATA.BIN", b"second".to_vec())]);
+        let manifest: Delta =
+            toml::from_str(&std::fs::read_to_string(delta_manifest_path(&delta_path)).unwrap())
+                .unwrap();
+        assert_eq!(manifest.changed, ["DATA.BIN"]);
+    }
+}
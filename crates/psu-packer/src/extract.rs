@@ -0,0 +1,160 @@
+use crate::logger::Logger;
+use crate::{is_safe_name, Error};
+use chrono::{Local, NaiveDateTime, TimeZone};
+use colored::Colorize;
+use ps2_filetypes::{PSUEntryKind, PSU};
+use std::fs::{File, FileTimes};
+use std::path::Path;
+use std::time::SystemTime;
+
+/// What [`extract`] wrote
+pub struct Extracted {
+    pub written: usize,
+    /// Files left out because their names would escape the folder
+    pub skipped: Vec<String>,
+}
+
+/// The save's folder name, the first directory entry, if it's safe to extract to
+pub fn save_folder_name(psu: &PSU) -> Option<&str> {
+    psu.entries
+        .iter()
+        .find(|entry| matches!(entry.kind, PSUEntryKind::Directory))
+        .map(|entry| entry.name.as_str())
+        .filter(|name| is_safe_name(name))
+}
+
+/// Writes every file in the PSU into `output`, restoring their timestamps and skipping names
+/// that would escape it. The directory entries, `.` and `..` included, only describe `output`.
+pub fn extract(psu: &PSU, output: &Path, log: &Logger) -> Result<Extracted, Error> {
+    std::fs::create_dir_all(output)?;
+
+    let mut extracted = Extracted {
+        written: 0,
+        skipped: vec![],
+    };
+    for entry in &psu.entries {
+        let PSUEntryKind::File = entry.kind else {
+            continue;
+        };
+        if !is_safe_name(&entry.name) {
+            log.warn(format!(
+                "{} {}",
+                "Skipping unsafe name".yellow(),
+                entry.name
+            ));
+            extracted.skipped.push(entry.name.clone());
+            continue;
+        }
+
+        let path = output.join(&entry.name);
+        std::fs::write(&path, entry.contents.as_deref().unwrap_or_default())?;
+
+        let times = FileTimes::new().set_modified(to_system_time(entry.modified));
+        #[cfg(target_os = "windows")]
+        let times = {
+            use std::os::windows::fs::FileTimesExt;
+            times.set_created(to_system_time(entry.created))
+        };
+        #[cfg(target_os = "macos")]
+        let times = {
+            use std::os::macos::fs::FileTimesExt;
+            times.set_created(to_system_time(entry.created))
+        };
+        File::options().write(true).open(&path)?.set_times(times)?;
+        extracted.written += 1;
+
+        log.info(format!("+ {} {}", "Extracting", entry.name.green()));
+        log.verbose(format!(
+            "  created {}, modified {}",
+            entry.created, entry.modified
+        ));
+    }
+
+    Ok(extracted)
+}
+
+/// Timestamps in a PSU are local time, the inverse of `convert_timestamp`
+fn to_system_time(timestamp: NaiveDateTime) -> SystemTime {
+    Local
+        .from_local_datetime(&timestamp)
+        .earliest()
+        .map(SystemTime::from)
+        .unwrap_or(SystemTime::UNIX_EPOCH)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::Verbosity;
+    use ps2_filetypes::{PSUEntry, DIR_ID, FILE_ID};
+
+    fn entry(id: u16, name: &str, modified: NaiveDateTime) -> PSUEntry {
+        let file = id == FILE_ID;
+        PSUEntry {
+            id,
+            size: if file { 4 } else { 0 },
+            created: modified,
+            sector: 0,
+            modified,
+            name: name.to_string(),
+            kind: if file {
+                PSUEntryKind::File
+            } else {
+                PSUEntryKind::Directory
+            },
+            contents: file.then(|| b"data".to_vec()),
+        }
+    }
+
+    #[test]
+    fn extracted_files_keep_their_modified_time() {
+        let modified =
+            NaiveDateTime::parse_from_str("2003-11-05 21:30:15", "%Y-%m-%d %H:%M:%S").unwrap();
+        let psu = PSU {
+            entries: vec![
+                entry(FILE_ID, "DATA.BIN", modified),
+                entry(FILE_ID, "../ESCAPE", modified),
+            ],
+        };
+        let output = tempfile::tempdir().unwrap();
+        let folder = output.path().join("save");
+
+        let extracted = extract(&psu, &folder, &Logger::new(Verbosity::Quiet)).unwrap();
+
+        let metadata = std::fs::metadata(folder.join("DATA.BIN")).unwrap();
+        assert_eq!(metadata.modified().unwrap(), to_system_time(modified));
+        assert!(!output.path().join("ESCAPE").exists());
+        assert_eq!(extracted.written, 1);
+        assert_eq!(extracted.skipped, ["../ESCAPE"]);
+    }
+
+    #[test]
+    fn directory_entries_only_name_the_folder() {
+        let modified = NaiveDateTime::default();
+        let psu = PSU {
+            entries: vec![
+                entry(DIR_ID, "BASLUS-20001", modified),
+                entry(DIR_ID, ".", modified),
+                entry(DIR_ID, "..", modified),
+                entry(FILE_ID, "icon.sys", modified),
+                entry(FILE_ID, "BOOT.ELF", modified),
+            ],
+        };
+        let output = tempfile::tempdir().unwrap();
+        let folder = output.path().join(save_folder_name(&psu).unwrap());
+
+        extract(&psu, &folder, &Logger::new(Verbosity::Quiet)).unwrap();
+
+        let mut names = std::fs::read_dir(&folder)
+            .unwrap()
+            .map(|entry| entry.unwrap().file_name().to_string_lossy().to_string())
+            .collect::<Vec<_>>();
+        names.sort();
+        assert_eq!(names, ["BOOT.ELF", "icon.sys"]);
+        assert!(output.path().join("BASLUS-20001").is_dir());
+        let unsafe_root = PSU {
+            entries: vec![entry(DIR_ID, "..", modified)],
+        };
+        assert_eq!(save_folder_name(&unsafe_root), None);
+    }
+}
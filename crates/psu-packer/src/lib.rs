@@ -0,0 +1,1251 @@
+use chrono::{DateTime, Local, NaiveDateTime};
+use colored::Colorize;
+use ps2_filetypes::{
+    IconSys, PSUEntry, PSUEntryKind, PSUWriter, DIR_ID, FILE_ID, MODE_DIRECTORY, MODE_FILE, PSU,
+};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+mod ascii;
+mod builder;
+mod card;
+mod case;
+pub mod checksum;
+mod delta;
+mod extract;
+pub mod logger;
+mod thumbnail;
+pub mod validate;
+
+pub use ascii::{ascii_name, sjis_title};
+pub use builder::{pack_if_changed, pack_with_config, PsuBuilder};
+pub use card::{build_card, open_card, CardSave};
+pub use case::{case_mismatches, correct_case};
+pub use delta::{delta_manifest_path, pack_delta, Delta};
+pub use extract::{extract, save_folder_name, Extracted};
+pub use thumbnail::{placeholder_thumbnail, psu_thumbnail, MAX_THUMBNAIL_SIZE};
+use checksum::{Checksums, HashingWriter};
+use logger::Logger;
+
+/// Saves bigger than this are streamed from disk rather than assembled in memory
+pub const STREAMING_THRESHOLD: u64 = 16 * 1024 * 1024;
+
+#[derive(Debug, Default, Clone, Deserialize, Serialize)]
+pub struct Config {
+    /// Folder name on the memory card
+    pub name: String,
+    #[serde(default, with = "date_format")]
+    pub timestamp: Option<NaiveDateTime>,
+    pub include: Option<Vec<String>>,
+    pub exclude: Option<Vec<String>>,
+    /// Mode bits for the save's directory, `DIR_ID` unless set
+    pub directory_mode: Option<u16>,
+    /// Mode bits for individual files, anything not listed gets `FILE_ID`
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_modes: BTreeMap<String, u16>,
+    /// Stores non-ASCII file names transliterated instead of refusing to pack them,
+    /// the files on disk keep their names
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub ascii_names: bool,
+    /// Packs `.psu` files found in the folder, which are otherwise left out unless listed
+    /// in `include` since they're usually earlier exports of the same save
+    #[serde(default, skip_serializing_if = "is_false")]
+    pub pack_psu_files: bool,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+impl Config {
+    /// The config that packs exactly `files`, so the PSU can be rebuilt the same way
+    /// even after the folder gains or loses files
+    pub fn effective(&self, files: &[PathBuf]) -> Config {
+        Config {
+            timestamp: Some(self.timestamp.unwrap_or_default()),
+            include: Some(
+                files
+                    .iter()
+                    .filter_map(|file| file.file_name())
+                    .map(|name| name.to_string_lossy().to_string())
+                    .collect(),
+            ),
+            exclude: None,
+            ..self.clone()
+        }
+    }
+
+    /// The name `file_name` is stored under in the PSU
+    pub fn entry_name(&self, file_name: &str) -> String {
+        if self.ascii_names {
+            ascii_name(file_name)
+        } else {
+            file_name.to_string()
+        }
+    }
+
+    /// Serializes the config in the psu.toml format
+    pub fn to_toml_string(&self) -> String {
+        toml::to_string(&ConfigFile {
+            config: self.clone(),
+        })
+        .expect("Config is always representable as TOML")
+    }
+}
+
+mod date_format {
+    use chrono::NaiveDateTime;
+    use serde::{self, Deserialize, Deserializer, Serializer};
+
+    const FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+    pub fn serialize<S>(timestamp: &Option<NaiveDateTime>, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match timestamp {
+            Some(timestamp) => serializer.serialize_str(&timestamp.format(FORMAT).to_string()),
+            None => serializer.serialize_none(),
+        }
+    }
+
+    pub fn deserialize<'de, D>(deserialize: D) -> Result<Option<NaiveDateTime>, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let s: Option<String> = Option::deserialize(deserialize)?;
+        if let Some(s) = s {
+            Ok(Some(
+                NaiveDateTime::parse_from_str(&s, FORMAT).map_err(serde::de::Error::custom)?,
+            ))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Serialize)]
+struct ConfigFile {
+    config: Config,
+}
+
+pub fn check_name(name: &str) -> bool {
+    for c in name.chars() {
+        if !matches!(c, 'a'..='z'|'A'..='Z'|'0'..='9'|'_'|'-'|' ') {
+            return false;
+        }
+    }
+    true
+}
+
+/// Whether a name read from an archive can be joined onto a folder without escaping it:
+/// a single plain component that isn't empty, `.`, `..` or a root
+pub fn is_safe_name(name: &str) -> bool {
+    let mut components = Path::new(name).components();
+    matches!(components.next(), Some(Component::Normal(_)))
+        && components.next().is_none()
+        && !name.contains(['/', '\\'])
+}
+
+/// Longest directory name a memory card entry can hold, the same limit the card writer checks
+pub use memcard::fat::MAX_NAME_LENGTH;
+
+/// Replaces every character `check_name` rejects with `_` and trims to the card's limit
+pub fn sanitize_name(name: &str) -> String {
+    let sanitized = name
+        .chars()
+        .map(|c| if check_name(&c.to_string()) { c } else { '_' })
+        .take(MAX_NAME_LENGTH)
+        .collect::<String>();
+
+    if sanitized.trim().is_empty() {
+        "_".to_string()
+    } else {
+        sanitized
+    }
+}
+
+/// Names of the subfolders with their own psu.toml, which usually means the wrong level
+/// was picked
+pub fn nested_projects(folder: &Path) -> Vec<String> {
+    let mut nested = std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().join("psu.toml").is_file())
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .collect::<Vec<_>>();
+    nested.sort();
+
+    nested
+}
+
+pub fn warn_nested_projects(folder: &Path, log: &Logger) {
+    for child in nested_projects(folder) {
+        log.warn(format!(
+            "{} {} {}",
+            "Subfolder".yellow(),
+            child,
+            "has its own psu.toml, did you mean to pack that instead?".yellow()
+        ));
+    }
+}
+
+/// Fails before any files are read if the output can't be written,
+/// rather than after the whole save has been packed
+pub fn check_output_writable(output: &Path) -> Result<(), Error> {
+    let directory = match output.parent() {
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let not_writable = |err| Error::OutputNotWritable(directory.to_path_buf(), err);
+
+    std::fs::create_dir_all(directory).map_err(not_writable)?;
+    if output.exists() {
+        // Opening for writing without truncating leaves the old PSU intact
+        File::options()
+            .write(true)
+            .open(output)
+            .map_err(|err| Error::OutputNotWritable(output.to_path_buf(), err))?;
+    } else {
+        let probe = directory.join(format!(".psu-packer-{}", std::process::id()));
+        File::create(&probe).map_err(not_writable)?;
+        std::fs::remove_file(probe)?;
+    }
+
+    Ok(())
+}
+
+/// Reads and checks psu.toml, returning the raw file alongside the parsed config.
+/// The name is left to the caller to check, since it can be overridden or sanitized.
+pub fn read_config(folder: &Path) -> Result<Option<(String, Config)>, Error> {
+    let config = parse_config(folder)?;
+    if let Some((_, config)) = &config {
+        check_config(config)?;
+    }
+
+    Ok(config)
+}
+
+/// Reads psu.toml without checking that the options make sense together
+pub(crate) fn parse_config(folder: &Path) -> Result<Option<(String, Config)>, Error> {
+    let config_file = folder.join("psu.toml");
+    if !config_file.exists() {
+        return Ok(None);
+    }
+
+    let str = std::fs::read_to_string(&config_file)?;
+    let config = toml::from_str::<ConfigFile>(&str)
+        .map_err(|err| {
+            Error::Config(match err.span() {
+                Some(span) => {
+                    let line = str[..span.start].matches('\n').count() + 1;
+                    format!("line {line}: {}", err.message())
+                }
+                None => err.message().to_string(),
+            })
+        })?
+        .config;
+
+    Ok(Some((str, config)))
+}
+
+/// Command line options that take precedence over psu.toml
+#[derive(Debug, Default, Clone)]
+pub struct ConfigOverrides {
+    /// Folder name on the memory card
+    pub name: Option<String>,
+    /// Replaces invalid characters in the name with `_` instead of failing
+    pub sanitize_name: bool,
+    pub pack_psu_files: bool,
+    pub ascii_names: bool,
+}
+
+/// Reads psu.toml, applies `overrides` and checks the name, the way every pack resolves its
+/// config. Fails if the folder has no psu.toml.
+pub fn resolve_config(
+    folder: &Path,
+    overrides: &ConfigOverrides,
+    log: &Logger,
+) -> Result<Config, Error> {
+    let Some((_, mut config)) = read_config(folder)? else {
+        return Err(Error::MissingConfig(folder.to_path_buf()));
+    };
+    if let Some(name) = &overrides.name {
+        config.name = name.clone();
+    }
+    config.pack_psu_files |= overrides.pack_psu_files;
+    config.ascii_names |= overrides.ascii_names;
+
+    if !check_name(&config.name) || config.name.len() > MAX_NAME_LENGTH {
+        if !overrides.sanitize_name {
+            return Err(Error::NameError);
+        }
+        let sanitized = sanitize_name(&config.name);
+        log.warn(format!(
+            "{} {} -> {}",
+            "Sanitized name".yellow(),
+            config.name,
+            sanitized.green()
+        ));
+        config.name = sanitized;
+    }
+
+    Ok(config)
+}
+
+/// Checks everything in the config except the name, which may still be overridden or sanitized
+pub fn check_config(config: &Config) -> Result<(), Error> {
+    if config.include.is_some() && config.exclude.is_some() {
+        return Err(Error::IncludeExcludeError);
+    }
+
+    if let Some(mode) = config.directory_mode {
+        if mode & MODE_DIRECTORY == 0 || mode & MODE_FILE != 0 {
+            return Err(Error::InvalidMode(config.name.clone(), mode));
+        }
+    }
+    for (file, &mode) in &config.file_modes {
+        if mode & MODE_FILE == 0 || mode & MODE_DIRECTORY != 0 {
+            return Err(Error::InvalidMode(file.clone(), mode));
+        }
+    }
+
+    if let Some(file) = config
+        .include
+        .iter()
+        .flatten()
+        .find(|file| file.eq_ignore_ascii_case("psu.toml"))
+    {
+        return Err(Error::SelfInclude(file.clone()));
+    }
+
+    Ok(())
+}
+
+/// Packing the output into itself would grow it every time it's rebuilt
+pub fn check_output_not_included(
+    folder: &Path,
+    config: &Config,
+    output: &Path,
+) -> Result<(), Error> {
+    let Ok(output) = output.canonicalize() else {
+        // Nothing to include yet
+        return Ok(());
+    };
+
+    for file in config.include.iter().flatten() {
+        if folder
+            .join(file)
+            .canonicalize()
+            .is_ok_and(|path| path == output)
+        {
+            return Err(Error::SelfInclude(file.clone()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Resolves the files the config selects, in the order they'll be packed
+pub fn resolve_files(folder: &Path, config: &Config, log: &Logger) -> Result<Vec<PathBuf>, Error> {
+    let files = if let Some(include) = &config.include {
+        // Saves can't hold subfolders, so there's no way to pack what's inside
+        if let Some(dir) = include.iter().find(|file| folder.join(file).is_dir()) {
+            return Err(Error::DirectoryInclude(dir.clone()));
+        }
+        include
+            .iter()
+            .filter_map(|file| {
+                if file.contains(|c| matches!(c, '\\' | '/')) {
+                    log.warn(format!(
+                        "{} {} {}",
+                        "File".dimmed(),
+                        file.dimmed(),
+                        "exists in subfolder, skipping".dimmed()
+                    ));
+                    None
+                } else if !folder.join(file).exists() {
+                    log.warn(format!(
+                        "{} {} {}",
+                        "File".dimmed(),
+                        file.dimmed(),
+                        "does not exist, skipping".dimmed()
+                    ));
+                    None
+                } else {
+                    Some(folder.join(file))
+                }
+            })
+            .collect::<Vec<_>>()
+    } else if let Some(exclude) = &config.exclude {
+        std::fs::read_dir(folder)?
+            .into_iter()
+            .flatten()
+            .filter_map(|d| {
+                if !exclude.contains(&d.file_name().to_str().unwrap().to_string()) {
+                    Some(d.path())
+                } else {
+                    log.verbose(format!(
+                        "- {} {}",
+                        "Excluding",
+                        d.file_name().to_string_lossy().dimmed()
+                    ));
+                    None
+                }
+            })
+            .collect::<Vec<_>>()
+    } else {
+        // Include all
+        std::fs::read_dir(folder)?
+            .into_iter()
+            .flatten()
+            .map(|d| d.path())
+            .collect::<Vec<_>>()
+    };
+    // read_dir's order depends on the filesystem, packs have to come out the same everywhere
+    let mut files = files;
+    if config.include.is_none() {
+        files.sort_by(|a, b| a.file_name().cmp(&b.file_name()));
+    }
+    // Configs and hashes written next to a PSU describe it, they're never part of the save
+    let files = files
+        .into_iter()
+        .filter(|file| config.include.is_some() || !is_written_config(file))
+        .filter(|file| config.include.is_some() || !is_pack_hash(file))
+        .filter(|file| {
+            if config.include.is_some() || config.pack_psu_files || !is_psu(file) {
+                return true;
+            }
+            log.info(format!(
+                "{} {}",
+                file.display().to_string().dimmed(),
+                "is a PSU, skipping (see --pack-psu-files)".dimmed()
+            ));
+            false
+        })
+        .collect::<Vec<_>>();
+    let files = filter_files(&files, log);
+    check_file_names(&files, config)?;
+    check_icon_references(&files, log)?;
+
+    Ok(files)
+}
+
+/// Where `--write-config` puts the effective config for `output`
+pub fn written_config_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".toml");
+    PathBuf::from(path)
+}
+
+/// Where packing records the [`pack_hash`] of `output`
+pub fn pack_hash_path(output: &Path) -> PathBuf {
+    let mut path = output.as_os_str().to_owned();
+    path.push(".hash");
+    PathBuf::from(path)
+}
+
+fn is_psu(file: &Path) -> bool {
+    file.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy().to_lowercase();
+        name.ends_with(".psu") || name.ends_with(".psu.gz")
+    })
+}
+
+fn is_written_config(file: &Path) -> bool {
+    file.file_name()
+        .is_some_and(|name| name.to_string_lossy().ends_with(".psu.toml"))
+}
+
+fn is_pack_hash(file: &Path) -> bool {
+    file.file_name().is_some_and(|name| {
+        let name = name.to_string_lossy().to_lowercase();
+        name.ends_with(".psu.hash") || name.ends_with(".psu.gz.hash")
+    })
+}
+
+/// Lays out the PSU for the resolved files and serializes it
+pub fn build_psu(config: &Config, files: &[PathBuf], log: &Logger) -> Result<Vec<u8>, Error> {
+    Ok(PSUWriter::new(layout_psu(config, files, &[], true, log)?).to_bytes()?)
+}
+
+/// Writes the PSU to `output`, streaming the files when the save is too big to hold in memory
+pub fn write_psu(
+    config: &Config,
+    files: &[PathBuf],
+    output: &Path,
+    log: &Logger,
+) -> Result<Checksums, Error> {
+    let mut writer = HashingWriter::new(BufWriter::new(File::create(output)?));
+    if total_size(files)? > STREAMING_THRESHOLD {
+        log.verbose("Streaming files straight into the output".to_string());
+        write_psu_streamed(config, files, &mut writer, log)?;
+    } else {
+        writer.write_all(&build_psu(config, files, log)?)?;
+    }
+
+    Ok(writer.finish()?)
+}
+
+/// Writes the PSU one file at a time instead of loading every file first
+fn write_psu_streamed(
+    config: &Config,
+    files: &[PathBuf],
+    writer: impl Write,
+    log: &Logger,
+) -> Result<(), Error> {
+    let psu = layout_psu(config, files, &[], false, log)?;
+    PSUWriter::new(psu).write_with(writer, |entry| {
+        let path = files
+            .iter()
+            .find(|file| {
+                file.file_name()
+                    .is_some_and(|name| config.entry_name(&name.to_string_lossy()) == entry.name)
+            })
+            .ok_or_else(|| std::io::Error::from(std::io::ErrorKind::NotFound))?;
+        File::open(path)
+    })?;
+
+    Ok(())
+}
+
+/// `generated` files are packed from memory after the ones on disk, stamped with the
+/// folder's timestamp
+fn layout_psu(
+    config: &Config,
+    files: &[PathBuf],
+    generated: &[(&str, &[u8])],
+    load_contents: bool,
+    log: &Logger,
+) -> Result<PSU, Error> {
+    let mut psu = PSU::default();
+    let timestamp = config.timestamp.unwrap_or_default();
+    log.verbose(format!("Folder timestamp {timestamp}"));
+    add_psu_defaults(
+        &mut psu,
+        &config.name,
+        files.len() + generated.len(),
+        timestamp,
+    );
+    add_files_to_psu(&mut psu, files, config, load_contents, log)?;
+    for &(name, contents) in generated {
+        log.info(format!("+ {} {}", "Adding", name.green()));
+        psu.entries.push(PSUEntry {
+            id: FILE_ID,
+            size: contents.len() as u32,
+            created: timestamp,
+            sector: 0,
+            modified: timestamp,
+            name: config.entry_name(name),
+            kind: PSUEntryKind::File,
+            contents: Some(contents.to_vec()),
+        });
+    }
+
+    if let Some(mode) = config.directory_mode {
+        psu.entries[0].id = mode;
+    }
+    for (file, &mode) in &config.file_modes {
+        let entry = psu
+            .entries
+            .iter_mut()
+            .skip(3)
+            .find(|entry| entry.name == config.entry_name(file));
+        match entry {
+            Some(entry) => entry.id = mode,
+            None => log.warn(format!(
+                "{} {} {}",
+                "Mode set for".yellow(),
+                file,
+                "which isn't being packed".yellow()
+            )),
+        }
+    }
+
+    Ok(psu)
+}
+
+fn total_size(files: &[PathBuf]) -> Result<u64, Error> {
+    let mut size = 0;
+    for file in files {
+        size += std::fs::metadata(file)?.len();
+    }
+
+    Ok(size)
+}
+
+/// SHA-256 of the effective config and every resolved file, so changing an option or
+/// adding, removing, touching or editing a file invalidates it
+pub fn pack_hash(config: &Config, files: &[PathBuf]) -> Result<String, Error> {
+    let mut hasher = Sha256::new();
+    // Length prefixes keep one field's bytes from passing for the next one's
+    let field = |hasher: &mut Sha256, bytes: &[u8]| {
+        hasher.update((bytes.len() as u64).to_le_bytes());
+        hasher.update(bytes);
+    };
+    field(&mut hasher, config.to_toml_string().as_bytes());
+    for file in files {
+        let metadata = std::fs::metadata(file)?;
+        let modified = metadata
+            .modified()?
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default();
+        field(
+            &mut hasher,
+            file.file_name().unwrap_or_default().as_encoded_bytes(),
+        );
+        field(&mut hasher, &modified.as_nanos().to_le_bytes());
+        // Streamed like the pack itself, so big saves aren't read into memory
+        hasher.update(metadata.len().to_le_bytes());
+        std::io::copy(&mut BufReader::new(File::open(file)?), &mut hasher)?;
+    }
+
+    Ok(format!("{:x}", hasher.finalize()))
+}
+
+/// The PS2 can't display non-ASCII names, and transliterating them can make two files collide
+fn check_file_names(files: &[PathBuf], config: &Config) -> Result<(), Error> {
+    let mut entry_names = Vec::new();
+    for file in files {
+        let name = file.file_name().unwrap_or_default().to_string_lossy();
+        if !name.is_ascii() && !config.ascii_names {
+            return Err(Error::NonAsciiName(name.to_string()));
+        }
+        let entry_name = config.entry_name(&name);
+        if entry_names.contains(&entry_name) {
+            return Err(Error::NameCollision(entry_name));
+        }
+        entry_names.push(entry_name);
+    }
+
+    Ok(())
+}
+
+/// A save whose icon.sys points at a missing icon shows up blank in the browser
+fn check_icon_references(files: &[PathBuf], log: &Logger) -> Result<(), Error> {
+    let Some(icon_sys) = files
+        .iter()
+        .find(|f| f.file_name().is_some_and(|n| n == "icon.sys"))
+    else {
+        return Ok(());
+    };
+
+    check_icons_packed(&IconSys::try_new(std::fs::read(icon_sys)?)?, files, log)
+}
+
+/// A missing list icon leaves the save blank, missing copy and delete icons only break
+/// those animations, so they're warned about like `validate` does
+fn check_icons_packed(icon_sys: &IconSys, files: &[PathBuf], log: &Logger) -> Result<(), Error> {
+    let packed = |name: &str| {
+        files
+            .iter()
+            .any(|f| f.file_name().is_some_and(|n| n == name))
+    };
+
+    if !packed(&icon_sys.icon_file) {
+        return Err(Error::MissingIcon(icon_sys.icon_file.clone()));
+    }
+    for icon in [&icon_sys.icon_copy_file, &icon_sys.icon_delete_file] {
+        if !packed(icon) {
+            log.warn(format!(
+                "{} {}{}",
+                "icon.sys references".yellow(),
+                icon,
+                ", which isn't being packed".yellow()
+            ));
+        }
+    }
+
+    Ok(())
+}
+
+fn filter_files(files: &[PathBuf], log: &Logger) -> Vec<PathBuf> {
+    files
+        .iter()
+        .filter_map(|f| {
+            if !f.is_file() {
+                log.info(format!(
+                    "{} {}",
+                    f.display().to_string().dimmed(),
+                    "is not a file, skipping".dimmed()
+                ));
+                None
+            } else {
+                Some(f.to_owned())
+            }
+        })
+        .collect()
+}
+
+fn add_psu_defaults(psu: &mut PSU, name: &str, file_count: usize, timestamp: NaiveDateTime) {
+    psu.entries.push(PSUEntry {
+        id: DIR_ID,
+        size: file_count as u32 + 2, // +2 to include . and ..
+        created: timestamp,
+        sector: 0,
+        modified: timestamp,
+        name: name.to_owned(),
+        kind: PSUEntryKind::Directory,
+        contents: None,
+    });
+    psu.entries.push(PSUEntry {
+        id: DIR_ID,
+        size: 0,
+        created: timestamp,
+        sector: 0,
+        modified: timestamp,
+        name: ".".to_string(),
+        kind: PSUEntryKind::Directory,
+        contents: None,
+    });
+    psu.entries.push(PSUEntry {
+        id: DIR_ID,
+        size: 0,
+        created: timestamp,
+        sector: 0,
+        modified: timestamp,
+        name: "..".to_string(),
+        kind: PSUEntryKind::Directory,
+        contents: None,
+    });
+}
+
+/// Without `load_contents` the entries only carry sizes, for writers that stream the files
+fn add_files_to_psu(
+    psu: &mut PSU,
+    files: &[PathBuf],
+    config: &Config,
+    load_contents: bool,
+    log: &Logger,
+) -> Result<(), Error> {
+    for file in files {
+        let name = file.file_name().unwrap().to_str().unwrap();
+        let entry_name = config.entry_name(name);
+
+        let f = if load_contents {
+            Some(std::fs::read(file)?)
+        } else {
+            None
+        };
+        let stat = std::fs::metadata(file)?;
+
+        let created = convert_timestamp(stat.created()?);
+        let modified = convert_timestamp(stat.modified()?);
+
+        if entry_name == name {
+            log.info(format!("+ {} {}", "Adding", name.green()));
+        } else {
+            log.info(format!("+ {} {} as {}", "Adding", name, entry_name.green()));
+        }
+        log.verbose(format!("  created {created}, modified {modified}"));
+
+        psu.entries.push(PSUEntry {
+            id: FILE_ID,
+            size: f.as_ref().map_or(stat.len(), |f| f.len() as u64) as u32,
+            created,
+            sector: 0,
+            modified,
+            name: entry_name,
+            kind: PSUEntryKind::File,
+            contents: f,
+        })
+    }
+
+    Ok(())
+}
+
+fn convert_timestamp(time: SystemTime) -> NaiveDateTime {
+    let duration = time.duration_since(UNIX_EPOCH).unwrap();
+    let local = DateTime::from_timestamp(duration.as_secs() as i64, duration.subsec_nanos())
+        .unwrap()
+        .with_timezone(&Local)
+        .naive_local();
+
+    local
+}
+
+pub enum Error {
+    NameError,
+    Config(String),
+    MissingConfig(PathBuf),
+    IOError(std::io::Error),
+    IncludeExcludeError,
+    MissingFolder,
+    MissingIcon(String),
+    SelfInclude(String),
+    NonAsciiName(String),
+    NameCollision(String),
+    DirectoryInclude(String),
+    InvalidMode(String, u16),
+    OutputNotWritable(PathBuf, std::io::Error),
+    NotReproducible(usize),
+    RoundTripMismatch(usize),
+    ValidationFailed(usize),
+    RenameFailed(usize),
+    CardBuildFailed(usize),
+    NoIcon(String),
+    InvalidThumbnailSize(u32),
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::NameError => write!(
+                f,
+                "Name must match [a-zA-Z0-9_- ]+ and be at most {MAX_NAME_LENGTH} characters, \
+                 see --sanitize-name"
+            ),
+            Error::Config(err) => write!(f, "Failed to parse psu.toml: {err}"),
+            Error::MissingConfig(folder) => {
+                write!(f, "Failed to find psu.toml in {}", folder.display())
+            }
+            Error::IncludeExcludeError => write!(f, "Exclude cannot be used in include mode"),
+            Error::MissingFolder => write!(f, "Expected a folder to package, see --help"),
+            Error::NotReproducible(offset) => {
+                write!(
+                    f,
+                    "Packing twice produced different output at offset {offset:#x}"
+                )
+            }
+            Error::RoundTripMismatch(offset) => {
+                write!(
+                    f,
+                    "Repacking the extracted save differs from the original at offset {offset:#x}"
+                )
+            }
+            Error::ValidationFailed(projects) => {
+                write!(f, "{projects} project(s) failed validation")
+            }
+            Error::RenameFailed(projects) => {
+                write!(f, "{projects} project(s) couldn't be renamed")
+            }
+            Error::CardBuildFailed(saves) => {
+                write!(f, "{saves} save(s) couldn't be written to the card")
+            }
+            Error::NoIcon(file) => {
+                write!(f, "{file} has no icon to render, see --placeholder")
+            }
+            Error::InvalidThumbnailSize(size) => {
+                write!(
+                    f,
+                    "Thumbnail size {size} must be between 1 and {MAX_THUMBNAIL_SIZE} pixels"
+                )
+            }
+            Error::MissingIcon(icon) => {
+                write!(f, "icon.sys references {icon}, which isn't being packed")
+            }
+            Error::SelfInclude(file) => {
+                write!(
+                    f,
+                    "{file} can't be included, it's the config or the output of this pack"
+                )
+            }
+            Error::NonAsciiName(file) => {
+                write!(
+                    f,
+                    "{file} isn't an ASCII name the PS2 can display, rename it or see --ascii-names"
+                )
+            }
+            Error::NameCollision(name) => {
+                write!(f, "More than one file would be stored as {name}")
+            }
+            Error::DirectoryInclude(dir) => {
+                write!(
+                    f,
+                    "{dir} is a directory, saves can't contain subfolders so list its files \
+                     individually after moving them into the save's folder"
+                )
+            }
+            Error::InvalidMode(entry, mode) => {
+                write!(
+                    f,
+                    "Mode {mode:#06x} for {entry} doesn't match the entry's kind"
+                )
+            }
+            Error::OutputNotWritable(path, err) => {
+                write!(
+                    f,
+                    "Can't write to {} ({err}), pick another output with -o",
+                    path.display()
+                )
+            }
+            Error::IOError(err) => write!(f, "{err:?}"),
+        }
+    }
+}
+
+impl std::fmt::Debug for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self)
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(err: std::io::Error) -> Self {
+        Error::IOError(err)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ps2_filetypes::{BinReader, PSUParser};
+
+    #[test]
+    fn is_safe_name_rejects_escaping_names() {
+        assert!(is_safe_name("BASLUS-20001"));
+        assert!(is_safe_name("icon.sys"));
+        for name in ["", ".", "..", "/home/user", "a/b", "a\\b", "../save"] {
+            assert!(!is_safe_name(name), "{name:?} should be rejected");
+        }
+    }
+
+    #[test]
+    fn sanitized_names_pass_check_name() {
+        for (name, sanitized) in [
+            ("Final Fantasy X", "Final Fantasy X"),
+            ("BASLUS-20001.SAVE", "BASLUS-20001_SAVE"),
+            ("save/../data", "save____data"),
+            ("Pokémon: Edition!", "Pok_mon_ Edition_"),
+            ("???", "___"),
+            ("", "_"),
+            ("   ", "_"),
+        ] {
+            assert_eq!(sanitize_name(name), sanitized, "{name:?}");
+            assert!(check_name(&sanitize_name(name)));
+        }
+
+        let long_name = "A".repeat(MAX_NAME_LENGTH + 10);
+        assert_eq!(sanitize_name(&long_name).len(), MAX_NAME_LENGTH);
+    }
+
+    #[test]
+    fn resolve_config_applies_overrides_like_a_pack() {
+        let folder = tempfile::tempdir().unwrap();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        assert!(matches!(
+            resolve_config(folder.path(), &ConfigOverrides::default(), &log),
+            Err(Error::MissingConfig(_))
+        ));
+
+        let long_name = "N".repeat(MAX_NAME_LENGTH + 1);
+        std::fs::write(
+            folder.path().join("psu.toml"),
+            format!("[config]\nname = \"{long_name}\"\n"),
+        )
+        .unwrap();
+        assert!(matches!(
+            resolve_config(folder.path(), &ConfigOverrides::default(), &log),
+            Err(Error::NameError)
+        ));
+
+        let overrides = ConfigOverrides {
+            sanitize_name: true,
+            ascii_names: true,
+            ..Default::default()
+        };
+        let config = resolve_config(folder.path(), &overrides, &log).unwrap();
+        assert_eq!(config.name.len(), MAX_NAME_LENGTH);
+        assert!(config.ascii_names);
+    }
+
+    #[test]
+    fn name_override_replaces_the_one_in_psu_toml() {
+        let folder = tempfile::tempdir().unwrap();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        std::fs::write(
+            folder.path().join("psu.toml"),
+            "[config]\nname = \"FROM_TOML\"\n",
+        )
+        .unwrap();
+        let overrides = |name: &str| ConfigOverrides {
+            name: Some(name.to_string()),
+            ..Default::default()
+        };
+
+        let config = resolve_config(folder.path(), &overrides("FROM CLI"), &log).unwrap();
+        assert_eq!(config.name, "FROM CLI");
+        assert!(matches!(
+            resolve_config(folder.path(), &overrides("FROM/CLI"), &log),
+            Err(Error::NameError)
+        ));
+    }
+
+    #[test]
+    fn non_ascii_file_names_are_refused_unless_transliterated() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("résumé.txt"), b"cv").unwrap();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let mut config = Config {
+            name: "SAVE".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            resolve_files(folder.path(), &config, &log),
+            Err(Error::NonAsciiName(name)) if name == "résumé.txt"
+        ));
+
+        config.ascii_names = true;
+        let files = resolve_files(folder.path(), &config, &log).unwrap();
+        let psu = layout_psu(&config, &files, &[], true, &log).unwrap();
+        assert_eq!(psu.entries[3].name, "resume.txt");
+    }
+
+    #[test]
+    fn child_with_its_own_psu_toml_is_a_nested_project() {
+        let parent = tempfile::tempdir().unwrap();
+        for child in ["child", "assets"] {
+            std::fs::create_dir(parent.path().join(child)).unwrap();
+        }
+        std::fs::write(parent.path().join("child/psu.toml"), "[config]\n").unwrap();
+        std::fs::write(parent.path().join("psu.toml"), "[config]\n").unwrap();
+
+        assert_eq!(nested_projects(parent.path()), ["child"]);
+        assert!(nested_projects(&parent.path().join("child")).is_empty());
+    }
+
+    #[test]
+    fn psu_in_the_folder_is_only_packed_when_asked_for() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("BOOT.ELF"), b"boot").unwrap();
+        std::fs::write(folder.path().join("SAVE.psu"), b"last pack").unwrap();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let names = |config: &Config| {
+            resolve_files(folder.path(), config, &log)
+                .unwrap()
+                .iter()
+                .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+        let mut config = Config {
+            name: "SAVE".to_string(),
+            ..Default::default()
+        };
+
+        assert_eq!(names(&config), ["BOOT.ELF"]);
+        config.pack_psu_files = true;
+        assert_eq!(names(&config), ["BOOT.ELF", "SAVE.psu"]);
+        config.pack_psu_files = false;
+        config.include = Some(vec!["SAVE.psu".to_string()]);
+        assert_eq!(names(&config), ["SAVE.psu"]);
+    }
+
+    #[test]
+    fn included_directory_fails_but_is_skipped_otherwise() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("BOOT.ELF"), b"boot").unwrap();
+        std::fs::create_dir(folder.path().join("extras")).unwrap();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let including = Config {
+            include: Some(vec!["BOOT.ELF".to_string(), "extras".to_string()]),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            resolve_files(folder.path(), &including, &log),
+            Err(Error::DirectoryInclude(dir)) if dir == "extras"
+        ));
+        let files = resolve_files(folder.path(), &Config::default(), &log).unwrap();
+        assert_eq!(files, [folder.path().join("BOOT.ELF")]);
+    }
+
+    #[test]
+    fn missing_list_icon_is_refused() {
+        let folder = tempfile::tempdir().unwrap();
+        let icon_sys = IconSys {
+            icon_file: "list.icn".to_string(),
+            ..Default::default()
+        };
+        std::fs::write(folder.path().join("icon.sys"), icon_sys.to_bytes().unwrap()).unwrap();
+        std::fs::write(folder.path().join("icon.icn"), b"icon").unwrap();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let config = Config {
+            name: "SAVE".to_string(),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            resolve_files(folder.path(), &config, &log),
+            Err(Error::MissingIcon(icon)) if icon == "list.icn"
+        ));
+
+        std::fs::write(folder.path().join("list.icn"), b"icon").unwrap();
+        let files = resolve_files(folder.path(), &config, &log).unwrap();
+        assert_eq!(files.len(), 3);
+    }
+
+    #[test]
+    fn config_and_output_are_rejected_in_the_include_list() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("SAVE.psu"), b"last pack").unwrap();
+        let include = |file: &str| Config {
+            name: "SAVE".to_string(),
+            include: Some(vec!["BOOT.ELF".to_string(), file.to_string()]),
+            ..Default::default()
+        };
+
+        assert!(matches!(
+            check_config(&include("PSU.TOML")),
+            Err(Error::SelfInclude(file)) if file == "PSU.TOML"
+        ));
+        assert!(check_config(&include("SAVE.psu")).is_ok());
+
+        let output = folder.path().join("SAVE.psu");
+        assert!(matches!(
+            check_output_not_included(folder.path(), &include("SAVE.psu"), &output),
+            Err(Error::SelfInclude(file)) if file == "SAVE.psu"
+        ));
+        let elsewhere = folder.path().join("OTHER.psu");
+        assert!(check_output_not_included(folder.path(), &include("SAVE.psu"), &elsewhere).is_ok());
+    }
+
+    #[test]
+    fn unwritable_output_is_refused_up_front() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("file"), b"not a folder").unwrap();
+
+        assert!(check_output_writable(&folder.path().join("SAVE.psu")).is_ok());
+        assert!(matches!(
+            check_output_writable(&folder.path().join("file/SAVE.psu")),
+            Err(Error::OutputNotWritable(..))
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn read_only_output_folder_is_refused_and_left_empty() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let folder = tempfile::tempdir().unwrap();
+        let read_only = folder.path().join("read-only");
+        std::fs::create_dir(&read_only).unwrap();
+        std::fs::set_permissions(&read_only, std::fs::Permissions::from_mode(0o555)).unwrap();
+        if File::create(read_only.join("probe")).is_ok() {
+            // Running as root, permissions aren't enforced
+            return;
+        }
+
+        assert!(matches!(
+            check_output_writable(&read_only.join("SAVE.psu")),
+            Err(Error::OutputNotWritable(path, _)) if path == read_only
+        ));
+        assert_eq!(std::fs::read_dir(&read_only).unwrap().count(), 0);
+    }
+
+    fn save_folder() -> (tempfile::TempDir, Config, Vec<PathBuf>) {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("BOOT.ELF"), b"\x7fELF boot").unwrap();
+        std::fs::write(folder.path().join("DATA.BIN"), vec![7; 3000]).unwrap();
+        let config = Config {
+            name: "BASLUS-20001".to_string(),
+            timestamp: NaiveDateTime::parse_from_str("2024-03-01 12:00:00", "%Y-%m-%d %H:%M:%S")
+                .ok(),
+            ..Default::default()
+        };
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let files = resolve_files(folder.path(), &config, &log).unwrap();
+        (folder, config, files)
+    }
+
+    #[test]
+    fn written_config_repacks_the_same_files() {
+        let (folder, config, files) = save_folder();
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let config_file = written_config_path(&folder.path().join("SAVE.psu"));
+        assert_eq!(config_file.file_name().unwrap(), "SAVE.psu.toml");
+
+        std::fs::write(&config_file, config.effective(&files).to_toml_string()).unwrap();
+
+        let written = toml::from_str::<ConfigFile>(&std::fs::read_to_string(&config_file).unwrap())
+            .unwrap()
+            .config;
+        assert_eq!(written.name, config.name);
+        assert_eq!(written.timestamp, config.timestamp);
+        assert_eq!(
+            written.include.as_deref(),
+            Some(["BOOT.ELF".to_string(), "DATA.BIN".to_string()].as_slice())
+        );
+        assert_eq!(resolve_files(folder.path(), &config, &log).unwrap(), files);
+        assert_eq!(resolve_files(folder.path(), &written, &log).unwrap(), files);
+    }
+
+    #[test]
+    fn folder_files_are_packed_in_name_order() {
+        let folder = tempfile::tempdir().unwrap();
+        for name in ["ZZZ.BIN", "BOOT.ELF", "MMM.BIN", "AAA.BIN"] {
+            std::fs::write(folder.path().join(name), name).unwrap();
+        }
+        let log = Logger::new(logger::Verbosity::Quiet);
+        let names = |config: &Config| {
+            resolve_files(folder.path(), config, &log)
+                .unwrap()
+                .iter()
+                .map(|file| file.file_name().unwrap().to_string_lossy().to_string())
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(
+            names(&Config::default()),
+            ["AAA.BIN", "BOOT.ELF", "MMM.BIN", "ZZZ.BIN"]
+        );
+        let excluding = Config {
+            exclude: Some(vec!["MMM.BIN".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(names(&excluding), ["AAA.BIN", "BOOT.ELF", "ZZZ.BIN"]);
+        let including = Config {
+            include: Some(vec!["ZZZ.BIN".to_string(), "AAA.BIN".to_string()]),
+            ..Default::default()
+        };
+        assert_eq!(names(&including), ["ZZZ.BIN", "AAA.BIN"]);
+    }
+
+    #[test]
+    fn streamed_psu_matches_the_buffered_one() {
+        let (_folder, config, files) = save_folder();
+        let log = Logger::new(logger::Verbosity::Quiet);
+
+        let mut streamed = vec![];
+        write_psu_streamed(&config, &files, &mut streamed, &log).unwrap();
+
+        assert_eq!(streamed, build_psu(&config, &files, &log).unwrap());
+    }
+
+    #[test]
+    fn packed_psu_reads_back_as_the_folder() {
+        let (folder, config, _) = save_folder();
+        let output = tempfile::tempdir().unwrap();
+        let psu_path = output.path().join("save.psu");
+
+        pack_with_config(
+            folder.path(),
+            &config,
+            &psu_path,
+            &Logger::new(logger::Verbosity::Quiet),
+        )
+        .unwrap();
+
+        let psu = PSUParser::read(&std::fs::read(&psu_path).unwrap()).unwrap();
+        let files = psu
+            .entries
+            .iter()
+            .filter(|entry| matches!(entry.kind, PSUEntryKind::File))
+            .map(|entry| (entry.name.as_str(), entry.contents.clone().unwrap()))
+            .collect::<Vec<_>>();
+        assert_eq!(psu.entries[0].name, "BASLUS-20001");
+        assert_eq!(
+            files,
+            [
+                ("BOOT.ELF", b"\x7fELF boot".to_vec()),
+                ("DATA.BIN", vec![7; 3000])
+            ]
+        );
+    }
+}
@@ -0,0 +1,37 @@
+use std::fmt::Display;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// Routes progress output through a verbosity level, errors are always reported by the caller
+pub struct Logger {
+    verbosity: Verbosity,
+}
+
+impl Logger {
+    pub fn new(verbosity: Verbosity) -> Self {
+        Self { verbosity }
+    }
+
+    pub fn info(&self, message: impl Display) {
+        if self.verbosity >= Verbosity::Normal {
+            println!("{message}");
+        }
+    }
+
+    pub fn warn(&self, message: impl Display) {
+        if self.verbosity >= Verbosity::Normal {
+            eprintln!("{message}");
+        }
+    }
+
+    pub fn verbose(&self, message: impl Display) {
+        if self.verbosity >= Verbosity::Verbose {
+            println!("{message}");
+        }
+    }
+}
@@ -1,260 +1,158 @@
-use chrono::{DateTime, Local, NaiveDateTime};
-use colored::Colorize;
-use ps2_filetypes::{PSUEntry, PSUEntryKind, PSUWriter, DIR_ID, FILE_ID, PSU};
-use serde::Deserialize;
-use std::path::PathBuf;
-use std::time::{SystemTime, UNIX_EPOCH};
 use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::checksum::HashingWriter;
+use psu_packer::logger::{Logger, Verbosity};
+use psu_packer::{
+    case_mismatches, pack_if_changed, pack_with_config, resolve_config, resolve_files,
+    warn_nested_projects, written_config_path, ConfigOverrides, Error,
+};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+mod make_card;
+mod make_delta;
+mod make_thumbnail;
+mod rename_prefix;
+mod round_trip;
+mod unpack;
+mod validate_projects;
+mod verify_reproducible;
 
 #[derive(Debug, FromArgs)]
 #[argh(description = "Expects a folder with a psu.toml file that follows this format\n\t[config]\n\tname = \"Test PSU\"\t\t\t# Folder name on Memory Card\n\tinclude = [ \"BOOT.ELF\", \"icon.sys\" ]\t# using `exclude` will automatically include all files except the specified ones\n\ttimestamp = \"2024-10-10 10:30:00\"\t# Optional, but recommended\n")]
 struct Args {
     /// folder to package to psu
     #[argh(positional)]
-    folder: String,
+    folder: Option<String>,
     /// output path
     #[argh(option, short = 'o')]
     output: Option<String>,
+    /// folder name on the memory card, overrides the one in psu.toml
+    #[argh(option)]
+    name: Option<String>,
+    /// replace invalid characters in the name with _ instead of failing
+    #[argh(switch)]
+    sanitize_name: bool,
+    /// pack .psu files in the folder even when they aren't listed in include
+    #[argh(switch)]
+    pack_psu_files: bool,
+    /// store non-ASCII file names transliterated instead of failing, like ascii_names in psu.toml
+    #[argh(switch)]
+    ascii_names: bool,
+    /// skip writing the psu when nothing changed since it was last packed
+    #[argh(switch)]
+    incremental: bool,
+    /// print the CRC32 and SHA-256 of the written psu
+    #[argh(switch)]
+    print_hash: bool,
+    /// also write the effective config to <output>.toml so the psu can be repacked later
+    #[argh(switch)]
+    write_config: bool,
+    /// only print errors
+    #[argh(switch, short = 'q')]
+    quiet: bool,
+    /// also print excluded files and resolved timestamps
+    #[argh(switch, short = 'v')]
+    verbose: bool,
+    #[argh(subcommand)]
+    command: Option<Command>,
 }
 
-#[derive(Debug, Deserialize)]
-struct Config {
-    name: String,
-    #[serde(default, with = "date_format")]
-    timestamp: Option<NaiveDateTime>,
-    include: Option<Vec<String>>,
-    exclude: Option<Vec<String>>,
+#[derive(Debug, FromArgs)]
+#[argh(subcommand)]
+enum Command {
+    Card(make_card::CardArgs),
+    Delta(make_delta::DeltaArgs),
+    RenamePrefix(rename_prefix::RenamePrefixArgs),
+    RoundTrip(round_trip::RoundTripArgs),
+    Thumbnail(make_thumbnail::ThumbnailArgs),
+    Unpack(unpack::UnpackArgs),
+    Validate(validate_projects::ValidateArgs),
+    VerifyReproducible(verify_reproducible::VerifyReproducibleArgs),
 }
 
-mod date_format {
-    use chrono::NaiveDateTime;
-    use serde::{self, Deserialize, Deserializer};
+fn main() -> Result<(), Error> {
+    let mut args: Args = argh::from_env();
+    let log = Logger::new(if args.quiet {
+        Verbosity::Quiet
+    } else if args.verbose {
+        Verbosity::Verbose
+    } else {
+        Verbosity::Normal
+    });
 
-    pub fn deserialize<'de, D>(deserialize: D) -> Result<Option<NaiveDateTime>, D::Error>
-    where
-        D: Deserializer<'de>,
-    {
-        let s: Option<String> = Option::deserialize(deserialize)?;
-        if let Some(s) = s {
-            Ok(Some(
-                NaiveDateTime::parse_from_str(&s, "%Y-%m-%d %H:%M:%S")
-                    .map_err(serde::de::Error::custom)?,
-            ))
-        } else {
-            Ok(None)
+    match args.command.take() {
+        Some(Command::Card(args)) => make_card::make_card(args, &log),
+        Some(Command::Delta(args)) => make_delta::make_delta(args, &log),
+        Some(Command::RenamePrefix(args)) => rename_prefix::rename_prefix(args, &log),
+        Some(Command::RoundTrip(args)) => round_trip::round_trip(args, &log),
+        Some(Command::Thumbnail(args)) => make_thumbnail::make_thumbnail(args, &log),
+        Some(Command::Unpack(args)) => unpack::unpack(args, &log),
+        Some(Command::Validate(args)) => validate_projects::validate_projects(args, &log),
+        Some(Command::VerifyReproducible(args)) => {
+            verify_reproducible::verify_reproducible(args, &log)
         }
+        None => pack(&args, &log),
     }
 }
 
-#[derive(Debug, Deserialize)]
-struct ConfigFile {
-    config: Config,
-}
-
-fn check_name(name: &str) -> bool {
-    for c in name.chars() {
-        if !matches!(c, 'a'..='z'|'A'..='Z'|'0'..='9'|'_'|'-'|' ') {
-            return false
-        }
+fn pack(args: &Args, log: &Logger) -> Result<(), Error> {
+    let folder = PathBuf::from(args.folder.as_ref().ok_or(Error::MissingFolder)?);
+    let config = resolve_config(
+        &folder,
+        &ConfigOverrides {
+            name: args.name.clone(),
+            sanitize_name: args.sanitize_name,
+            pack_psu_files: args.pack_psu_files,
+            ascii_names: args.ascii_names,
+        },
+        log,
+    )?;
+
+    warn_nested_projects(&folder, log);
+    for (listed, actual) in case_mismatches(&folder, &config) {
+        log.warn(format!(
+            "{} {} {} {}",
+            "psu.toml lists".yellow(),
+            listed,
+            "but the file on disk is".yellow(),
+            actual
+        ));
     }
-    true
-}
-
-fn main() -> Result<(), Error> {
-    let args: Args = argh::from_env();
-    let folder = PathBuf::from(args.folder);
-
-    let config_file = folder.join("psu.toml");
 
-    if config_file.exists() {
-        let str = std::fs::read_to_string(&config_file)?;
-        let config = toml::from_str::<ConfigFile>(&str)
-            .expect("Failed to parse config file")
-            .config;
-
-        let output_file = args.output.unwrap_or(format!("{}.psu", config.name));
-
-        if !check_name(&config.name) {
-            return Err(Error::NameError);
-        }
-
-        if config.include.is_some() && config.exclude.is_some() {
-            return Err(Error::IncludeExcludeError);
+    let output_file = args
+        .output
+        .clone()
+        .unwrap_or(format!("{}.psu", config.name));
+    let output = Path::new(&output_file);
+    let checksums = if args.incremental {
+        match pack_if_changed(&folder, &config, output, log)? {
+            Some(checksums) => checksums,
+            None => {
+                if args.print_hash {
+                    let mut writer = HashingWriter::new(std::io::sink());
+                    std::io::copy(&mut File::open(output)?, &mut writer)?;
+                    println!("{}", writer.finish()?);
+                }
+                return Ok(());
+            }
         }
-
-        let mut psu = PSU::default();
-
-        let files = if let Some(include) = config.include {
-            include
-                .iter()
-                .filter_map(|file| {
-                    if file.contains(|c| matches!(c, '\\' | '/')) {
-                        eprintln!(
-                            "{} {} {}",
-                            "File".dimmed(),
-                            file.dimmed(),
-                            "exists in subfolder, skipping".dimmed()
-                        );
-                        None
-                    } else if !folder.join(file).exists() {
-                        eprintln!(
-                            "{} {} {}",
-                            "File".dimmed(),
-                            file.dimmed(),
-                            "does not exist, skipping".dimmed()
-                        );
-                        None
-                    } else {
-                        Some(folder.join(file))
-                    }
-                })
-                .collect::<Vec<_>>()
-        } else if let Some(exclude) = config.exclude {
-            std::fs::read_dir(folder)?
-                .into_iter()
-                .flatten()
-                .filter_map(|d| {
-                    if !exclude.contains(&d.file_name().to_str().unwrap().to_string()) {
-                        Some(d.path())
-                    } else {
-                        None
-                    }
-                })
-                .collect::<Vec<_>>()
-        } else {
-            // Include all
-            std::fs::read_dir(folder)?
-                .into_iter()
-                .flatten()
-                .map(|d| d.path())
-                .collect::<Vec<_>>()
-        };
-        let files = filter_files(&files);
-        add_psu_defaults(
-            &mut psu,
-            &config.name,
-            files.len(),
-            config.timestamp.unwrap_or_default(),
-        );
-        add_files_to_psu(&mut psu, &files)?;
-        std::fs::write(&output_file, PSUWriter::new(psu).to_bytes()?)?;
-        println!("Wrote {}! {}", output_file.green(), "".clear());
     } else {
-        eprintln!("{}", "Failed to find psu.toml".red());
+        pack_with_config(&folder, &config, output, log)?
+    };
+    log.info(format!("Wrote {}! {}", output_file.green(), "".clear()));
+    if args.write_config {
+        let files = resolve_files(&folder, &config, &Logger::new(Verbosity::Quiet))?;
+        let config_file = written_config_path(output);
+        std::fs::write(&config_file, config.effective(&files).to_toml_string())?;
+        log.info(format!(
+            "Wrote {}",
+            config_file.display().to_string().green()
+        ));
     }
-
-    Ok(())
-}
-
-fn filter_files(files: &[PathBuf]) -> Vec<PathBuf> {
-    files
-        .iter()
-        .filter_map(|f| {
-            if !f.is_file() {
-                println!(
-                    "{} {}",
-                    f.display().to_string().dimmed(),
-                    "is not a file, skipping".dimmed()
-                );
-                None
-            } else {
-                Some(f.to_owned())
-            }
-        })
-        .collect()
-}
-
-fn add_psu_defaults(psu: &mut PSU, name: &str, file_count: usize, timestamp: NaiveDateTime) {
-    psu.entries.push(PSUEntry {
-        id: DIR_ID,
-        size: file_count as u32 + 2, // +2 to include . and ..
-        created: timestamp,
-        sector: 0,
-        modified: timestamp,
-        name: name.to_owned(),
-        kind: PSUEntryKind::Directory,
-        contents: None,
-    });
-    psu.entries.push(PSUEntry {
-        id: DIR_ID,
-        size: 0,
-        created: timestamp,
-        sector: 0,
-        modified: timestamp,
-        name: ".".to_string(),
-        kind: PSUEntryKind::Directory,
-        contents: None,
-    });
-    psu.entries.push(PSUEntry {
-        id: DIR_ID,
-        size: 0,
-        created: timestamp,
-        sector: 0,
-        modified: timestamp,
-        name: "..".to_string(),
-        kind: PSUEntryKind::Directory,
-        contents: None,
-    });
-}
-
-fn add_files_to_psu(psu: &mut PSU, files: &[PathBuf]) -> Result<(), Error> {
-    for file in files {
-        let name = file.file_name().unwrap().to_str().unwrap();
-
-        let f = std::fs::read(file)?;
-        let stat = std::fs::metadata(file)?;
-
-        println!("+ {} {}", "Adding", name.green());
-
-        psu.entries.push(PSUEntry {
-            id: FILE_ID,
-            size: f.len() as u32,
-            created: convert_timestamp(stat.created()?),
-            sector: 0,
-            modified: convert_timestamp(stat.modified()?),
-            name: name.to_owned(),
-            kind: PSUEntryKind::File,
-            contents: Some(f),
-        })
+    if args.print_hash {
+        println!("{checksums}");
     }
 
     Ok(())
 }
-
-fn convert_timestamp(time: SystemTime) -> NaiveDateTime {
-    let duration = time.duration_since(UNIX_EPOCH).unwrap();
-    let local = DateTime::from_timestamp(duration.as_secs() as i64, duration.subsec_nanos())
-        .unwrap()
-        .with_timezone(&Local)
-        .naive_local();
-
-    local
-}
-
-enum Error {
-    NameError,
-    IOError(std::io::Error),
-    IncludeExcludeError,
-}
-
-impl std::fmt::Display for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        match self {
-            Error::NameError => write!(f, "Name must match [a-zA-Z0-9._-\\s]+"),
-            Error::IncludeExcludeError => write!(f, "Exclude cannot be used in include mode"),
-            Error::IOError(err) => write!(f, "{err:?}"),
-        }
-    }
-}
-
-impl std::fmt::Debug for Error {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "{}", self)
-    }
-}
-
-impl From<std::io::Error> for Error {
-    fn from(err: std::io::Error) -> Self {
-        Error::IOError(err)
-    }
-}
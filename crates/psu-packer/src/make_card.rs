@@ -0,0 +1,77 @@
+use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::logger::Logger;
+use psu_packer::{build_card, check_output_writable, open_card, Error};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "card",
+    description = "Write PSUs or project folders to a memory card image, reporting any that don't fit"
+)]
+pub struct CardArgs {
+    /// psu files or project folders with a psu.toml
+    #[argh(positional)]
+    saves: Vec<String>,
+    /// memory card image to add the saves to, a blank 8MB card is created if it doesn't exist
+    #[argh(option, short = 'o')]
+    output: String,
+}
+
+pub fn make_card(args: CardArgs, log: &Logger) -> Result<(), Error> {
+    let output = Path::new(&args.output);
+    check_output_writable(output)?;
+    let mut card = open_card(output)?;
+    let sources = args.saves.iter().map(PathBuf::from).collect::<Vec<_>>();
+    let saves = build_card(&mut card, &sources, log);
+    for page in card.ecc_errors() {
+        log.warn(format!("Page {page} of the card fails its ECC check").yellow());
+    }
+
+    for save in &saves {
+        let name = save.name.clone().unwrap_or_default();
+        let size = format!("({} KB)", save.size.div_ceil(1024));
+        let err = match &save.result {
+            Ok(()) => {
+                log.info(format!(
+                    "+ {} {} {}",
+                    name.green(),
+                    size.dimmed(),
+                    save.source.display().to_string().dimmed()
+                ));
+                continue;
+            }
+            // The card's own errors read better without the io::Error wrapping
+            Err(Error::IOError(err)) => err.to_string(),
+            Err(err) => err.to_string(),
+        };
+        if save.did_not_fit() {
+            eprintln!(
+                "{} {} {} {err}",
+                name.red(),
+                size.dimmed(),
+                "doesn't fit:".red()
+            );
+        } else {
+            eprintln!("{} {err}", save.source.display().to_string().red());
+        }
+    }
+
+    let written = saves.iter().filter(|save| save.result.is_ok()).count();
+    let free = card.free_bytes() / 1024;
+    std::fs::write(output, card.into_bytes())?;
+    log.info(format!(
+        "Wrote {} of {} save(s) to {}, {free} KB free",
+        written,
+        saves.len(),
+        output.display().to_string().green()
+    ));
+
+    let failed = saves.len() - written;
+    if failed > 0 {
+        return Err(Error::CardBuildFailed(failed));
+    }
+
+    Ok(())
+}
@@ -0,0 +1,54 @@
+use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::logger::Logger;
+use psu_packer::{delta_manifest_path, pack_delta, Error};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "delta",
+    description = "Pack only the files that differ from a baseline PSU, to be copied over it"
+)]
+pub struct DeltaArgs {
+    /// psu the delta applies to
+    #[argh(positional)]
+    baseline: String,
+    /// folder with the updated save
+    #[argh(positional)]
+    folder: String,
+    /// output path, defaults to the folder's name with .delta.psu
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+}
+
+pub fn make_delta(args: DeltaArgs, log: &Logger) -> Result<(), Error> {
+    let folder = PathBuf::from(&args.folder);
+    let output = args.output.map(PathBuf::from).unwrap_or_else(|| {
+        let name = folder.file_name().unwrap_or_default().to_string_lossy();
+        PathBuf::from(format!("{name}.delta.psu"))
+    });
+
+    let delta = pack_delta(Path::new(&args.baseline), &folder, &output)?;
+    for file in &delta.changed {
+        log.info(format!("~ {} {}", "Changed", file.yellow()));
+    }
+    for file in &delta.added {
+        log.info(format!("+ {} {}", "Added", file.green()));
+    }
+    for file in &delta.removed {
+        log.info(format!("- {} {}", "Removed", file.red()));
+    }
+    if delta.is_empty() {
+        log.warn("Nothing differs from the baseline".yellow());
+    }
+
+    log.info(format!(
+        "Wrote {} and {}, copy it over {} and delete the removed files",
+        output.display().to_string().green(),
+        delta_manifest_path(&output).display().to_string().green(),
+        delta.baseline
+    ));
+
+    Ok(())
+}
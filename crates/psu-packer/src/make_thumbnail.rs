@@ -0,0 +1,48 @@
+use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::logger::Logger;
+use psu_packer::{check_output_writable, placeholder_thumbnail, psu_thumbnail, Error};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "thumbnail",
+    description = "Render the icon of a PSU to a PNG, for save listings"
+)]
+pub struct ThumbnailArgs {
+    /// psu file to render the icon of
+    #[argh(positional)]
+    file: String,
+    /// output path, defaults to the psu's name with .png
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+    /// width and height of the png in pixels
+    #[argh(option, default = "128")]
+    size: u32,
+    /// write a placeholder for saves without an icon instead of failing
+    #[argh(switch)]
+    placeholder: bool,
+}
+
+pub fn make_thumbnail(args: ThumbnailArgs, log: &Logger) -> Result<(), Error> {
+    let output = args
+        .output
+        .map(PathBuf::from)
+        .unwrap_or_else(|| Path::new(&args.file).with_extension("png"));
+    check_output_writable(&output)?;
+
+    let png = match psu_thumbnail(Path::new(&args.file), args.size)? {
+        Some(png) => png,
+        None if args.placeholder => {
+            log.warn(format!("{} has no icon, writing a placeholder", args.file).yellow());
+            placeholder_thumbnail(args.size)?
+        }
+        None => return Err(Error::NoIcon(args.file)),
+    };
+    std::fs::write(&output, png)?;
+
+    log.info(format!("Wrote {}", output.display().to_string().green()));
+
+    Ok(())
+}
@@ -0,0 +1,153 @@
+use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::logger::Logger;
+use psu_packer::{check_name, Error};
+use std::path::{Path, PathBuf};
+use toml_edit::DocumentMut;
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "rename-prefix",
+    description = "Replace the name prefix (e.g. APP_) of every project in a directory"
+)]
+pub struct RenamePrefixArgs {
+    /// directory containing project folders with a psu.toml
+    #[argh(positional)]
+    directory: String,
+    /// prefix to replace
+    #[argh(option)]
+    from: String,
+    /// replacement prefix
+    #[argh(option)]
+    to: String,
+    /// also rename project folders that start with the old prefix
+    #[argh(switch)]
+    rename_folders: bool,
+    /// write the changes, otherwise only report what would change
+    #[argh(switch)]
+    apply: bool,
+}
+
+pub fn rename_prefix(args: RenamePrefixArgs, log: &Logger) -> Result<(), Error> {
+    let mut projects = std::fs::read_dir(&args.directory)?
+        .flatten()
+        .map(|d| d.path())
+        .filter(|p| p.join("psu.toml").is_file())
+        .collect::<Vec<_>>();
+    projects.sort();
+
+    if !args.apply {
+        log.info("Dry run, pass --apply to write changes".dimmed());
+    }
+
+    let mut failed = 0;
+    for project in projects {
+        if let Err(err) = rename_project(&project, &args, log) {
+            eprintln!("{} {}", project.display().to_string().red(), err);
+            failed += 1;
+        }
+    }
+    if failed > 0 {
+        return Err(Error::RenameFailed(failed));
+    }
+
+    Ok(())
+}
+
+fn rename_project(project: &Path, args: &RenamePrefixArgs, log: &Logger) -> Result<(), String> {
+    let config_file = project.join("psu.toml");
+    let contents = std::fs::read_to_string(&config_file).map_err(|err| err.to_string())?;
+    // Edited in place rather than reserialized, so comments and formatting survive
+    let mut document = contents
+        .parse::<DocumentMut>()
+        .map_err(|err| err.to_string())?;
+
+    let name = document
+        .get("config")
+        .and_then(|config| config.get("name"))
+        .and_then(|name| name.as_str())
+        .ok_or("psu.toml has no config.name")?
+        .to_owned();
+
+    if let Some(rest) = name.strip_prefix(&args.from) {
+        let new_name = format!("{}{rest}", args.to);
+        if !check_name(&new_name) {
+            return Err(format!("{new_name} is not a valid name"));
+        }
+
+        log.info(format!(
+            "{}: {} -> {}",
+            config_file.display(),
+            name,
+            new_name.green()
+        ));
+        if args.apply {
+            let value = document["config"]["name"]
+                .as_value_mut()
+                .ok_or("psu.toml has no config.name")?;
+            // Keeps the whitespace and trailing comment around the old value
+            let decor = value.decor().clone();
+            *value = new_name.into();
+            *value.decor_mut() = decor;
+            std::fs::write(&config_file, document.to_string()).map_err(|err| err.to_string())?;
+        }
+    }
+
+    if args.rename_folders {
+        let folder_name = project
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or_default();
+
+        if let Some(rest) = folder_name.strip_prefix(&args.from) {
+            let new_project: PathBuf = project.with_file_name(format!("{}{rest}", args.to));
+            if new_project.exists() {
+                return Err(format!("{} already exists", new_project.display()));
+            }
+
+            log.info(format!(
+                "{} -> {}",
+                project.display(),
+                new_project.display().to_string().green()
+            ));
+            if args.apply {
+                std::fs::rename(project, &new_project).map_err(|err| err.to_string())?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use psu_packer::logger::Verbosity;
+
+    #[test]
+    fn comments_around_the_name_survive() {
+        let parent = tempfile::tempdir().unwrap();
+        let project = parent.path().join("APP_SAVE");
+        std::fs::create_dir(&project).unwrap();
+        std::fs::write(
+            project.join("psu.toml"),
+            "[config]\n# Shown in the browser\nname = \"APP_SAVE\" # keep short\nexclude = []\n",
+        )
+        .unwrap();
+        let args = RenamePrefixArgs {
+            directory: parent.path().display().to_string(),
+            from: "APP_".to_string(),
+            to: "GAME_".to_string(),
+            rename_folders: false,
+            apply: true,
+        };
+
+        rename_prefix(args, &Logger::new(Verbosity::Quiet)).unwrap();
+
+        assert_eq!(
+            std::fs::read_to_string(project.join("psu.toml")).unwrap(),
+            "[config]\n# Shown in the browser\nname = \"GAME_SAVE\" # keep short\nexclude = []\n"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+use argh::FromArgs;
+use colored::Colorize;
+use ps2_filetypes::{
+    decompress, BinReader, PSUEntry, PSUEntryKind, PSUParser, PSUWriter, DIR_ID, FILE_ID, PSU,
+};
+use psu_packer::logger::{Logger, Verbosity};
+use psu_packer::{extract, pack_with_config, Config, Error};
+use std::path::Path;
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "round-trip",
+    description = "Extract a PSU, pack it again and check the result matches the original"
+)]
+pub struct RoundTripArgs {
+    /// psu file to round-trip
+    #[argh(positional)]
+    file: String,
+}
+
+pub fn round_trip(args: RoundTripArgs, log: &Logger) -> Result<(), Error> {
+    let original = decompress(&std::fs::read(&args.file)?)?.into_owned();
+    let psu = PSUParser::read(&original)?;
+
+    let temp_dir = tempfile::tempdir()?;
+    let repacked = repack(&psu, temp_dir.path())?;
+
+    let Some(offset) = first_difference(&original, &repacked) else {
+        log.info(format!(
+            "{} round-trips byte for byte ({} bytes)",
+            args.file.green(),
+            original.len()
+        ));
+        return Ok(());
+    };
+
+    let differences = diff_entries(&psu.entries, &PSUParser::read(&repacked)?.entries);
+    for difference in &differences {
+        eprintln!("  {difference}");
+    }
+    if differences.is_empty() {
+        eprintln!(
+            "  {}",
+            "Every entry matches, only the padding differs".yellow()
+        );
+    }
+    Err(Error::RoundTripMismatch(offset))
+}
+
+/// Extracts the save into `temp_dir` and packs it with a config that recreates the original.
+/// Creation times can only be restored on some platforms, so every entry takes its timestamps
+/// from the original and the comparison is about everything else.
+fn repack(psu: &PSU, temp_dir: &Path) -> Result<Vec<u8>, Error> {
+    let quiet = Logger::new(Verbosity::Quiet);
+    let folder = temp_dir.join("save");
+    extract(psu, &folder, &quiet)?;
+
+    let output = temp_dir.join("repacked.psu");
+    pack_with_config(&folder, &config_for(psu), &output, &quiet)?;
+
+    let mut repacked = PSUParser::read(&std::fs::read(output)?)?;
+    for (entry, original) in repacked.entries.iter_mut().zip(&psu.entries) {
+        entry.created = original.created;
+        entry.modified = original.modified;
+    }
+
+    Ok(PSUWriter::new(repacked).to_bytes()?)
+}
+
+/// Lists the files in their original order and carries over any non-default modes
+fn config_for(psu: &PSU) -> Config {
+    let mut config = Config::default();
+    let mut files = vec![];
+
+    for entry in &psu.entries {
+        match entry.kind {
+            PSUEntryKind::Directory if config.name.is_empty() => {
+                config.name = entry.name.clone();
+                config.timestamp = Some(entry.created);
+                if entry.id != DIR_ID {
+                    config.directory_mode = Some(entry.id);
+                }
+            }
+            PSUEntryKind::Directory => {}
+            PSUEntryKind::File => {
+                if entry.id != FILE_ID {
+                    config.file_modes.insert(entry.name.clone(), entry.id);
+                }
+                files.push(entry.name.clone());
+            }
+        }
+    }
+    config.include = Some(files);
+
+    config
+}
+
+fn first_difference(original: &[u8], repacked: &[u8]) -> Option<usize> {
+    original
+        .iter()
+        .zip(repacked)
+        .position(|(a, b)| a != b)
+        .or((original.len() != repacked.len()).then(|| original.len().min(repacked.len())))
+}
+
+/// Describes how each entry changed, in archive order
+fn diff_entries(original: &[PSUEntry], repacked: &[PSUEntry]) -> Vec<String> {
+    let mut differences = vec![];
+    if original.len() != repacked.len() {
+        differences.push(format!(
+            "{} entries became {}",
+            original.len(),
+            repacked.len()
+        ));
+    }
+
+    for (before, after) in original.iter().zip(repacked) {
+        let mut changed = vec![];
+        if before.name != after.name {
+            changed.push(format!("renamed to {}", after.name));
+        }
+        if before.id != after.id {
+            changed.push(format!("mode {:#06x} -> {:#06x}", before.id, after.id));
+        }
+        if before.size != after.size {
+            changed.push(format!("size {} -> {}", before.size, after.size));
+        }
+        if before.sector != after.sector {
+            changed.push(format!("sector {} -> {}", before.sector, after.sector));
+        }
+        if before.contents != after.contents {
+            changed.push("contents differ".to_string());
+        }
+
+        if !changed.is_empty() {
+            differences.push(format!("{}: {}", before.name.red(), changed.join(", ")));
+        }
+    }
+
+    differences
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use ps2_filetypes::MODE_HIDDEN;
+
+    fn packed_save(parent: &Path) -> String {
+        let folder = parent.join("save");
+        std::fs::create_dir(&folder).unwrap();
+        std::fs::write(folder.join("BOOT.ELF"), b"\x7fELF boot").unwrap();
+        std::fs::write(folder.join("DATA.BIN"), vec![3; 2500]).unwrap();
+        let config = Config {
+            name: "BASLUS-20001".to_string(),
+            timestamp: Some(NaiveDate::from_ymd_opt(2004, 6, 1).unwrap().into()),
+            file_modes: [("DATA.BIN".to_string(), FILE_ID | MODE_HIDDEN)].into(),
+            ..Default::default()
+        };
+        let psu = parent.join("save.psu");
+        pack_with_config(&folder, &config, &psu, &Logger::new(Verbosity::Quiet)).unwrap();
+
+        psu.display().to_string()
+    }
+
+    #[test]
+    fn packed_save_round_trips() {
+        let parent = tempfile::tempdir().unwrap();
+        let file = packed_save(parent.path());
+
+        round_trip(RoundTripArgs { file }, &Logger::new(Verbosity::Quiet)).unwrap();
+    }
+
+    #[test]
+    fn changed_entries_are_described() {
+        let parent = tempfile::tempdir().unwrap();
+        let data = std::fs::read(packed_save(parent.path())).unwrap();
+        let original = PSUParser::read(&data).unwrap().entries;
+        let mut changed = original.clone();
+        changed[4].id = FILE_ID;
+        changed[4].contents = Some(vec![0; 2500]);
+
+        let differences = diff_entries(&original, &changed);
+
+        assert_eq!(differences.len(), 1);
+        assert!(differences[0].contains("DATA.BIN"));
+        assert!(differences[0].contains("mode 0xa497 -> 0x8497, contents differ"));
+    }
+}
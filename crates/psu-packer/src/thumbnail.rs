@@ -0,0 +1,109 @@
+use crate::Error;
+use image::codecs::png::PngEncoder;
+use image::{Rgba, RgbaImage};
+use ps2_filetypes::{decompress, BinReader, ICNParser, IconSys, PSUEntryKind, PSUParser, PSU};
+use std::path::Path;
+
+/// Thumbnails are square, this bounds their width and height in pixels
+pub const MAX_THUMBNAIL_SIZE: u32 = 2048;
+
+/// Renders the icon a PSU shows in the browser as a `size`x`size` PNG. That's the icon
+/// icon.sys lists, or `icon.icn` when there's no icon.sys. Returns `None` if the save
+/// doesn't contain it.
+pub fn psu_thumbnail(psu: &Path, size: u32) -> Result<Option<Vec<u8>>, Error> {
+    check_thumbnail_size(size)?;
+    let psu = PSUParser::read(&decompress(&std::fs::read(psu)?)?)?;
+
+    let icon_file = match file_contents(&psu, "icon.sys") {
+        Some(icon_sys) => IconSys::try_new(icon_sys.to_vec())?.icon_file,
+        None => "icon.icn".to_string(),
+    };
+    let Some(icon) = file_contents(&psu, &icon_file) else {
+        return Ok(None);
+    };
+
+    Ok(Some(encode_png(&ICNParser::read(icon)?.render(size))?))
+}
+
+/// A grey tile with a lighter border, for listings that need an image for every save
+pub fn placeholder_thumbnail(size: u32) -> Result<Vec<u8>, Error> {
+    check_thumbnail_size(size)?;
+    let border = (size / 16).max(1);
+    let image = RgbaImage::from_fn(size, size, |x, y| {
+        if x < border || y < border || x >= size - border || y >= size - border {
+            Rgba([96, 96, 96, 255])
+        } else {
+            Rgba([48, 48, 48, 255])
+        }
+    });
+
+    encode_png(&image)
+}
+
+fn check_thumbnail_size(size: u32) -> Result<(), Error> {
+    if size == 0 || size > MAX_THUMBNAIL_SIZE {
+        return Err(Error::InvalidThumbnailSize(size));
+    }
+
+    Ok(())
+}
+
+fn file_contents<'a>(psu: &'a PSU, name: &str) -> Option<&'a [u8]> {
+    psu.entries
+        .iter()
+        .filter(|entry| matches!(entry.kind, PSUEntryKind::File))
+        .find(|entry| entry.name == name)
+        .and_then(|entry| entry.contents.as_deref())
+}
+
+fn encode_png(image: &RgbaImage) -> Result<Vec<u8>, Error> {
+    let mut png = Vec::new();
+    image
+        .write_with_encoder(PngEncoder::new(&mut png))
+        .map_err(std::io::Error::other)?;
+
+    Ok(png)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::logger::{Logger, Verbosity};
+    use crate::{pack_with_config, Config};
+    use ps2_filetypes::{BinWriter, ICNWriter, ICN};
+
+    #[test]
+    fn renders_the_icon_of_a_save() {
+        let folder = tempfile::tempdir().unwrap();
+        let output = tempfile::tempdir().unwrap();
+        let logo = RgbaImage::from_pixel(16, 16, Rgba([255, 0, 0, 255]));
+        let icn = ICNWriter::new(ICN::flat_plane(&logo)).write().unwrap();
+        std::fs::write(folder.path().join("icon.icn"), icn).unwrap();
+        let psu = output.path().join("save.psu");
+        let config = Config {
+            name: "BASLUS-20001".to_string(),
+            ..Default::default()
+        };
+        pack_with_config(folder.path(), &config, &psu, &Logger::new(Verbosity::Quiet)).unwrap();
+
+        let png = psu_thumbnail(&psu, 64).unwrap().unwrap();
+
+        let image = image::load_from_memory(&png).unwrap().to_rgba8();
+        assert_eq!(image.dimensions(), (64, 64));
+        let center = image.get_pixel(32, 32).0;
+        assert_eq!(center[3], 255);
+        assert!(center[0] > center[1] && center[0] > center[2]);
+    }
+
+    #[test]
+    fn placeholder_is_a_png_of_the_requested_size() {
+        let png = placeholder_thumbnail(32).unwrap();
+
+        let image = image::load_from_memory(&png).unwrap();
+        assert_eq!((image.width(), image.height()), (32, 32));
+        assert!(matches!(
+            placeholder_thumbnail(0),
+            Err(Error::InvalidThumbnailSize(0))
+        ));
+    }
+}
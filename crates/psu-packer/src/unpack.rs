@@ -0,0 +1,43 @@
+use argh::FromArgs;
+use colored::Colorize;
+use ps2_filetypes::{BinReader, PSUParser};
+use psu_packer::logger::Logger;
+use psu_packer::{extract, save_folder_name, Error};
+use std::path::PathBuf;
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "unpack",
+    description = "Extract a PSU into a folder, restoring each file's timestamps"
+)]
+pub struct UnpackArgs {
+    /// psu file to extract
+    #[argh(positional)]
+    file: String,
+    /// directory to extract into, defaults to the save's folder name
+    #[argh(option, short = 'o')]
+    output: Option<String>,
+}
+
+pub fn unpack(args: UnpackArgs, log: &Logger) -> Result<(), Error> {
+    let psu = PSUParser::read(&std::fs::read(&args.file)?)?;
+
+    let output = match args.output {
+        Some(output) => PathBuf::from(output),
+        None => PathBuf::from(save_folder_name(&psu).ok_or_else(|| {
+            std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "The save's folder name isn't safe to extract to, pick one with -o",
+            )
+        })?),
+    };
+    extract(&psu, &output, log)?;
+
+    log.info(format!(
+        "Extracted to {}",
+        output.display().to_string().green()
+    ));
+
+    Ok(())
+}
@@ -0,0 +1,530 @@
+use crate::{
+    ascii_name, case_mismatches, check_config, check_name, correct_case, nested_projects,
+    parse_config, sanitize_name, Config, Error, MAX_NAME_LENGTH,
+};
+use ps2_filetypes::sjis::{decode_text, encode_sjis};
+use ps2_filetypes::{IconSys, TitleCfg, TITLE_SIZE};
+use serde::Serialize;
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+const MAX_SAVE_SIZE: u64 = 8 * 1024 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// What's wrong with a save, serialized with a `code` tag for tooling
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "code", rename_all = "snake_case")]
+pub enum Problem {
+    NameTooLong { max: usize },
+    NameInvalid,
+    NameSuggestion { name: String },
+    ConfigParseFailed { error: String },
+    ConfigInvalid { error: String },
+    IncludeMissing { file: String },
+    SaveTooLarge,
+    IconSysMissing,
+    IconSysParseFailed { error: String },
+    TitleMisDecoded,
+    TitleNotShiftJis,
+    TitleTooLong { length: usize, max: usize },
+    TitleNotDisplayable { chars: String },
+    IconMissing { icon: String },
+    TitleCfgEncoding,
+    TitleCfgMissingFields,
+    BootFileMissing { boot: String },
+    NoBootFile,
+    NestedProject { child: String },
+    ConfigCaseMismatch { listed: String, actual: String },
+    FileNameNotAscii { file: String, ascii: String },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::NameTooLong { max } => {
+                write!(f, "Folder name is longer than {max} characters")
+            }
+            Problem::NameInvalid => write!(f, "Folder name must match [a-zA-Z0-9_- ]+"),
+            Problem::NameSuggestion { name } => {
+                write!(f, "Use \"{name}\" as the folder name to fix it")
+            }
+            Problem::ConfigParseFailed { error } => write!(f, "Failed to parse psu.toml: {error}"),
+            Problem::ConfigInvalid { error } => write!(f, "psu.toml is invalid: {error}"),
+            Problem::IncludeMissing { file } => {
+                write!(f, "psu.toml includes {file}, which doesn't exist")
+            }
+            Problem::SaveTooLarge => write!(f, "Save is larger than a standard 8MB memory card"),
+            Problem::IconSysMissing => {
+                write!(
+                    f,
+                    "icon.sys is missing, the save won't show up in the browser"
+                )
+            }
+            Problem::IconSysParseFailed { error } => write!(f, "Failed to parse icon.sys: {error}"),
+            Problem::TitleMisDecoded => write!(
+                f,
+                "icon.sys title may be mis-decoded, unrecognized characters show up as ? \
+                 and are saved that way"
+            ),
+            Problem::TitleNotShiftJis => write!(
+                f,
+                "icon.sys title contains characters that can't be encoded as Shift-JIS"
+            ),
+            Problem::TitleTooLong { length, max } => {
+                write!(f, "icon.sys title is {length} bytes, the limit is {max}")
+            }
+            Problem::TitleNotDisplayable { chars } => write!(
+                f,
+                "icon.sys title contains {chars}, which the PS2 browser can't display"
+            ),
+            Problem::IconMissing { icon } => {
+                write!(f, "icon.sys references {icon}, which doesn't exist")
+            }
+            Problem::TitleCfgEncoding => write!(f, "title.cfg isn't valid UTF-8 or Shift-JIS"),
+            Problem::TitleCfgMissingFields => write!(f, "title.cfg is missing mandatory fields"),
+            Problem::BootFileMissing { boot } => {
+                write!(f, "title.cfg boots {boot}, which doesn't exist")
+            }
+            Problem::NoBootFile => write!(f, "title.cfg has no boot file"),
+            Problem::NestedProject { child } => write!(
+                f,
+                "{child} has its own psu.toml, did you mean to validate that folder instead?"
+            ),
+            Problem::ConfigCaseMismatch { listed, actual } => {
+                write!(f, "psu.toml lists {listed}, but the file is named {actual}")
+            }
+            Problem::FileNameNotAscii { file, ascii } => write!(
+                f,
+                "{file} can't be displayed on a PS2, rename it to {ascii} or set ascii_names"
+            ),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    #[serde(flatten)]
+    pub problem: Problem,
+    /// Not part of the report, fixes are applied interactively
+    #[serde(skip)]
+    pub fix: Option<Fix>,
+}
+
+impl Issue {
+    fn error(problem: Problem) -> Self {
+        Self {
+            severity: Severity::Error,
+            problem,
+            fix: None,
+        }
+    }
+
+    fn warning(problem: Problem) -> Self {
+        Self {
+            severity: Severity::Warning,
+            problem,
+            fix: None,
+        }
+    }
+
+    fn with_fix(self, fix: Option<Fix>) -> Self {
+        Self { fix, ..self }
+    }
+}
+
+/// A repair that can be applied to the folder in one step
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Fix {
+    /// Points the list, copy and delete icons at the same file
+    UseIconForAll(String),
+    /// Rewrites psu.toml entries to the case of the files on disk
+    CorrectConfigCase,
+}
+
+impl Fix {
+    pub fn apply(&self, folder: &Path) -> Result<(), Error> {
+        match self {
+            Fix::UseIconForAll(icon) => {
+                let path = folder.join("icon.sys");
+                let mut icon_sys = IconSys::try_new(std::fs::read(&path)?)?;
+                icon_sys.icon_file = icon.clone();
+                icon_sys.icon_copy_file = icon.clone();
+                icon_sys.icon_delete_file = icon.clone();
+                std::fs::write(path, icon_sys.to_bytes()?)?;
+            }
+            Fix::CorrectConfigCase => {
+                let config = read_config_lenient(folder)
+                    .ok_or(std::io::Error::from(std::io::ErrorKind::NotFound))?;
+                correct_case(folder, &config)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Runs every check against the folder and collects all issues instead of stopping at the first.
+/// `strict_display` also warns about title characters that encode fine but render wrong.
+pub fn validate(folder: &Path, strict_display: bool) -> Vec<Issue> {
+    validate_cancellable(folder, strict_display, |_| true).unwrap_or_default()
+}
+
+/// How many steps `validate_cancellable` reports
+pub const CHECK_COUNT: usize = 7;
+
+/// Like `validate`, but calls `step` with the number of checks done before each one and
+/// after the last. Stops and returns `None` as soon as `step` returns false.
+pub fn validate_cancellable(
+    folder: &Path,
+    strict_display: bool,
+    mut step: impl FnMut(usize) -> bool,
+) -> Option<Vec<Issue>> {
+    type Check<'a> = &'a dyn Fn(&mut Vec<Issue>);
+    let checks: [Check; CHECK_COUNT] = [
+        &|issues| check_config_file(folder, issues),
+        &|issues| check_size(folder, issues),
+        &|issues| check_icon_sys(folder, strict_display, issues),
+        &|issues| check_title_cfg(folder, issues),
+        &|issues| check_nested_projects(folder, issues),
+        &|issues| check_config_case(folder, issues),
+        &|issues| check_file_names(folder, issues),
+    ];
+
+    let mut issues = Vec::new();
+    for (done, check) in checks.iter().enumerate() {
+        if !step(done) {
+            return None;
+        }
+        check(&mut issues);
+    }
+
+    step(CHECK_COUNT).then_some(issues)
+}
+
+/// Checks psu.toml the way packing reads it, or the folder name the GUI exports under
+/// when there's no psu.toml
+fn check_config_file(folder: &Path, issues: &mut Vec<Issue>) {
+    let config = match parse_config(folder) {
+        Ok(config) => config.map(|(_, config)| config),
+        Err(err) => {
+            let error = match err {
+                Error::Config(error) => error,
+                err => err.to_string(),
+            };
+            issues.push(Issue::error(Problem::ConfigParseFailed { error }));
+            return;
+        }
+    };
+
+    let name = match &config {
+        Some(config) => config.name.clone(),
+        None => folder
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+    };
+    check_name_issues(&name, issues);
+
+    let Some(config) = config else {
+        return;
+    };
+    if let Err(err) = check_config(&config) {
+        issues.push(Issue::error(Problem::ConfigInvalid {
+            error: err.to_string(),
+        }));
+    }
+    // Packing skips these with a warning, which leaves the save without them
+    for file in config.include.iter().flatten() {
+        if !folder.join(file).exists() {
+            issues.push(Issue::error(Problem::IncludeMissing { file: file.clone() }));
+        }
+    }
+}
+
+fn check_name_issues(name: &str, issues: &mut Vec<Issue>) {
+    let too_long = name.len() > MAX_NAME_LENGTH;
+    if too_long {
+        issues.push(Issue::error(Problem::NameTooLong {
+            max: MAX_NAME_LENGTH,
+        }));
+    }
+    let invalid = !check_name(name);
+    if invalid {
+        issues.push(Issue::error(Problem::NameInvalid));
+    }
+    if too_long || invalid {
+        issues.push(Issue::warning(Problem::NameSuggestion {
+            name: sanitize_name(name),
+        }));
+    }
+}
+
+fn check_size(folder: &Path, issues: &mut Vec<Issue>) {
+    let size = std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum::<u64>();
+
+    if size > MAX_SAVE_SIZE {
+        issues.push(Issue::warning(Problem::SaveTooLarge));
+    }
+}
+
+fn check_icon_sys(folder: &Path, strict_display: bool, issues: &mut Vec<Issue>) {
+    let Ok(bytes) = std::fs::read(folder.join("icon.sys")) else {
+        issues.push(Issue::error(Problem::IconSysMissing));
+        return;
+    };
+
+    let icon_sys = match IconSys::try_new(bytes) {
+        Ok(icon_sys) => icon_sys,
+        Err(err) => {
+            issues.push(Issue::error(Problem::IconSysParseFailed {
+                error: err.to_string(),
+            }));
+            return;
+        }
+    };
+
+    if !icon_sys.title_decoded_cleanly {
+        issues.push(Issue::warning(Problem::TitleMisDecoded));
+    }
+    let encoded_title = encode_sjis(&icon_sys.title);
+    if encoded_title.chunks(2).any(|pair| pair == [0, 0]) {
+        issues.push(Issue::error(Problem::TitleNotShiftJis));
+    }
+    if encoded_title.len() > TITLE_SIZE {
+        issues.push(Issue::error(Problem::TitleTooLong {
+            length: encoded_title.len(),
+            max: TITLE_SIZE,
+        }));
+    }
+    if strict_display {
+        let hidden = undisplayable_chars(&icon_sys.title);
+        if !hidden.is_empty() {
+            issues.push(Issue::warning(Problem::TitleNotDisplayable {
+                chars: hidden,
+            }));
+        }
+    }
+
+    // Copy and delete icons are often left pointing at files that were never shipped,
+    // the icon the list shows is usually fine to reuse for both
+    let fix = [icon_sys.icon_file.as_str(), "icon.icn"]
+        .into_iter()
+        .find(|icon| !icon.is_empty() && folder.join(icon).is_file())
+        .map(|icon| Fix::UseIconForAll(icon.to_string()));
+    if !folder.join(&icon_sys.icon_file).is_file() {
+        issues.push(
+            Issue::error(Problem::IconMissing {
+                icon: icon_sys.icon_file.clone(),
+            })
+            .with_fix(fix.clone()),
+        );
+    }
+    for icon in [&icon_sys.icon_copy_file, &icon_sys.icon_delete_file] {
+        if !folder.join(icon).is_file() {
+            issues.push(
+                Issue::warning(Problem::IconMissing { icon: icon.clone() }).with_fix(fix.clone()),
+            );
+        }
+    }
+}
+
+/// Characters that encode to a code point outside the ranges the browser's font covers
+fn undisplayable_chars(title: &str) -> String {
+    let mut chars = title
+        .chars()
+        .filter(|c| match encode_sjis(&c.to_string())[..] {
+            // Unencodable characters are already reported as errors
+            [0, 0] => false,
+            [high, low] => !is_displayable(high, low),
+            _ => false,
+        })
+        .collect::<Vec<_>>();
+    chars.dedup();
+
+    chars.into_iter().collect()
+}
+
+fn is_displayable(high: u8, low: u8) -> bool {
+    match high {
+        // The half-width space PS2 titles use
+        0x80 => low == 0x3F,
+        // Full-width punctuation and symbols
+        0x81 => matches!(low, 0x40..=0xAC),
+        // Digits, Latin letters and hiragana, the gaps between them are unassigned
+        0x82 => matches!(low, 0x4F..=0x58 | 0x60..=0x79 | 0x81..=0x9A | 0x9F..=0xF1),
+        // Katakana
+        0x83 => matches!(low, 0x40..=0x96),
+        _ => false,
+    }
+}
+
+fn check_title_cfg(folder: &Path, issues: &mut Vec<Issue>) {
+    // title.cfg is only needed for OPL, so it's fine for it to be missing
+    let Ok(bytes) = std::fs::read(folder.join("title.cfg")) else {
+        return;
+    };
+
+    let Some((contents, _)) = decode_text(&bytes) else {
+        issues.push(Issue::error(Problem::TitleCfgEncoding));
+        return;
+    };
+
+    let title_cfg = TitleCfg::new(contents);
+    if !title_cfg.has_mandatory_fields() {
+        issues.push(Issue::warning(Problem::TitleCfgMissingFields));
+    }
+
+    match title_cfg.index_map.get("boot") {
+        Some(boot) if !boot.is_empty() => {
+            if !folder.join(boot).is_file() {
+                issues.push(Issue::error(Problem::BootFileMissing {
+                    boot: boot.clone(),
+                }));
+            }
+        }
+        _ => issues.push(Issue::error(Problem::NoBootFile)),
+    }
+}
+
+/// psu.toml is parsed leniently here, a broken one is reported when packing
+fn read_config_lenient(folder: &Path) -> Option<Config> {
+    let contents = std::fs::read_to_string(folder.join("psu.toml")).ok()?;
+    let mut table = contents.parse::<toml::Table>().ok()?;
+
+    table.remove("config")?.try_into().ok()
+}
+
+/// The PS2 is case sensitive even when the filesystem the save is built on isn't
+fn check_config_case(folder: &Path, issues: &mut Vec<Issue>) {
+    let Some(config) = read_config_lenient(folder) else {
+        return;
+    };
+
+    for (listed, actual) in case_mismatches(folder, &config) {
+        issues.push(
+            Issue::warning(Problem::ConfigCaseMismatch { listed, actual })
+                .with_fix(Some(Fix::CorrectConfigCase)),
+        );
+    }
+}
+
+/// A subfolder with its own psu.toml usually means the parent was opened by mistake
+fn check_nested_projects(folder: &Path, issues: &mut Vec<Issue>) {
+    for child in nested_projects(folder) {
+        issues.push(Issue::warning(Problem::NestedProject { child }));
+    }
+}
+
+/// Packing refuses non-ASCII names unless psu.toml opts into transliterating them
+fn check_file_names(folder: &Path, issues: &mut Vec<Issue>) {
+    if read_config_lenient(folder).is_some_and(|config| config.ascii_names) {
+        return;
+    }
+
+    let names = std::fs::read_dir(folder)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .map(|entry| entry.file_name().to_string_lossy().to_string())
+        .filter(|name| !name.is_ascii());
+
+    for file in names {
+        issues.push(Issue::error(Problem::FileNameNotAscii {
+            ascii: ascii_name(&file),
+            file,
+        }));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problems(folder: &Path) -> Vec<Problem> {
+        let mut issues = Vec::new();
+        check_config_file(folder, &mut issues);
+        issues.into_iter().map(|issue| issue.problem).collect()
+    }
+
+    #[test]
+    fn name_comes_from_psu_toml_not_the_folder() {
+        let parent = tempfile::tempdir().unwrap();
+        let folder = parent.path().join("not a valid name!");
+        std::fs::create_dir(&folder).unwrap();
+        std::fs::write(folder.join("psu.toml"), "[config]\nname = \"SAVE\"\n").unwrap();
+        assert_eq!(problems(&folder), []);
+
+        std::fs::write(folder.join("psu.toml"), "[config]\nname = \"BAD/NAME\"\n").unwrap();
+        assert!(problems(&folder).contains(&Problem::NameInvalid));
+    }
+
+    #[test]
+    fn broken_config_and_missing_includes_are_reported() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("psu.toml"), "[config\n").unwrap();
+        assert!(matches!(
+            problems(folder.path())[..],
+            [Problem::ConfigParseFailed { .. }]
+        ));
+
+        std::fs::write(folder.path().join("BOOT.ELF"), []).unwrap();
+        std::fs::write(
+            folder.path().join("psu.toml"),
+            "[config]\nname = \"SAVE\"\ninclude = [\"BOOT.ELF\", \"icon.sys\"]\nexclude = []\n",
+        )
+        .unwrap();
+        let problems = problems(folder.path());
+        assert!(matches!(problems[0], Problem::ConfigInvalid { .. }));
+        assert_eq!(
+            problems[1..],
+            [Problem::IncludeMissing {
+                file: "icon.sys".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn missing_delete_icon_is_a_fixable_warning() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("icon.icn"), [0; 16]).unwrap();
+        let icon_sys = IconSys {
+            icon_delete_file: "delete.icn".to_string(),
+            ..Default::default()
+        };
+        std::fs::write(folder.path().join("icon.sys"), icon_sys.to_bytes().unwrap()).unwrap();
+
+        let mut issues = Vec::new();
+        check_icon_sys(folder.path(), false, &mut issues);
+        let [issue] = &issues[..] else {
+            panic!("expected one issue, got {issues:?}");
+        };
+        assert_eq!(issue.severity, Severity::Warning);
+        assert_eq!(
+            issue.problem,
+            Problem::IconMissing {
+                icon: "delete.icn".to_string()
+            }
+        );
+        assert_eq!(issue.fix, Some(Fix::UseIconForAll("icon.icn".to_string())));
+
+        issue.fix.as_ref().unwrap().apply(folder.path()).unwrap();
+        let mut issues = Vec::new();
+        check_icon_sys(folder.path(), false, &mut issues);
+        assert!(issues.is_empty(), "{issues:?}");
+    }
+}
@@ -0,0 +1,132 @@
+use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::logger::Logger;
+use psu_packer::validate::{validate, Issue, Severity};
+use psu_packer::Error;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "validate",
+    description = "Check every project with a psu.toml in a directory, failing if any has errors"
+)]
+pub struct ValidateArgs {
+    /// directory containing the projects, or a single project
+    #[argh(positional)]
+    dir: String,
+    /// print the report as JSON
+    #[argh(switch)]
+    json: bool,
+    /// also warn about title characters the PS2 browser can't display
+    #[argh(switch)]
+    strict_display: bool,
+}
+
+struct Report {
+    project: PathBuf,
+    issues: Vec<Issue>,
+}
+
+/// A report as printed by `--json`, each issue carries its English message next to its code
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    project: &'a Path,
+    issues: Vec<JsonIssue<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonIssue<'a> {
+    #[serde(flatten)]
+    issue: &'a Issue,
+    message: String,
+}
+
+pub fn validate_projects(args: ValidateArgs, log: &Logger) -> Result<(), Error> {
+    let reports = projects(Path::new(&args.dir))?
+        .into_iter()
+        .map(|project| Report {
+            issues: validate(&project, args.strict_display),
+            project,
+        })
+        .collect::<Vec<_>>();
+
+    if args.json {
+        let json = reports
+            .iter()
+            .map(|report| JsonReport {
+                project: &report.project,
+                issues: report
+                    .issues
+                    .iter()
+                    .map(|issue| JsonIssue {
+                        issue,
+                        message: issue.problem.to_string(),
+                    })
+                    .collect(),
+            })
+            .collect::<Vec<_>>();
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&json).map_err(std::io::Error::other)?
+        );
+    } else {
+        print_report(&reports, log);
+    }
+
+    let failed = reports
+        .iter()
+        .filter(|report| has_errors(&report.issues))
+        .count();
+    if failed > 0 {
+        return Err(Error::ValidationFailed(failed));
+    }
+
+    Ok(())
+}
+
+/// The directory itself if it's a project, followed by every subfolder that is
+fn projects(dir: &Path) -> Result<Vec<PathBuf>, Error> {
+    let mut projects = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.join("psu.toml").is_file())
+        .collect::<Vec<_>>();
+    projects.sort();
+    if dir.join("psu.toml").is_file() {
+        projects.insert(0, dir.to_path_buf());
+    }
+
+    Ok(projects)
+}
+
+fn has_errors(issues: &[Issue]) -> bool {
+    issues.iter().any(|issue| issue.severity == Severity::Error)
+}
+
+fn print_report(reports: &[Report], log: &Logger) {
+    for report in reports {
+        let project = report.project.display().to_string();
+        if report.issues.is_empty() {
+            log.info(format!("{} {}", project.green(), "ok".green()));
+            continue;
+        }
+
+        if has_errors(&report.issues) {
+            eprintln!("{}", project.red());
+        } else {
+            log.warn(project.yellow());
+        }
+        for issue in &report.issues {
+            match issue.severity {
+                Severity::Error => eprintln!("  {} {}", "error:".red(), issue.problem),
+                Severity::Warning => {
+                    log.warn(format!("  {} {}", "warning:".yellow(), issue.problem))
+                }
+            }
+        }
+    }
+
+    log.info(format!("Validated {} project(s)", reports.len()));
+}
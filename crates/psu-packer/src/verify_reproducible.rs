@@ -0,0 +1,49 @@
+use argh::FromArgs;
+use colored::Colorize;
+use psu_packer::logger::{Logger, Verbosity};
+use psu_packer::{resolve_config, resolve_files, write_psu, ConfigOverrides, Error};
+use std::path::PathBuf;
+
+#[derive(Debug, FromArgs)]
+#[argh(
+    subcommand,
+    name = "verify-reproducible",
+    description = "Pack a folder twice and check that both PSUs are byte-identical"
+)]
+pub struct VerifyReproducibleArgs {
+    /// folder to package to psu
+    #[argh(positional)]
+    folder: String,
+}
+
+pub fn verify_reproducible(args: VerifyReproducibleArgs, log: &Logger) -> Result<(), Error> {
+    let folder = PathBuf::from(args.folder);
+    let config = resolve_config(&folder, &ConfigOverrides::default(), log)?;
+    let files = resolve_files(&folder, &config, log)?;
+
+    // Both packs go through the same path a real pack takes, streaming included
+    let temp_dir = tempfile::tempdir()?;
+    let quiet = Logger::new(Verbosity::Quiet);
+    let first = temp_dir.path().join("first.psu");
+    let second = temp_dir.path().join("second.psu");
+    let first_checksums = write_psu(&config, &files, &first, &quiet)?;
+    let second_checksums = write_psu(&config, &files, &second, &quiet)?;
+
+    if first_checksums.sha256 != second_checksums.sha256 {
+        let (first, second) = (std::fs::read(first)?, std::fs::read(second)?);
+        let offset = first
+            .iter()
+            .zip(&second)
+            .position(|(a, b)| a != b)
+            .unwrap_or(first.len().min(second.len()));
+        return Err(Error::NotReproducible(offset));
+    }
+
+    log.info(format!(
+        "{} is reproducible ({} bytes)",
+        config.name.green(),
+        std::fs::metadata(first)?.len()
+    ));
+
+    Ok(())
+}
@@ -1,14 +1,14 @@
 use crate::data::state::AppState;
 use bytesize::ByteSize;
-use eframe::egui::{Color32, Ui};
+use eframe::egui::Ui;
 
 fn size_label(ui: &mut Ui, size: u64) {
     ui.label("Size: ");
     ui.colored_label(
         if size > 8 * 1024 * 1024 {
-            Color32::RED
+            ui.visuals().error_fg_color
         } else {
-            Color32::WHITE
+            ui.visuals().strong_text_color()
         },
         ByteSize::b(size).to_string(),
     );
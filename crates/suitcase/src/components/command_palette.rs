@@ -0,0 +1,301 @@
+use crate::data::state::AppState;
+use crate::io::new_file::NewFile;
+use eframe::egui::{
+    Align2, Context, Key, Modifiers, ScrollArea, TextEdit, ThemePreference, Window,
+};
+
+struct Command {
+    name: &'static str,
+    available: fn(&AppState) -> bool,
+    run: fn(&mut AppState),
+}
+
+fn always(_: &AppState) -> bool {
+    true
+}
+
+fn folder_open(app: &AppState) -> bool {
+    app.opened_folder.is_some()
+}
+
+fn writable(app: &AppState) -> bool {
+    app.opened_folder.is_some() && !app.read_only
+}
+
+const COMMANDS: &[Command] = &[
+    Command {
+        name: "Open Folder",
+        available: always,
+        run: AppState::open_folder,
+    },
+    Command {
+        name: "Open Save",
+        available: always,
+        run: AppState::open_save,
+    },
+    Command {
+        name: "Close Folder",
+        available: folder_open,
+        run: AppState::close_folder,
+    },
+    Command {
+        name: "Add Files",
+        available: writable,
+        run: AppState::add_files,
+    },
+    Command {
+        name: "Save File",
+        available: writable,
+        run: AppState::save_file,
+    },
+    Command {
+        name: "Export PSU",
+        available: folder_open,
+        run: AppState::export_psu,
+    },
+    Command {
+        name: "Build Memory Card",
+        available: always,
+        run: AppState::build_memory_card,
+    },
+    Command {
+        name: "Validate Save",
+        available: folder_open,
+        run: AppState::validate,
+    },
+    Command {
+        name: "Initialize Project",
+        available: writable,
+        run: AppState::initialize_project,
+    },
+    Command {
+        name: "New Title Configuration",
+        available: writable,
+        run: |app| app.new_file(NewFile::TitleCfg),
+    },
+    Command {
+        name: "New icon.sys",
+        available: writable,
+        run: |app| app.new_file(NewFile::IconSys),
+    },
+    Command {
+        name: "New psu.toml",
+        available: writable,
+        run: |app| app.new_file(NewFile::PsuToml),
+    },
+    Command {
+        name: "New Text File",
+        available: writable,
+        run: |app| app.new_file(NewFile::Text),
+    },
+    Command {
+        name: "Create ICN",
+        available: writable,
+        run: AppState::create_icn,
+    },
+    Command {
+        name: "Boot OSDSYS",
+        available: |app| !app.pcsx2_path.is_empty(),
+        run: AppState::start_pcsx2,
+    },
+    Command {
+        name: "Settings",
+        available: always,
+        run: AppState::open_settings,
+    },
+    Command {
+        name: "Theme: Follow System",
+        available: always,
+        run: |app| app.set_theme(ThemePreference::System),
+    },
+    Command {
+        name: "Theme: Dark",
+        available: always,
+        run: |app| app.set_theme(ThemePreference::Dark),
+    },
+    Command {
+        name: "Theme: Light",
+        available: always,
+        run: |app| app.set_theme(ThemePreference::Light),
+    },
+    Command {
+        name: "Toggle High Contrast",
+        available: always,
+        run: |app| {
+            app.high_contrast = !app.high_contrast;
+            app.apply_theme();
+        },
+    },
+    Command {
+        name: "Toggle Strict Title Validation",
+        available: always,
+        run: |app| app.strict_display = !app.strict_display,
+    },
+    Command {
+        name: "Toggle Normalize Line Endings",
+        available: always,
+        run: |app| app.normalize_line_endings = !app.normalize_line_endings,
+    },
+    Command {
+        name: "Toggle Guide Missing Files",
+        available: always,
+        run: |app| app.guide_missing_files = !app.guide_missing_files,
+    },
+    Command {
+        name: "Toggle Save Summary",
+        available: folder_open,
+        run: |app| app.show_summary = !app.show_summary,
+    },
+    Command {
+        name: "Toggle Reload External Changes",
+        available: always,
+        run: |app| app.watch_files = !app.watch_files,
+    },
+];
+
+/// Runs any menu action by typing part of its name
+#[derive(Default)]
+pub struct CommandPalette {
+    open: bool,
+    query: String,
+    selected: usize,
+}
+
+impl CommandPalette {
+    pub fn open(&mut self) {
+        self.open = true;
+        self.query.clear();
+        self.selected = 0;
+    }
+
+    pub fn show(&mut self, ctx: &Context, app: &mut AppState) {
+        if !self.open {
+            return;
+        }
+
+        let matches = matching_commands(&self.query, app);
+        let (up, down, enter, escape) = ctx.input_mut(|input| {
+            (
+                input.consume_key(Modifiers::NONE, Key::ArrowUp),
+                input.consume_key(Modifiers::NONE, Key::ArrowDown),
+                input.consume_key(Modifiers::NONE, Key::Enter),
+                input.consume_key(Modifiers::NONE, Key::Escape),
+            )
+        });
+        if down {
+            self.selected = (self.selected + 1).min(matches.len().saturating_sub(1));
+        }
+        if up {
+            self.selected = self.selected.saturating_sub(1);
+        }
+
+        let mut chosen = enter.then(|| matches.get(self.selected)).flatten().copied();
+        Window::new("Command Palette")
+            .title_bar(false)
+            .resizable(false)
+            .anchor(Align2::CENTER_TOP, [0.0, 64.0])
+            .fixed_size([360.0, 0.0])
+            .show(ctx, |ui| {
+                let response = ui.add(
+                    TextEdit::singleline(&mut self.query)
+                        .hint_text("Type a command")
+                        .desired_width(f32::INFINITY),
+                );
+                response.request_focus();
+                if response.changed() {
+                    self.selected = 0;
+                }
+
+                ui.separator();
+                ScrollArea::vertical().max_height(240.0).show(ui, |ui| {
+                    if matches.is_empty() {
+                        ui.weak("No matching commands");
+                    }
+                    for (index, command) in matches.iter().enumerate() {
+                        let label = ui.selectable_label(index == self.selected, command.name);
+                        if index == self.selected && (up || down) {
+                            label.scroll_to_me(None);
+                        }
+                        if label.clicked() {
+                            chosen = Some(*command);
+                        }
+                    }
+                });
+            });
+
+        if let Some(command) = chosen {
+            (command.run)(app);
+            self.open = false;
+        } else if escape {
+            self.open = false;
+        }
+    }
+}
+
+/// Available commands that fuzzy match the query, best match first
+fn matching_commands(query: &str, app: &AppState) -> Vec<&'static Command> {
+    let mut matches = COMMANDS
+        .iter()
+        .filter(|command| (command.available)(app))
+        .filter_map(|command| Some((fuzzy_score(query, command.name)?, command)))
+        .collect::<Vec<_>>();
+    // Stable, so equal scores keep the order of the list above
+    matches.sort_by_key(|(score, _)| -score);
+
+    matches.into_iter().map(|(_, command)| command).collect()
+}
+
+/// Scores `candidate` if every character of `query` appears in it in order, ignoring case.
+/// Runs of consecutive characters and matches at the start of a word score higher.
+fn fuzzy_score(query: &str, candidate: &str) -> Option<i32> {
+    let candidate = candidate.to_lowercase().chars().collect::<Vec<_>>();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous = None;
+
+    for c in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let index = position + candidate[position..].iter().position(|&other| other == c)?;
+        score += 1;
+        if index == 0 || candidate[index - 1] == ' ' {
+            score += 3;
+        }
+        if previous.is_some_and(|previous| previous + 1 == index) {
+            score += 2;
+        }
+        previous = Some(index);
+        position = index + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_score_prefers_word_starts_and_runs() {
+        assert_eq!(fuzzy_score("xyz", "Open Folder"), None);
+        assert_eq!(fuzzy_score("fo", "Open Folder"), Some(7));
+        assert_eq!(fuzzy_score("FO", "open folder"), Some(7));
+        assert!(fuzzy_score("of", "Open Folder") > fuzzy_score("pf", "Open Folder"));
+        assert!(
+            fuzzy_score("exp", "Export PSU") > fuzzy_score("exp", "Toggle Reload External Changes")
+        );
+    }
+
+    #[test]
+    fn matching_commands_skips_unavailable_ones() {
+        let mut app = AppState::new();
+        let names = |app: &AppState| {
+            matching_commands("fold", app)
+                .iter()
+                .map(|command| command.name)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(names(&app), ["Open Folder"]);
+        app.opened_folder = Some(std::path::PathBuf::from("save"));
+        assert_eq!(names(&app), ["Open Folder", "Close Folder"]);
+    }
+}
@@ -1,3 +1,4 @@
+use crate::messages::{tr, Message};
 use eframe::egui::Context;
 use std::collections::HashMap;
 use std::path::PathBuf;
@@ -80,3 +81,31 @@ impl Dialogs for &Context {
         rfd::FileDialog::new().pick_files()
     }
 }
+
+pub enum UnsavedChoice {
+    Save,
+    Discard,
+    Cancel,
+}
+
+/// Asks whether to save, discard or keep editing before something with unsaved changes closes
+pub fn confirm_unsaved(description: String) -> UnsavedChoice {
+    let result = rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Warning)
+        .set_title(tr(Message::UnsavedTitle))
+        .set_description(description)
+        .set_buttons(rfd::MessageButtons::YesNoCancelCustom(
+            "Save".to_string(),
+            "Discard".to_string(),
+            "Cancel".to_string(),
+        ))
+        .show();
+
+    match result {
+        rfd::MessageDialogResult::Yes => UnsavedChoice::Save,
+        rfd::MessageDialogResult::No => UnsavedChoice::Discard,
+        rfd::MessageDialogResult::Custom(label) if label == "Save" => UnsavedChoice::Save,
+        rfd::MessageDialogResult::Custom(label) if label == "Discard" => UnsavedChoice::Discard,
+        _ => UnsavedChoice::Cancel,
+    }
+}
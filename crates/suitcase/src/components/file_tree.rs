@@ -1,4 +1,6 @@
+use crate::components::icon_thumbnail::thumbnail;
 use crate::data::virtual_file::VirtualFile;
+use crate::io::open_terminal::open_terminal;
 use crate::AppState;
 use eframe::egui::collapsing_header::CollapsingState;
 use eframe::egui::{
@@ -6,7 +8,29 @@ use eframe::egui::{
     Style, TextWrapMode, Ui,
 };
 use std::collections::HashMap;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::Arc;
+use std::thread;
+
+enum IndexMessage {
+    Progress(usize),
+    Done(HashMap<PathBuf, Vec<PathBuf>>),
+}
+
+/// A folder being indexed on a worker thread, dropping it cancels the walk
+struct IndexJob {
+    rx: Receiver<IndexMessage>,
+    cancel: Arc<AtomicBool>,
+    indexed: usize,
+}
+
+impl Drop for IndexJob {
+    fn drop(&mut self) {
+        self.cancel.store(true, Ordering::Relaxed);
+    }
+}
 
 pub struct FileTree {
     show_timestamp: bool,
@@ -14,6 +38,7 @@ pub struct FileTree {
     id: Id,
     expanded: HashMap<PathBuf, bool>,
     dir_cache: HashMap<PathBuf, Vec<PathBuf>>,
+    index_job: Option<IndexJob>,
 }
 
 fn set_menu_style(style: &mut Style) {
@@ -32,6 +57,7 @@ impl FileTree {
             id: Id::new("file_tree"),
             expanded: HashMap::new(),
             dir_cache: HashMap::new(),
+            index_job: None,
         }
     }
 
@@ -47,6 +73,15 @@ impl FileTree {
     }
 
     pub fn show(&mut self, ui: &mut Ui, state: &mut AppState) {
+        self.poll_index_job();
+        if let Some(job) = &self.index_job {
+            ui.horizontal(|ui| {
+                ui.spinner();
+                ui.weak(format!("Indexing {} files...", job.indexed));
+            });
+            ui.ctx().request_repaint();
+        }
+
         set_menu_style(ui.style_mut());
         ScrollArea::new([true, true]).show(ui, |ui| {
             ui.with_layout(
@@ -92,6 +127,15 @@ impl FileTree {
 
         response.inner.context_menu(|ui| {
             ui.set_min_width(100.0);
+            if ui.button("Copy Path").clicked() {
+                ui.ctx().copy_text(path.display().to_string());
+                ui.close_menu();
+            }
+            if ui.button("Open Terminal Here").clicked() {
+                open_terminal(&path);
+                ui.close_menu();
+            }
+            ui.separator();
             ui.button("Export");
             ui.separator();
             ui.button("Delete");
@@ -106,6 +150,19 @@ impl FileTree {
                 .wrap_mode(TextWrapMode::Extend),
         );
 
+        let response = match file_name.to_lowercase().rsplit('.').next() {
+            Some("icn") => response.on_hover_ui(|ui| {
+                let texture = state.thumbnails.for_icn(ui.ctx(), &path);
+                thumbnail(ui, texture.as_ref());
+            }),
+            Some("sys") => response.on_hover_ui(|ui| {
+                let folder = path.parent().unwrap_or(&path);
+                let texture = state.thumbnails.for_folder(ui.ctx(), folder);
+                thumbnail(ui, texture.as_ref());
+            }),
+            _ => response,
+        };
+
         if response.double_clicked() {
             state.open_file(VirtualFile {
                 name: file_name.clone(),
@@ -115,28 +172,42 @@ impl FileTree {
         }
     }
 
-    fn index_folder_internal(&mut self, root: &PathBuf) {
-        let mut folders = Vec::new();
-        let mut files = Vec::new();
-        let children = std::fs::read_dir(&root).expect("failed to read root directory");
+    fn poll_index_job(&mut self) {
+        let Some(job) = &mut self.index_job else {
+            return;
+        };
 
-        for entry in children {
-            let path = entry.unwrap().path();
-            if path.is_dir() {
-                self.index_folder_internal(&path);
-                folders.push(path);
-            } else {
-                files.push(path);
+        while let Ok(message) = job.rx.try_recv() {
+            match message {
+                IndexMessage::Progress(indexed) => job.indexed = indexed,
+                IndexMessage::Done(dir_cache) => {
+                    self.dir_cache = dir_cache;
+                    self.index_job = None;
+                    return;
+                }
             }
         }
-
-        self.dir_cache
-            .insert(root.clone(), [folders, files].concat());
     }
 
-    pub fn index_folder(&mut self, root: &PathBuf) {
-        self.dir_cache.clear();
-        self.index_folder_internal(root);
+    /// Starts indexing `root` in the background, replacing any index that is still running.
+    /// The previous tree stays visible until the new one is ready.
+    pub fn index_folder(&mut self, root: &Path) {
+        let (tx, rx) = mpsc::channel();
+        let cancel = Arc::new(AtomicBool::new(false));
+        self.index_job = Some(IndexJob {
+            rx,
+            cancel: cancel.clone(),
+            indexed: 0,
+        });
+
+        let root = root.to_path_buf();
+        thread::spawn(move || {
+            let mut dir_cache = HashMap::new();
+            let mut indexed = 0;
+            if index_folder_internal(&root, &mut dir_cache, &mut indexed, &tx, &cancel) {
+                _ = tx.send(IndexMessage::Done(dir_cache));
+            }
+        });
     }
 
     // pub fn show(&mut self, ui: &mut Ui, app: &mut AppState) {
@@ -254,3 +325,40 @@ impl FileTree {
     //     });
     // }
 }
+
+/// Walks `root` recursively, returning false if the job was cancelled part way through
+fn index_folder_internal(
+    root: &Path,
+    dir_cache: &mut HashMap<PathBuf, Vec<PathBuf>>,
+    indexed: &mut usize,
+    tx: &Sender<IndexMessage>,
+    cancel: &AtomicBool,
+) -> bool {
+    let mut folders = Vec::new();
+    let mut files = Vec::new();
+    let children = std::fs::read_dir(root).into_iter().flatten().flatten();
+
+    for entry in children {
+        if cancel.load(Ordering::Relaxed) {
+            return false;
+        }
+
+        let path = entry.path();
+        if path.is_dir() {
+            if !index_folder_internal(&path, dir_cache, indexed, tx, cancel) {
+                return false;
+            }
+            folders.push(path);
+        } else {
+            files.push(path);
+        }
+
+        *indexed += 1;
+        if indexed.is_multiple_of(100) {
+            _ = tx.send(IndexMessage::Progress(*indexed));
+        }
+    }
+
+    dir_cache.insert(root.to_path_buf(), [folders, files].concat());
+    true
+}
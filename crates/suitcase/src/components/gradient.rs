@@ -0,0 +1,79 @@
+use eframe::egui::epaint::{Mesh, Vertex, WHITE_UV};
+use eframe::egui::{lerp, pos2, Color32, Painter, Rect, Shape};
+
+/// Cells per side, enough that the Gouraud shaded cells are indistinguishable from true bilinear
+const SUBDIVISIONS: u32 = 16;
+
+/// Samples the icon.sys background at `(u, v)` in `0..=1`, with the corners ordered
+/// top left, top right, bottom left, bottom right like they're stored in the file.
+///
+/// The browser blends the raw 8-bit channels without any gamma correction, so this interpolates
+/// the stored bytes directly: first along the top and bottom edges, then between the two.
+pub fn sample_gradient(corners: &[Color32; 4], u: f32, v: f32) -> Color32 {
+    let channel = |i: usize| {
+        let top = lerp(corners[0][i] as f32..=corners[1][i] as f32, u);
+        let bottom = lerp(corners[2][i] as f32..=corners[3][i] as f32, u);
+        lerp(top..=bottom, v).round() as u8
+    };
+
+    Color32::from_rgb(channel(0), channel(1), channel(2))
+}
+
+/// Paints the four corner background gradient into `rect` with `opacity` applied on top.
+///
+/// A plain quad is split into two triangles, which interpolates along the diagonal and gives a
+/// visible seam, so the rect is subdivided and each vertex is sampled bilinearly instead.
+pub fn paint_gradient(painter: &Painter, rect: Rect, corners: &[Color32; 4], opacity: f32) {
+    let mut mesh = Mesh::default();
+
+    for row in 0..=SUBDIVISIONS {
+        let v = row as f32 / SUBDIVISIONS as f32;
+        for column in 0..=SUBDIVISIONS {
+            let u = column as f32 / SUBDIVISIONS as f32;
+            mesh.vertices.push(Vertex {
+                pos: pos2(lerp(rect.x_range(), u), lerp(rect.y_range(), v)),
+                uv: WHITE_UV,
+                color: sample_gradient(corners, u, v).gamma_multiply(opacity),
+            });
+        }
+    }
+
+    let stride = SUBDIVISIONS + 1;
+    for row in 0..SUBDIVISIONS {
+        for column in 0..SUBDIVISIONS {
+            let i = row * stride + column;
+            mesh.indices
+                .extend_from_slice(&[i, i + 1, i + stride + 1, i, i + stride + 1, i + stride]);
+        }
+    }
+
+    painter.add(Shape::mesh(mesh));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sample_blends_the_stored_bytes_bilinearly() {
+        let corners = [
+            Color32::BLACK,
+            Color32::from_rgb(255, 0, 0),
+            Color32::from_rgb(0, 255, 0),
+            Color32::from_rgb(0, 0, 255),
+        ];
+
+        assert_eq!(sample_gradient(&corners, 0.0, 0.0), corners[0]);
+        assert_eq!(sample_gradient(&corners, 1.0, 0.0), corners[1]);
+        assert_eq!(sample_gradient(&corners, 0.0, 1.0), corners[2]);
+        assert_eq!(sample_gradient(&corners, 1.0, 1.0), corners[3]);
+        assert_eq!(
+            sample_gradient(&corners, 0.5, 0.0),
+            Color32::from_rgb(128, 0, 0)
+        );
+        assert_eq!(
+            sample_gradient(&corners, 0.5, 0.5),
+            Color32::from_rgb(64, 64, 64)
+        );
+    }
+}
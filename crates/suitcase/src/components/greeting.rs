@@ -1,18 +1,30 @@
 use eframe::egui::Ui;
+use crate::components::icon_thumbnail::thumbnail;
 use crate::components::menu_bar::OPEN_FOLDER_KEYBOARD_SHORTCUT;
 use crate::data::state::AppState;
+use crate::messages::{tr, tr_args, Message};
 
 pub fn greeting(ui: &mut Ui, app: &mut AppState) {
     let is_folder_open = app.opened_folder.is_some();
 
     ui.centered_and_justified(|ui| {
         if !is_folder_open {
-            ui.heading(format!(
-                "Open a folder to get started ({})",
-                &ui.ctx().format_shortcut(&OPEN_FOLDER_KEYBOARD_SHORTCUT)
+            ui.heading(tr_args(
+                Message::OpenFolderToStart,
+                &[(
+                    "shortcut",
+                    &ui.ctx().format_shortcut(&OPEN_FOLDER_KEYBOARD_SHORTCUT),
+                )],
             ));
         } else {
-            ui.heading("No open editors");
+            ui.vertical_centered(|ui| {
+                ui.add_space(ui.available_height() / 3.0);
+                let folder = app.opened_folder.clone().unwrap();
+                let texture = app.thumbnails.for_folder(ui.ctx(), &folder);
+                thumbnail(ui, texture.as_ref());
+                ui.add_space(8.0);
+                ui.heading(tr(Message::NoOpenEditors));
+            });
         }
     });
 }
\ No newline at end of file
@@ -0,0 +1,100 @@
+use eframe::egui::{
+    vec2, Align2, ColorImage, Context, FontId, Image, Sense, TextureHandle, TextureOptions, Ui,
+};
+use ps2_filetypes::{BinReader, ICNParser, IconSys};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const THUMBNAIL_SIZE: f32 = 96.0;
+
+/// A value read from a file, reloaded whenever the file's modification time changes
+pub struct Cached<T> {
+    modified: Option<SystemTime>,
+    value: T,
+}
+
+/// Decoded icon textures, only reloaded when the file on disk changes
+#[derive(Default)]
+pub struct IconThumbnails {
+    textures: HashMap<PathBuf, Cached<Option<TextureHandle>>>,
+    icon_files: HashMap<PathBuf, Cached<Option<String>>>,
+}
+
+impl IconThumbnails {
+    /// The icon a save shows in the browser, as listed in its icon.sys or `icon.icn`
+    pub fn for_folder(&mut self, ctx: &Context, folder: &Path) -> Option<TextureHandle> {
+        let icon_sys = folder.join("icon.sys");
+        let icon_file = cached(&mut self.icon_files, &icon_sys, |path| {
+            let icon_sys = IconSys::try_new(std::fs::read(path).ok()?).ok()?;
+            Some(icon_sys.icon_file)
+        })
+        .filter(|name| !name.is_empty())
+        .unwrap_or_else(|| "icon.icn".to_string());
+
+        self.for_icn(ctx, &folder.join(icon_file))
+    }
+
+    pub fn for_icn(&mut self, ctx: &Context, path: &Path) -> Option<TextureHandle> {
+        cached(&mut self.textures, path, |path| {
+            let icn = ICNParser::read(&std::fs::read(path).ok()?).ok()?;
+            let image = icn.decode_texture();
+            Some(ctx.load_texture(
+                path.display().to_string(),
+                ColorImage::from_rgba_unmultiplied(
+                    [image.width() as usize, image.height() as usize],
+                    image.as_raw(),
+                ),
+                TextureOptions::default(),
+            ))
+        })
+    }
+}
+
+pub fn cached<T: Clone>(
+    cache: &mut HashMap<PathBuf, Cached<T>>,
+    path: &Path,
+    load: impl FnOnce(&Path) -> T,
+) -> T {
+    let modified = std::fs::metadata(path)
+        .and_then(|metadata| metadata.modified())
+        .ok();
+
+    match cache.get(path) {
+        Some(entry) if entry.modified == modified => entry.value.clone(),
+        _ => {
+            let value = load(path);
+            cache.insert(
+                path.to_path_buf(),
+                Cached {
+                    modified,
+                    value: value.clone(),
+                },
+            );
+            value
+        }
+    }
+}
+
+/// Draws the thumbnail, or an empty frame when the save has no icon
+pub fn thumbnail(ui: &mut Ui, texture: Option<&TextureHandle>) {
+    let size = vec2(THUMBNAIL_SIZE, THUMBNAIL_SIZE);
+    match texture {
+        Some(texture) => {
+            ui.add(Image::new(texture).fit_to_exact_size(size));
+        }
+        None => {
+            let (rect, _) = ui.allocate_exact_size(size, Sense::hover());
+            let visuals = ui.visuals();
+            ui.painter()
+                .rect_filled(rect, 4.0, visuals.extreme_bg_color);
+            ui.painter().text(
+                rect.center(),
+                Align2::CENTER_CENTER,
+                "No icon",
+                FontId::proportional(12.0),
+                visuals.weak_text_color(),
+            );
+        }
+    }
+}
@@ -0,0 +1,28 @@
+use crate::io::job::Job;
+use eframe::egui::{Button, Context, Id, Modal, ProgressBar};
+
+/// Blocks the UI behind a progress bar until the job finishes or is cancelled
+pub fn job_window<T: Send + 'static>(ctx: &Context, job: &Job<T>) {
+    Modal::new(Id::new("job_window")).show(ctx, |ui| {
+        ui.set_width(300.0);
+        ui.heading(job.title());
+        ui.add_space(4.0);
+
+        let bar = match job.fraction() {
+            Some(fraction) => ProgressBar::new(fraction).show_percentage(),
+            None => ProgressBar::new(0.0).animate(true),
+        };
+        ui.add(bar);
+
+        ui.add_space(4.0);
+        let cancelling = job.is_cancelled();
+        let label = if cancelling {
+            "Cancelling…"
+        } else {
+            "Cancel"
+        };
+        if ui.add_enabled(!cancelling, Button::new(label)).clicked() {
+            job.cancel();
+        }
+    });
+}
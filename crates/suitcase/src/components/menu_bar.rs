@@ -1,7 +1,9 @@
 use crate::components::menu_item::MenuItemComponent;
 use crate::data::state::AppState;
+use crate::io::new_file::NewFile;
+use crate::messages::Language;
 use eframe::egui;
-use eframe::egui::{menu, Context, KeyboardShortcut, Modifiers, Ui};
+use eframe::egui::{menu, Button, Context, KeyboardShortcut, Modifiers, ThemePreference, Ui};
 
 const CTRL_OR_CMD: Modifiers = if cfg!(target_os = "macos") {
     Modifiers::MAC_CMD
@@ -38,9 +40,12 @@ const CREATE_ICN_KEYBOARD_SHORTCUT: KeyboardShortcut =
 
 const OPEN_SETTINGS_KEYBOARD_SHORTCUT: KeyboardShortcut =
     KeyboardShortcut::new(CTRL_OR_CMD, egui::Key::Comma);
+const COMMAND_PALETTE_KEYBOARD_SHORTCUT: KeyboardShortcut =
+    KeyboardShortcut::new(CTRL_OR_CMD_SHIFT, egui::Key::P);
 
 pub fn menu_bar(ui: &mut Ui, app: &mut AppState) {
     let is_folder_open = app.opened_folder.is_some();
+    let is_writable = is_folder_open && !app.read_only;
 
     menu::bar(ui, |ui| {
         ui.menu_button("File", |ui| {
@@ -51,7 +56,14 @@ pub fn menu_bar(ui: &mut Ui, app: &mut AppState) {
                 app.open_folder();
                 ui.close_menu();
             }
-            ui.add_enabled_ui(is_folder_open, |ui| {
+            if ui
+                .add_enabled(is_folder_open, Button::new("Close Folder"))
+                .clicked()
+            {
+                app.close_folder();
+                ui.close_menu();
+            }
+            ui.add_enabled_ui(is_writable, |ui| {
                 if ui
                     .menu_item_shortcut("Add Files", &ADD_FILE_KEYBOARD_SHORTCUT)
                     .clicked()
@@ -66,6 +78,22 @@ pub fn menu_bar(ui: &mut Ui, app: &mut AppState) {
                     app.save_file();
                     ui.close_menu();
                 }
+                ui.menu_button("New File", |ui| {
+                    for kind in NewFile::ALL {
+                        if ui.button(kind.label()).clicked() {
+                            app.new_file(kind);
+                            ui.close_menu();
+                        }
+                    }
+                });
+                if ui
+                    .button("Initialize Project")
+                    .on_hover_text("Create psu.toml, icon.sys and title.cfg if they're missing")
+                    .clicked()
+                {
+                    app.initialize_project();
+                    ui.close_menu();
+                }
                 // ui.separator();
                 // if ui
                 //     .menu_item_shortcut("Create ICN", &CREATE_ICN_KEYBOARD_SHORTCUT)
@@ -84,6 +112,55 @@ pub fn menu_bar(ui: &mut Ui, app: &mut AppState) {
                 ui.close_menu();
             }
         });
+        ui.menu_button("View", |ui| {
+            if ui
+                .menu_item_shortcut("Command Palette", &COMMAND_PALETTE_KEYBOARD_SHORTCUT)
+                .clicked()
+            {
+                app.open_command_palette();
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.menu_button("Theme", |ui| {
+                for (theme, label) in [
+                    (ThemePreference::System, "Follow System"),
+                    (ThemePreference::Dark, "Dark"),
+                    (ThemePreference::Light, "Light"),
+                ] {
+                    if ui.radio_value(&mut app.theme, theme, label).clicked() {
+                        app.apply_theme();
+                        ui.close_menu();
+                    }
+                }
+            });
+            if ui
+                .checkbox(&mut app.high_contrast, "High Contrast")
+                .changed()
+            {
+                app.apply_theme();
+            }
+            ui.checkbox(&mut app.show_summary, "Save Summary")
+                .on_hover_text("Show how the save appears on the console below the file tree");
+            ui.checkbox(&mut app.watch_files, "Reload External Changes")
+                .on_hover_text("Refresh the file tree and open tabs when files change on disk");
+            ui.checkbox(&mut app.strict_display, "Strict Title Validation")
+                .on_hover_text("Warn about title characters the PS2 browser can't display");
+            ui.checkbox(&mut app.normalize_line_endings, "Normalize Line Endings")
+                .on_hover_text("Save text files with LF line endings, even ones loaded with CRLF");
+            ui.checkbox(&mut app.guide_missing_files, "Guide Missing Files")
+                .on_hover_text("Offer to create icon.sys, title.cfg and psu.toml if missing");
+            ui.menu_button("Language", |ui| {
+                for language in Language::ALL {
+                    if ui
+                        .radio(Language::current() == language, language.name())
+                        .clicked()
+                    {
+                        language.set_current();
+                        ui.close_menu();
+                    }
+                }
+            });
+        });
         ui.menu_button("Export", |ui| {
             ui.add_enabled_ui(is_folder_open, |ui| {
                 if ui
@@ -94,6 +171,15 @@ pub fn menu_bar(ui: &mut Ui, app: &mut AppState) {
                     ui.close_menu();
                 }
             });
+            if ui.button("Build Memory Card…").clicked() {
+                app.build_memory_card();
+                ui.close_menu();
+            }
+            ui.separator();
+            ui.checkbox(&mut app.write_config, "Write psu.toml Alongside")
+                .on_hover_text("Save the config next to the PSU to repack it later");
+            ui.checkbox(&mut app.compress_export, "Compress with Gzip")
+                .on_hover_text("Export a .psu.gz, it needs decompressing before use on a PS2");
         });
         ui.menu_button("Help", |ui| {
             ui.menu_item_link("GitHub", "https://github.com/techwritescode/ps2-rust")
@@ -113,5 +199,7 @@ pub fn handle_accelerators(ctx: &Context, app: &mut AppState) {
         app.add_files();
     } else if ctx.input_mut(|i| i.consume_shortcut(&OPEN_SETTINGS_KEYBOARD_SHORTCUT)) {
         app.open_settings();
+    } else if ctx.input_mut(|i| i.consume_shortcut(&COMMAND_PALETTE_KEYBOARD_SHORTCUT)) {
+        app.open_command_palette();
     }
 }
@@ -0,0 +1,45 @@
+use crate::data::state::AppState;
+use crate::io::new_file::NewFile;
+use eframe::egui::{Button, Context, TopBottomPanel};
+
+/// Files a project needs before it packs cleanly, in the order they're suggested
+const REQUIRED_FILES: [NewFile; 3] = [NewFile::IconSys, NewFile::TitleCfg, NewFile::PsuToml];
+
+pub fn missing_project_files(app: &AppState) -> Vec<NewFile> {
+    REQUIRED_FILES
+        .into_iter()
+        .filter(|kind| !app.files.iter().any(|file| file.name == kind.file_name()))
+        .collect()
+}
+
+/// Points new users at the first file the opened project is missing, with a button to
+/// create it from its template. Turned off with `AppState::guide_missing_files`.
+pub fn missing_files_bar(ctx: &Context, app: &mut AppState) {
+    if !app.guide_missing_files || app.opened_folder.is_none() || app.read_only {
+        return;
+    }
+    let missing = missing_project_files(app);
+    let Some(&first) = missing.first() else {
+        return;
+    };
+
+    TopBottomPanel::top("missing_files").show(ctx, |ui| {
+        ui.horizontal(|ui| {
+            let names = missing
+                .iter()
+                .map(|kind| kind.file_name())
+                .collect::<Vec<_>>();
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!("This project is missing {}", names.join(", ")),
+            );
+            if ui
+                .add(Button::new(format!("Create {}", first.file_name())))
+                .highlight()
+                .clicked()
+            {
+                app.new_file(first);
+            }
+        });
+    });
+}
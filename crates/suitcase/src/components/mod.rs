@@ -1,10 +1,17 @@
 pub mod bottom_bar;
 pub mod buttons;
+pub mod command_palette;
 pub mod dialogs;
 pub mod file_tree;
+pub mod job_window;
+pub mod icon_thumbnail;
 pub mod menu_bar;
 pub mod menu_item;
+pub mod missing_files;
+pub mod summary_card;
 pub mod tab_viewer;
 pub mod toolbar;
 pub mod greeting;
 pub(crate) mod file_picker;
+pub mod validation_window;
+pub mod gradient;
@@ -0,0 +1,75 @@
+use crate::components::icon_thumbnail::{cached, thumbnail, Cached};
+use crate::data::state::AppState;
+use eframe::egui::{Frame, Grid, Ui};
+use ps2_filetypes::sjis::decode_text;
+use ps2_filetypes::{IconSys, TitleCfg};
+use std::collections::HashMap;
+use std::path::PathBuf;
+
+/// The title and version OPL lists the game under
+#[derive(Clone)]
+struct OplTitle {
+    title: Option<String>,
+    version: Option<String>,
+}
+
+/// How the save presents on the console at a glance, the browser's icon and two title lines
+/// alongside what OPL shows. Follows the files on disk, so edits show up once they're saved.
+#[derive(Default)]
+pub struct SummaryCard {
+    title_lines: HashMap<PathBuf, Cached<Option<(String, String)>>>,
+    opl_titles: HashMap<PathBuf, Cached<Option<OplTitle>>>,
+}
+
+impl SummaryCard {
+    pub fn show(&mut self, ui: &mut Ui, app: &mut AppState) {
+        let Some(folder) = app.opened_folder.clone() else {
+            return;
+        };
+
+        let title_lines = cached(&mut self.title_lines, &folder.join("icon.sys"), |path| {
+            let icon_sys = IconSys::try_new(std::fs::read(path).ok()?).ok()?;
+            Some(icon_sys.title_lines())
+        });
+        let opl_title = cached(&mut self.opl_titles, &folder.join("title.cfg"), |path| {
+            let (contents, _) = decode_text(&std::fs::read(path).ok()?)?;
+            let title_cfg = TitleCfg::new(contents);
+            Some(OplTitle {
+                title: title_cfg.index_map.get("Title").cloned(),
+                version: title_cfg.index_map.get("Version").cloned(),
+            })
+        });
+        let texture = app.thumbnails.for_folder(ui.ctx(), &folder);
+
+        ui.add_space(4.0);
+        Frame::group(ui.style()).show(ui, |ui| {
+            ui.set_width(ui.available_width());
+            ui.vertical_centered(|ui| {
+                thumbnail(ui, texture.as_ref());
+                match title_lines {
+                    Some((first, second)) => {
+                        ui.strong(first);
+                        ui.strong(second);
+                    }
+                    None => {
+                        ui.weak("No icon.sys, the browser won't list this save");
+                    }
+                }
+            });
+
+            let Some(opl_title) = opl_title else {
+                return;
+            };
+            ui.separator();
+            Grid::new("summary_card_opl").num_columns(2).show(ui, |ui| {
+                ui.weak("OPL Title");
+                ui.label(opl_title.title.as_deref().unwrap_or("-"));
+                ui.end_row();
+                ui.weak("Version");
+                ui.label(opl_title.version.as_deref().unwrap_or("-"));
+                ui.end_row();
+            });
+        });
+        ui.add_space(4.0);
+    }
+}
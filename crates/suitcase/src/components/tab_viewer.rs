@@ -1,7 +1,11 @@
+use crate::components::dialogs::{confirm_unsaved, UnsavedChoice};
+use crate::messages::{tr_args, Message};
 use crate::tabs::Tab;
 use eframe::egui::{Id, Ui, WidgetText};
 use crate::AppState;
-use crate::tabs::{IconSysViewer, TitleCfgViewer, ICNViewer};
+use crate::tabs::registry::Viewer;
+use crate::tabs::{IconSysViewer, MCDViewer, TitleCfgViewer, ICNViewer};
+use std::path::Path;
 
 pub struct TabViewer<'a> {
     pub(crate) app: &'a mut AppState,
@@ -11,6 +15,11 @@ pub enum TabType {
     IconSysViewer(IconSysViewer),
     TitleCfgViewer(TitleCfgViewer),
     ICNViewer(ICNViewer),
+    MCDViewer(MCDViewer),
+    /// Viewers registered through `ViewerRegistry` from outside the built-in set. Only tests
+    /// construct one, the shipped viewers are all built in.
+    #[cfg_attr(not(test), expect(dead_code))]
+    Custom(Box<dyn Viewer>),
 }
 
 impl TabType {
@@ -19,6 +28,8 @@ impl TabType {
             TabType::IconSysViewer(tab) => tab.get_id(),
             TabType::TitleCfgViewer(tab) => tab.get_id(),
             TabType::ICNViewer(tab) => tab.get_id(),
+            TabType::MCDViewer(tab) => tab.get_id(),
+            TabType::Custom(tab) => tab.get_id(),
         }
     }
 
@@ -27,6 +38,18 @@ impl TabType {
             TabType::IconSysViewer(tab) => tab.get_title(),
             TabType::TitleCfgViewer(tab) => tab.get_title(),
             TabType::ICNViewer(tab) => tab.get_title(),
+            TabType::MCDViewer(tab) => tab.get_title(),
+            TabType::Custom(tab) => tab.get_title(),
+        }
+    }
+
+    pub fn get_path(&self) -> &Path {
+        match self {
+            TabType::IconSysViewer(tab) => tab.get_path(),
+            TabType::TitleCfgViewer(tab) => tab.get_path(),
+            TabType::ICNViewer(tab) => tab.get_path(),
+            TabType::MCDViewer(tab) => tab.get_path(),
+            TabType::Custom(tab) => tab.get_path(),
         }
     }
 
@@ -35,6 +58,8 @@ impl TabType {
             TabType::IconSysViewer(tab) => tab.get_modified(),
             TabType::TitleCfgViewer(tab) => tab.get_modified(),
             TabType::ICNViewer(tab) => tab.get_modified(),
+            TabType::MCDViewer(tab) => tab.get_modified(),
+            TabType::Custom(tab) => tab.get_modified(),
         }
     }
 
@@ -43,6 +68,18 @@ impl TabType {
             TabType::IconSysViewer(tab) => tab.save(),
             TabType::TitleCfgViewer(tab) => tab.save(),
             TabType::ICNViewer(tab) => tab.save(),
+            TabType::MCDViewer(tab) => tab.save(),
+            TabType::Custom(tab) => tab.save(),
+        }
+    }
+
+    pub fn reload(&mut self) {
+        match self {
+            TabType::IconSysViewer(tab) => tab.reload(),
+            TabType::TitleCfgViewer(tab) => tab.reload(),
+            TabType::ICNViewer(tab) => tab.reload(),
+            TabType::MCDViewer(tab) => tab.reload(),
+            TabType::Custom(tab) => tab.reload(),
         }
     }
 }
@@ -67,8 +104,14 @@ impl<'a> egui_dock::TabViewer for TabViewer<'a> {
                 tab.show(ui);
             }
             TabType::TitleCfgViewer(tab) => {
+                tab.show(ui, self.app);
+            }
+            TabType::MCDViewer(tab) => {
                 tab.show(ui);
             }
+            TabType::Custom(tab) => {
+                tab.show(ui, self.app);
+            }
         }
     }
 
@@ -77,6 +120,14 @@ impl<'a> egui_dock::TabViewer for TabViewer<'a> {
     }
 
     fn on_close(&mut self, tab: &mut Self::Tab) -> bool {
+        if tab.get_modified() {
+            let description = tr_args(Message::UnsavedTab, &[("title", &tab.get_title())]);
+            match confirm_unsaved(description) {
+                UnsavedChoice::Save => tab.save(),
+                UnsavedChoice::Discard => {}
+                UnsavedChoice::Cancel => return false,
+            }
+        }
         if let TabType::ICNViewer(tab) = &mut **tab {
             tab.closing = true;
         }
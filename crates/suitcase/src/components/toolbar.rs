@@ -1,5 +1,7 @@
 use crate::components::buttons::CustomButtons;
 use crate::data::state::AppState;
+use crate::io::new_file::NewFile;
+use crate::io::pcsx2_memcards::{find_memcards, memcard_dir};
 use eframe::egui::{include_image, menu, ImageSource, Response, Ui};
 
 fn toolbar_item(ui: &mut Ui, source: ImageSource, tooltip: impl Into<String>) -> Response {
@@ -42,7 +44,7 @@ pub fn toolbar(ui: &mut Ui, app: &mut AppState) -> Response {
             "Make title configuration",
         )
         .clicked()
-        .then(|| app.create_title_cfg());
+        .then(|| app.new_file(NewFile::TitleCfg));
         if toolbar_item(
             ui,
             include_image!("../../assets/hidpi/main_mk_iconsys.png"),
@@ -92,7 +94,56 @@ pub fn toolbar(ui: &mut Ui, app: &mut AppState) -> Response {
             {
                 app.start_pcsx2();
             }
+            memcard_picker(ui, app);
         }
     })
     .response
 }
+
+/// Lists the cards in PCSX2's memcards folder, it's only read while the menu is open
+fn memcard_picker(ui: &mut Ui, app: &mut AppState) {
+    let selected = app
+        .pcsx2_memcard
+        .as_ref()
+        .and_then(|card| card.file_name())
+        .map_or("No memory card".to_string(), |name| {
+            name.to_string_lossy().to_string()
+        });
+
+    ui.menu_button(selected, |ui| {
+        let Some(dir) = memcard_dir(&app.pcsx2_path) else {
+            ui.label("PCSX2's memcards folder wasn't found");
+            return;
+        };
+        let cards = match find_memcards(&dir) {
+            Ok(cards) => cards,
+            Err(err) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Failed to read {}: {err}", dir.display()),
+                );
+                return;
+            }
+        };
+
+        if ui.radio(app.pcsx2_memcard.is_none(), "None").clicked() {
+            app.pcsx2_memcard = None;
+            ui.close_menu();
+        }
+        if cards.is_empty() {
+            ui.label(format!("No memory cards in {}", dir.display()));
+        }
+        for card in cards {
+            let name = card.file_name().unwrap_or_default().to_string_lossy();
+            if ui
+                .radio(app.pcsx2_memcard.as_ref() == Some(&card), name.as_ref())
+                .clicked()
+            {
+                app.pcsx2_memcard = Some(card.clone());
+                ui.close_menu();
+            }
+        }
+    })
+    .response
+    .on_hover_text("Memory card to use with PCSX2");
+}
@@ -0,0 +1,69 @@
+use crate::data::state::AppState;
+use crate::io::job::Job;
+use crate::io::validate::{fix_label, Severity, ValidationIssue};
+use crate::messages::{tr, Message};
+use eframe::egui::{Button, Context, ScrollArea, Spinner, Window};
+
+/// Shows the running validation while `job` is set, then the issues it found
+pub fn validation_window(
+    ctx: &Context,
+    issues: &mut Option<Vec<ValidationIssue>>,
+    job: Option<&Job<Vec<ValidationIssue>>>,
+    state: &mut AppState,
+) {
+    if issues.is_none() && job.is_none() {
+        return;
+    }
+
+    let mut open = true;
+    Window::new(tr(Message::ValidationTitle))
+        .open(&mut open)
+        .collapsible(false)
+        .default_width(400.0)
+        .show(ctx, |ui| {
+            if let Some(job) = job {
+                ui.horizontal(|ui| {
+                    ui.add(Spinner::new());
+                    ui.label(job.title());
+                    let cancel = Button::new(tr(Message::Cancel));
+                    if ui.add_enabled(!job.is_cancelled(), cancel).clicked() {
+                        job.cancel();
+                    }
+                });
+                return;
+            }
+            let Some(found) = issues.as_ref() else {
+                return;
+            };
+
+            if found.is_empty() {
+                ui.label(tr(Message::ValidationPassed));
+                return;
+            }
+
+            ScrollArea::vertical().show(ui, |ui| {
+                for issue in found.iter() {
+                    let (color, label) = match issue.severity {
+                        Severity::Error => (ui.visuals().error_fg_color, tr(Message::Error)),
+                        Severity::Warning => (ui.visuals().warn_fg_color, tr(Message::Warning)),
+                    };
+                    ui.horizontal_wrapped(|ui| {
+                        ui.colored_label(color, label);
+                        ui.label(&issue.message);
+                        if let Some(fix) = &issue.fix {
+                            if ui.small_button(fix_label(fix)).clicked() {
+                                state.apply_fix(fix.clone());
+                            }
+                        }
+                    });
+                }
+            });
+        });
+
+    if !open {
+        if let Some(job) = job {
+            job.cancel();
+        }
+        *issues = None;
+    }
+}
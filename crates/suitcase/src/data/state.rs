@@ -1,22 +1,33 @@
+use crate::components::icon_thumbnail::IconThumbnails;
 use crate::data::files::Files;
 use crate::data::virtual_file::VirtualFile;
+use crate::io::new_file::NewFile;
+use crate::io::validate::Fix;
+use eframe::egui::ThemePreference;
 use std::path::PathBuf;
 
 #[derive(Clone)]
 pub enum AppEvent {
     OpenFolder,
+    CloseFolder,
     OpenFile(VirtualFile),
     SetTitle(String),
     AddFiles,
     ExportPSU,
+    BuildMemoryCard,
     SaveFile,
     OpenSave,
     CreateICN,
-    CreateTitleCfg,
+    NewFile(NewFile),
     OpenSettings,
     StartPCSX2,
     StartPCSX2Elf(PathBuf),
     Validate,
+    ApplyTheme,
+    InitializeProject,
+    GenerateIcon,
+    ApplyFix(Fix),
+    OpenCommandPalette,
 }
 
 pub struct AppState {
@@ -24,6 +35,21 @@ pub struct AppState {
     pub files: Files,
     pub events: Vec<AppEvent>,
     pub pcsx2_path: String,
+    /// Memory card image picked from PCSX2's memcards folder
+    pub pcsx2_memcard: Option<PathBuf>,
+    pub theme: ThemePreference,
+    pub high_contrast: bool,
+    pub read_only: bool,
+    pub watch_files: bool,
+    pub show_summary: bool,
+    pub strict_display: bool,
+    /// Saves text files with LF endings even if they were loaded with CRLF
+    pub normalize_line_endings: bool,
+    /// Suggests creating icon.sys, title.cfg and psu.toml when the project lacks them
+    pub guide_missing_files: bool,
+    pub write_config: bool,
+    pub compress_export: bool,
+    pub thumbnails: IconThumbnails,
 }
 
 impl AppState {}
@@ -41,20 +67,26 @@ impl AppState {
     pub fn open_folder(&mut self) {
         self.events.push(AppEvent::OpenFolder);
     }
+    pub fn close_folder(&mut self) {
+        self.events.push(AppEvent::CloseFolder);
+    }
     pub fn open_save(&mut self) {
         self.events.push(AppEvent::OpenSave);
     }
     pub fn export_psu(&mut self) {
         self.events.push(AppEvent::ExportPSU);
     }
+    pub fn build_memory_card(&mut self) {
+        self.events.push(AppEvent::BuildMemoryCard);
+    }
     pub fn save_file(&mut self) {
         self.events.push(AppEvent::SaveFile);
     }
     pub fn create_icn(&mut self) {
         self.events.push(AppEvent::CreateICN);
     }
-    pub fn create_title_cfg(&mut self) {
-        self.events.push(AppEvent::CreateTitleCfg);
+    pub fn new_file(&mut self, kind: NewFile) {
+        self.events.push(AppEvent::NewFile(kind));
     }
     pub fn open_settings(&mut self) {
         self.events.push(AppEvent::OpenSettings);
@@ -68,6 +100,25 @@ impl AppState {
     pub fn validate(&mut self) {
         self.events.push(AppEvent::Validate);
     }
+    pub fn apply_theme(&mut self) {
+        self.events.push(AppEvent::ApplyTheme);
+    }
+    pub fn set_theme(&mut self, theme: ThemePreference) {
+        self.theme = theme;
+        self.apply_theme();
+    }
+    pub fn initialize_project(&mut self) {
+        self.events.push(AppEvent::InitializeProject);
+    }
+    pub fn generate_icon(&mut self) {
+        self.events.push(AppEvent::GenerateIcon);
+    }
+    pub fn apply_fix(&mut self, fix: Fix) {
+        self.events.push(AppEvent::ApplyFix(fix));
+    }
+    pub fn open_command_palette(&mut self) {
+        self.events.push(AppEvent::OpenCommandPalette);
+    }
 }
 
 impl Default for AppState {
@@ -83,6 +134,18 @@ impl AppState {
             files: Files::default(),
             events: vec![],
             pcsx2_path: String::new(),
+            pcsx2_memcard: None,
+            theme: ThemePreference::System,
+            high_contrast: false,
+            read_only: false,
+            watch_files: true,
+            show_summary: true,
+            strict_display: false,
+            normalize_line_endings: false,
+            guide_missing_files: true,
+            write_config: false,
+            compress_export: false,
+            thumbnails: IconThumbnails::default(),
         }
     }
 }
@@ -0,0 +1,55 @@
+use crate::io::job::Job;
+use eframe::egui::Context;
+use psu_packer::logger::{Logger, Verbosity};
+use psu_packer::{build_card, open_card};
+use std::path::Path;
+
+/// Asks for the saves and the card image to write them to, then builds the card in the
+/// background. An existing card keeps its saves, a new one is formatted as a blank 8MB card.
+/// Finishes with a line per save saying whether it was written.
+pub fn build_memcard(ctx: &Context) -> Option<Job<Vec<String>>> {
+    let sources = rfd::FileDialog::new()
+        .set_title("Saves to put on the memory card")
+        .add_filter("PS2 Save Files", &["psu", "gz"])
+        .pick_files()?;
+    let card_path = rfd::FileDialog::new()
+        .set_title("Memory card to write the saves to")
+        .add_filter("PS2 Memory Card", &["ps2"])
+        .set_file_name("Mcd001.ps2")
+        .save_file()?;
+
+    let title = format!("Writing {} saves to the memory card", sources.len());
+    Some(Job::spawn(ctx, title, move |progress| {
+        progress.set_total(sources.len() as u64);
+        let mut card =
+            open_card(&card_path).map_err(|err| std::io::Error::other(err.to_string()))?;
+        let log = Logger::new(Verbosity::Quiet);
+
+        let mut report = vec![];
+        for source in &sources {
+            progress.check_cancelled()?;
+            for save in build_card(&mut card, std::slice::from_ref(source), &log) {
+                let name = save.name.clone().unwrap_or_else(|| source_name(source));
+                let size = save.size.div_ceil(1024);
+                report.push(match &save.result {
+                    Ok(()) => format!("{name} ({size} KB) written"),
+                    Err(_) if save.did_not_fit() => format!("{name} ({size} KB) doesn't fit"),
+                    Err(err) => format!("{name} failed: {err}"),
+                });
+            }
+            progress.advance(1);
+        }
+        report.push(format!("{} KB free", card.free_bytes() / 1024));
+        std::fs::write(&card_path, card.into_bytes())?;
+
+        Ok(report)
+    }))
+}
+
+fn source_name(source: &Path) -> String {
+    source
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
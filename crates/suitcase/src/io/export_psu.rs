@@ -1,10 +1,19 @@
+use crate::io::job::{Job, Progress};
+use crate::AppState;
+use eframe::egui::Context;
+use flate2::write::GzEncoder;
+use flate2::Compression;
 use ps2_filetypes::chrono::{DateTime, Utc};
-use ps2_filetypes::{PSUEntry, PSUEntryKind, PSUWriter, DIR_ID, FILE_ID, PSU};
+use ps2_filetypes::{
+    BinReader, PSUEntry, PSUEntryKind, PSUParser, PSUWriter, DIR_ID, FILE_ID, PSU,
+};
+use psu_packer::{written_config_path, Config};
 use std::fs::File;
-use std::io::Write;
-use crate::AppState;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
 
-pub fn export_psu(state: &mut AppState) -> std::io::Result<()> {
+/// Asks where to export the opened folder and starts writing the PSU in the background
+pub fn export_psu(state: &mut AppState, ctx: &Context) -> std::io::Result<Option<Job<PathBuf>>> {
     let folder_name = state
         .opened_folder
         .clone()
@@ -15,71 +24,182 @@ pub fn export_psu(state: &mut AppState) -> std::io::Result<()> {
         .unwrap()
         .to_owned();
 
-    let target_filename = folder_name.to_owned() + ".psu";
+    let target_filename = if state.compress_export {
+        folder_name.to_owned() + ".psu.gz"
+    } else {
+        folder_name.to_owned() + ".psu"
+    };
 
-    if let Some(filename) = rfd::FileDialog::new()
+    let Some(filename) = rfd::FileDialog::new()
         .set_file_name(target_filename)
         .save_file()
-    {
-        let mut psu = PSU::default();
+    else {
+        return Ok(None);
+    };
+    if filename.exists() && !confirm_overwrite(&filename, state)? {
+        return Ok(None);
+    }
 
-        let root = PSUEntry {
-            id: DIR_ID,
-            size: state.files.len() as u32 + 2,
-            created: Utc::now().naive_utc(),
-            sector: 0,
-            modified: Utc::now().naive_utc(),
-            name: folder_name,
-            kind: PSUEntryKind::Directory,
-            contents: None,
-        };
-        let cur = PSUEntry {
-            id: FILE_ID,
-            size: 0,
-            created: Utc::now().naive_utc(),
-            sector: 0,
-            modified: Utc::now().naive_utc(),
-            name: ".".to_string(),
-            kind: PSUEntryKind::Directory,
-            contents: None,
-        };
-        let parent = PSUEntry {
+    let title = format!("Exporting {} files", state.files.len());
+    let files = state
+        .files
+        .iter()
+        .map(|file| (file.name.clone(), file.file_path.clone()))
+        .collect::<Vec<_>>();
+    let write_config = state.write_config;
+
+    Ok(Some(Job::spawn(ctx, title, move |progress| {
+        write_export(&filename, folder_name, &files, write_config, progress)?;
+        Ok(filename)
+    })))
+}
+
+/// Writes to a temporary file first, so a failed or cancelled export never leaves a
+/// half-written PSU behind or clobbers the one it was replacing.
+/// A filename ending in `.gz` is gzip-compressed, whether or not the option picked it.
+fn write_export(
+    filename: &Path,
+    folder_name: String,
+    files: &[(String, PathBuf)],
+    write_config: bool,
+    progress: &Progress,
+) -> std::io::Result<()> {
+    let now = Utc::now().naive_utc();
+    let directory = |name: &str, size: u32| PSUEntry {
+        id: DIR_ID,
+        size,
+        created: now,
+        sector: 0,
+        modified: now,
+        name: name.to_string(),
+        kind: PSUEntryKind::Directory,
+        contents: None,
+    };
+
+    let mut psu = PSU {
+        entries: vec![
+            directory(&folder_name, files.len() as u32 + 2),
+            directory(".", 0),
+            directory("..", 0),
+        ],
+    };
+
+    let mut total = 0;
+    for (name, path) in files {
+        let metadata = path.metadata()?;
+        total += metadata.len();
+        let created_at: DateTime<Utc> = metadata.modified()?.into();
+        let modified_at: DateTime<Utc> = metadata.modified()?.into();
+
+        psu.entries.push(PSUEntry {
             id: FILE_ID,
-            size: 0,
-            created: Utc::now().naive_utc(),
+            size: metadata.len() as u32,
             sector: 0,
-            modified: Utc::now().naive_utc(),
-            name: "..".to_string(),
-            kind: PSUEntryKind::Directory,
             contents: None,
-        };
+            name: name.clone(),
+            created: created_at.naive_local(),
+            modified: modified_at.naive_local(),
+            kind: PSUEntryKind::File,
+        });
+    }
+    progress.set_total(total);
 
-        psu.entries.push(root);
-        psu.entries.push(cur);
-        psu.entries.push(parent);
-
-        for file in state.files.iter() {
-            let metadata = file.file_path.metadata()?;
-            let contents = std::fs::read(&file.file_path)?;
-            let size = contents.len() as u32;
-
-            let created_at: DateTime<Utc> = metadata.modified()?.into();
-            let modified_at: DateTime<Utc> = metadata.modified()?.into();
-
-            psu.entries.push(PSUEntry {
-                id: FILE_ID,
-                size,
-                sector: 0,
-                contents: Some(contents),
-                name: file.name.clone(),
-                created: created_at.naive_local(),
-                modified: modified_at.naive_local(),
-                kind: PSUEntryKind::File,
-            });
+    let mut partial = filename.as_os_str().to_owned();
+    partial.push(".part");
+    let partial = PathBuf::from(partial);
+
+    let writer = PSUWriter::new(psu);
+    let write_entries = |out: &mut dyn Write| {
+        writer.write_with(out, |entry| {
+            progress.check_cancelled()?;
+            let (_, path) = files
+                .iter()
+                .find(|(name, _)| *name == entry.name)
+                .ok_or(std::io::ErrorKind::NotFound)?;
+            Ok(progress.reader(File::open(path)?))
+        })
+    };
+    let compress = filename
+        .extension()
+        .is_some_and(|extension| extension == "gz");
+    let result = File::create(&partial).and_then(|file| {
+        let mut file = BufWriter::new(file);
+        if compress {
+            let mut encoder = GzEncoder::new(&mut file, Compression::default());
+            write_entries(&mut encoder)?;
+            encoder.finish()?;
+        } else {
+            write_entries(&mut file)?;
         }
-        let data = PSUWriter::new(psu).to_bytes()?;
-        File::create(&filename)?.write_all(&data)?;
+        file.flush()
+    });
+    if let Err(err) = result.and_then(|()| std::fs::rename(&partial, filename)) {
+        let _ = std::fs::remove_file(&partial);
+        return Err(err);
+    }
+
+    if write_config {
+        let config = Config {
+            name: folder_name,
+            timestamp: Some(now),
+            include: Some(files.iter().map(|(name, _)| name.clone()).collect()),
+            ..Default::default()
+        };
+        std::fs::write(written_config_path(filename), config.to_toml_string())?;
     }
 
     Ok(())
 }
+
+/// Lists what the export would add, remove or change compared to the PSU being replaced
+fn diff_existing(existing: &PSU, state: &AppState) -> std::io::Result<Vec<String>> {
+    let mut changes = Vec::new();
+    let existing_files = existing
+        .entries
+        .iter()
+        .filter(|entry| matches!(entry.kind, PSUEntryKind::File))
+        .collect::<Vec<_>>();
+
+    for file in state.files.iter() {
+        match existing_files.iter().find(|entry| entry.name == file.name) {
+            None => changes.push(format!("+ {}", file.name)),
+            Some(entry) => {
+                if entry.contents.as_deref() != Some(&std::fs::read(&file.file_path)?) {
+                    changes.push(format!("~ {}", file.name));
+                }
+            }
+        }
+    }
+    for entry in existing_files {
+        if !state.files.iter().any(|file| file.name == entry.name) {
+            changes.push(format!("- {}", entry.name));
+        }
+    }
+
+    Ok(changes)
+}
+
+fn confirm_overwrite(filename: &Path, state: &AppState) -> std::io::Result<bool> {
+    // If the old file isn't a readable PSU there's nothing meaningful to compare against
+    let Ok(existing) = PSUParser::read(&std::fs::read(filename)?) else {
+        return Ok(true);
+    };
+
+    let changes = diff_existing(&existing, state)?;
+    if changes.is_empty() {
+        return Ok(true);
+    }
+
+    let result = rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Warning)
+        .set_title("Overwrite PSU")
+        .set_description(format!(
+            "Overwriting {} will make these changes:\n\n{}",
+            filename.display(),
+            changes.join("\n")
+        ))
+        .set_buttons(rfd::MessageButtons::OkCancel)
+        .show();
+
+    Ok(result == rfd::MessageDialogResult::Ok)
+}
@@ -0,0 +1,52 @@
+use image::RgbaImage;
+use ps2_filetypes::color::Color;
+use ps2_filetypes::{BinWriter, ICNWriter, IconSys, ICN};
+use std::path::Path;
+
+pub const GENERATED_FILES: [&str; 2] = ["icon.icn", "icon.sys"];
+
+/// Turns an image into a flat `icon.icn` and an `icon.sys` whose background is sampled from it.
+/// Both files are overwritten, callers are expected to confirm first.
+pub fn generate_icon(folder: &Path, image: &Path) -> std::io::Result<()> {
+    let image = image::open(image)
+        .map_err(std::io::Error::other)?
+        .to_rgba8();
+
+    let icn = ICN::flat_plane(&image);
+    std::fs::write(folder.join("icon.icn"), ICNWriter::new(icn).write()?)?;
+
+    let icon_sys = IconSys {
+        title: folder
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        background_colors: sample_background(&image),
+        ..Default::default()
+    };
+    std::fs::write(folder.join("icon.sys"), icon_sys.to_bytes()?)?;
+
+    Ok(())
+}
+
+/// Averages each quadrant of the image, ordered top-left, top-right, bottom-left, bottom-right
+fn sample_background(image: &RgbaImage) -> [Color; 4] {
+    let (width, height) = image.dimensions();
+    let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+
+    [(0, 0), (1, 0), (0, 1), (1, 1)].map(|(column, row)| {
+        let mut sum = [0u64; 3];
+        let mut count = 0u64;
+        for y in (row * half_height..height).take(half_height as usize) {
+            for x in (column * half_width..width).take(half_width as usize) {
+                let pixel = image.get_pixel(x, y);
+                sum.iter_mut()
+                    .zip(pixel.0)
+                    .for_each(|(sum, channel)| *sum += channel as u64);
+                count += 1;
+            }
+        }
+        let [r, g, b] = sum.map(|channel| (channel / count.max(1)) as u8);
+        Color::new(r, g, b, 128)
+    })
+}
@@ -0,0 +1,77 @@
+use std::path::{Path, PathBuf};
+
+/// Copies `files` into the project `folder`. Folders, files already in the project and names
+/// the project already has are refused rather than overwritten. Returns the paths that were
+/// added and a line for every file that wasn't.
+pub fn import_files(folder: &Path, files: Vec<PathBuf>) -> (Vec<PathBuf>, Vec<String>) {
+    let mut added = Vec::new();
+    let mut rejected = Vec::new();
+
+    for file in files {
+        let Some(name) = file.file_name() else {
+            continue;
+        };
+        let display_name = name.to_string_lossy();
+        let new_path = folder.join(name);
+
+        if file.is_dir() {
+            rejected.push(format!(
+                "{display_name} is a folder, saves can't contain subfolders"
+            ));
+        } else if file.parent() == Some(folder) {
+            rejected.push(format!("{display_name} is already in the project"));
+        } else if new_path.exists() {
+            rejected.push(format!(
+                "The project already has a file named {display_name}"
+            ));
+        } else {
+            match std::fs::copy(&file, &new_path) {
+                Ok(_) => added.push(new_path),
+                Err(err) => rejected.push(format!("{display_name} couldn't be copied: {err}")),
+            }
+        }
+    }
+
+    (added, rejected)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn existing_names_and_folders_are_refused() {
+        let project = tempfile::tempdir().unwrap();
+        let outside = tempfile::tempdir().unwrap();
+        std::fs::write(project.path().join("icon.sys"), b"project").unwrap();
+        let files = ["icon.sys", "BOOT.ELF"].map(|name| {
+            let path = outside.path().join(name);
+            std::fs::write(&path, b"outside").unwrap();
+            path
+        });
+        let subfolder = outside.path().join("DATA");
+        std::fs::create_dir(&subfolder).unwrap();
+
+        let (added, rejected) = import_files(
+            project.path(),
+            vec![
+                files[0].clone(),
+                files[1].clone(),
+                subfolder,
+                project.path().join("icon.sys"),
+                outside.path().join("MISSING.BIN"),
+            ],
+        );
+
+        assert_eq!(added, [project.path().join("BOOT.ELF")]);
+        assert_eq!(rejected.len(), 4);
+        assert_eq!(
+            std::fs::read(project.path().join("icon.sys")).unwrap(),
+            b"project"
+        );
+        assert_eq!(
+            std::fs::read(project.path().join("BOOT.ELF")).unwrap(),
+            b"outside"
+        );
+    }
+}
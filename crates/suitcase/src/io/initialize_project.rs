@@ -0,0 +1,19 @@
+use crate::io::new_file::{create_new_file, NewFile};
+use std::io::ErrorKind;
+use std::path::Path;
+
+/// Scaffolds the files a save needs, skipping any that already exist.
+/// Returns the names of the files that were created.
+pub fn initialize_project(folder: &Path) -> std::io::Result<Vec<&'static str>> {
+    let mut created = Vec::new();
+
+    for kind in [NewFile::PsuToml, NewFile::IconSys, NewFile::TitleCfg] {
+        match create_new_file(folder, kind) {
+            Ok(_) => created.push(kind.file_name()),
+            Err(err) if err.kind() == ErrorKind::AlreadyExists => {}
+            Err(err) => return Err(err),
+        }
+    }
+
+    Ok(created)
+}
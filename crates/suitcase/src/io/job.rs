@@ -0,0 +1,123 @@
+use eframe::egui::Context;
+use std::io::{ErrorKind, Read};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread::JoinHandle;
+
+/// Shared between a job and the UI, the worker reports into it and polls for cancellation
+pub struct Progress {
+    ctx: Context,
+    done: AtomicU64,
+    total: AtomicU64,
+    cancelled: AtomicBool,
+}
+
+impl Progress {
+    pub fn set_total(&self, total: u64) {
+        self.total.store(total, Ordering::Relaxed);
+    }
+
+    pub fn advance(&self, amount: u64) {
+        self.done.fetch_add(amount, Ordering::Relaxed);
+        self.ctx.request_repaint();
+    }
+
+    /// Errors with `ErrorKind::Interrupted` once the job has been cancelled
+    pub fn check_cancelled(&self) -> std::io::Result<()> {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return Err(ErrorKind::Interrupted.into());
+        }
+
+        Ok(())
+    }
+
+    /// Wraps a reader so everything read through it counts as progress
+    pub fn reader<R: Read>(&self, inner: R) -> ProgressReader<'_, R> {
+        ProgressReader {
+            inner,
+            progress: self,
+        }
+    }
+}
+
+pub struct ProgressReader<'a, R> {
+    inner: R,
+    progress: &'a Progress,
+}
+
+impl<R: Read> Read for ProgressReader<'_, R> {
+    fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+        self.progress.check_cancelled()?;
+        let read = self.inner.read(buf)?;
+        self.progress.advance(read as u64);
+
+        Ok(read)
+    }
+}
+
+/// Long running work on a background thread, so the UI stays responsive and can cancel it.
+/// Cancelled jobs finish with `ErrorKind::Interrupted` and are expected to clean up after
+/// themselves.
+pub struct Job<T> {
+    title: String,
+    progress: Arc<Progress>,
+    handle: Option<JoinHandle<std::io::Result<T>>>,
+}
+
+impl<T: Send + 'static> Job<T> {
+    pub fn spawn(
+        ctx: &Context,
+        title: impl Into<String>,
+        work: impl FnOnce(&Progress) -> std::io::Result<T> + Send + 'static,
+    ) -> Self {
+        let progress = Arc::new(Progress {
+            ctx: ctx.clone(),
+            done: AtomicU64::new(0),
+            total: AtomicU64::new(0),
+            cancelled: AtomicBool::new(false),
+        });
+        let handle = std::thread::spawn({
+            let progress = progress.clone();
+            move || {
+                let result = work(&progress);
+                progress.ctx.request_repaint();
+                result
+            }
+        });
+
+        Self {
+            title: title.into(),
+            progress,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
+    /// Fraction done, `None` until the job knows how much there is to do
+    pub fn fraction(&self) -> Option<f32> {
+        let total = self.progress.total.load(Ordering::Relaxed);
+        let done = self.progress.done.load(Ordering::Relaxed);
+        (total > 0).then(|| (done as f64 / total as f64).min(1.0) as f32)
+    }
+
+    pub fn cancel(&self) {
+        self.progress.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.progress.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// The job's result once the worker has finished, `None` while it's still running
+    pub fn try_finish(&mut self) -> Option<std::io::Result<T>> {
+        if !self.handle.as_ref()?.is_finished() {
+            return None;
+        }
+
+        let result = self.handle.take()?.join();
+        Some(result.unwrap_or_else(|_| Err(std::io::Error::other("Background job panicked"))))
+    }
+}
@@ -1,6 +1,13 @@
+pub mod build_memcard;
 pub mod export_psu;
 pub mod read_folder;
 pub mod calculate_size;
 pub mod reveal_file_in_explorer;
 pub mod file_watcher;
-pub mod validate;
\ No newline at end of file
+pub mod validate;pub mod open_terminal;
+pub mod initialize_project;
+pub mod job;
+pub mod generate_icon;
+pub mod pcsx2_memcards;
+pub mod new_file;
+pub mod import_files;
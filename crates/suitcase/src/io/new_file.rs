@@ -0,0 +1,142 @@
+use ps2_filetypes::{IconSys, TitleCfg};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// The kinds of file the File > New menu can create from a template
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NewFile {
+    TitleCfg,
+    PsuToml,
+    IconSys,
+    Text,
+}
+
+impl NewFile {
+    pub const ALL: [NewFile; 4] = [
+        NewFile::TitleCfg,
+        NewFile::IconSys,
+        NewFile::PsuToml,
+        NewFile::Text,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            NewFile::TitleCfg => "Title Configuration",
+            NewFile::PsuToml => "Packer Config (psu.toml)",
+            NewFile::IconSys => "icon.sys",
+            NewFile::Text => "Empty Text File",
+        }
+    }
+
+    /// The name the file is created with, text files get a number added when it's taken
+    pub fn file_name(self) -> &'static str {
+        match self {
+            NewFile::TitleCfg => "title.cfg",
+            NewFile::PsuToml => "psu.toml",
+            NewFile::IconSys => "icon.sys",
+            NewFile::Text => "untitled.txt",
+        }
+    }
+
+    /// Templates that name the save use the folder's name
+    fn contents(self, folder: &Path) -> std::io::Result<Vec<u8>> {
+        let name = folder
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        Ok(match self {
+            NewFile::TitleCfg => TitleCfg::new(String::new())
+                .add_missing_fields()
+                .to_string()
+                .into_bytes(),
+            NewFile::PsuToml => format!(
+                "[config]\nname = {}\nexclude = [\"psu.toml\"]\n",
+                toml::Value::String(name)
+            )
+            .into_bytes(),
+            NewFile::IconSys => IconSys {
+                title: name,
+                ..Default::default()
+            }
+            .to_bytes()?,
+            NewFile::Text => Vec::new(),
+        })
+    }
+
+    fn path_in(self, folder: &Path) -> PathBuf {
+        let path = folder.join(self.file_name());
+        if self != NewFile::Text || !path.exists() {
+            return path;
+        }
+
+        (2..)
+            .map(|n| folder.join(format!("untitled {n}.txt")))
+            .find(|candidate| !candidate.exists())
+            .unwrap_or(path)
+    }
+}
+
+/// Writes a new file of `kind` into `folder` and returns its path.
+/// Fails with `AlreadyExists` rather than overwriting a file that's there.
+pub fn create_new_file(folder: &Path, kind: NewFile) -> std::io::Result<PathBuf> {
+    let path = kind.path_in(folder);
+    let contents = kind.contents(folder)?;
+
+    let mut file = std::fs::File::create_new(&path)?;
+    file.write_all(&contents)?;
+
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::ErrorKind;
+
+    fn save_folder() -> (tempfile::TempDir, PathBuf) {
+        let parent = tempfile::tempdir().unwrap();
+        let folder = parent.path().join("MY SAVE");
+        std::fs::create_dir(&folder).unwrap();
+        (parent, folder)
+    }
+
+    #[test]
+    fn each_template_is_created_and_named_after_the_folder() {
+        let (_parent, folder) = save_folder();
+        let read = |kind| std::fs::read(create_new_file(&folder, kind).unwrap()).unwrap();
+
+        let title_cfg = TitleCfg::new(String::from_utf8(read(NewFile::TitleCfg)).unwrap());
+        assert!(title_cfg.has_mandatory_fields());
+
+        let psu_toml: toml::Table = std::str::from_utf8(&read(NewFile::PsuToml))
+            .unwrap()
+            .parse()
+            .unwrap();
+        assert_eq!(psu_toml["config"]["name"].as_str(), Some("MY SAVE"));
+
+        let icon_sys = IconSys::try_new(read(NewFile::IconSys)).unwrap();
+        assert_eq!(icon_sys.title, "MY SAVE");
+
+        assert!(read(NewFile::Text).is_empty());
+        assert_eq!(
+            create_new_file(&folder, NewFile::Text).unwrap(),
+            folder.join("untitled 2.txt")
+        );
+    }
+
+    #[test]
+    fn existing_file_is_not_overwritten() {
+        let (_parent, folder) = save_folder();
+        std::fs::write(folder.join("title.cfg"), "title=Mine\n").unwrap();
+
+        let err = create_new_file(&folder, NewFile::TitleCfg).unwrap_err();
+
+        assert_eq!(err.kind(), ErrorKind::AlreadyExists);
+        assert_eq!(
+            std::fs::read_to_string(folder.join("title.cfg")).unwrap(),
+            "title=Mine\n"
+        );
+    }
+}
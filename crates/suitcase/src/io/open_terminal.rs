@@ -0,0 +1,32 @@
+use std::path::Path;
+use std::process::Command;
+
+#[cfg(target_os = "windows")]
+pub fn open_terminal(path: &Path) {
+    let _ = Command::new("cmd")
+        .args(["/C", "start", "cmd"])
+        .current_dir(path)
+        .spawn();
+}
+
+#[cfg(target_os = "macos")]
+pub fn open_terminal(path: &Path) {
+    let _ = Command::new("open")
+        .args(["-a", "Terminal"])
+        .arg(path)
+        .spawn();
+}
+
+#[cfg(target_os = "linux")]
+pub fn open_terminal(path: &Path) {
+    // There's no standard terminal on Linux, try the user's choice first, then common ones
+    let terminals = std::env::var("TERMINAL")
+        .into_iter()
+        .chain(["x-terminal-emulator", "gnome-terminal", "konsole", "xterm"].map(String::from));
+
+    for terminal in terminals {
+        if Command::new(terminal).current_dir(path).spawn().is_ok() {
+            break;
+        }
+    }
+}
@@ -0,0 +1,70 @@
+use std::path::{Path, PathBuf};
+
+/// File extensions PCSX2 lists as memory cards, folder memcards aren't supported
+pub const MEMCARD_EXTENSIONS: [&str; 3] = ["ps2", "vmc", "bin"];
+
+/// Finds the folder PCSX2 keeps its memory cards in. A portable install next to
+/// `pcsx2_path` wins over the per-user folder, which is `None` if neither exists.
+pub fn memcard_dir(pcsx2_path: &str) -> Option<PathBuf> {
+    let portable = Path::new(pcsx2_path)
+        .parent()
+        .map(|install| install.join("memcards"));
+
+    portable
+        .into_iter()
+        .chain(default_memcard_dirs())
+        .find(|dir| dir.is_dir())
+}
+
+#[cfg(target_os = "windows")]
+fn default_memcard_dirs() -> Vec<PathBuf> {
+    std::env::var_os("USERPROFILE")
+        .map(|home| PathBuf::from(home).join("Documents/PCSX2/memcards"))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(target_os = "macos")]
+fn default_memcard_dirs() -> Vec<PathBuf> {
+    std::env::var_os("HOME")
+        .map(|home| PathBuf::from(home).join("Library/Application Support/PCSX2/memcards"))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(target_os = "linux")]
+fn default_memcard_dirs() -> Vec<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let config = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home.as_ref().map(|home| home.join(".config")));
+
+    // The Flatpak keeps its config inside the sandbox
+    config
+        .map(|config| config.join("PCSX2/memcards"))
+        .into_iter()
+        .chain(home.map(|home| home.join(".var/app/net.pcsx2.PCSX2/config/PCSX2/memcards")))
+        .collect()
+}
+
+/// Lists the memory card images in `dir`, sorted by name
+pub fn find_memcards(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    let mut cards = std::fs::read_dir(dir)?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.is_file() && is_memcard(path))
+        .collect::<Vec<_>>();
+    cards.sort();
+
+    Ok(cards)
+}
+
+fn is_memcard(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| {
+            MEMCARD_EXTENSIONS
+                .iter()
+                .any(|card_ext| ext.eq_ignore_ascii_case(card_ext))
+        })
+}
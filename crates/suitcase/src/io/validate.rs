@@ -1,3 +1,88 @@
-pub fn validate(folder: &str) {
-    println!("Checking {}.", folder);
-}
\ No newline at end of file
+use crate::io::job::Job;
+use crate::messages::{tr, tr_args, Message};
+use eframe::egui::Context;
+use psu_packer::validate::{validate_cancellable, Issue, Problem, CHECK_COUNT};
+pub use psu_packer::validate::{Fix, Severity};
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+#[derive(Clone)]
+pub struct ValidationIssue {
+    pub severity: Severity,
+    pub message: String,
+    pub fix: Option<Fix>,
+}
+
+/// Label for the button that applies the fix
+pub fn fix_label(fix: &Fix) -> String {
+    match fix {
+        Fix::UseIconForAll(icon) => tr_args(Message::UseIconForAll, &[("icon", icon)]),
+        Fix::CorrectConfigCase => tr(Message::CorrectConfigCase).to_string(),
+    }
+}
+
+/// Runs psu-packer's checks against the folder on a background thread, with the messages in
+/// the UI language. Cancelling takes effect between checks.
+/// `strict_display` also warns about title characters that encode fine but render wrong.
+pub fn validate(ctx: &Context, folder: PathBuf, strict_display: bool) -> Job<Vec<ValidationIssue>> {
+    Job::spawn(ctx, tr(Message::ValidationRunning), move |progress| {
+        progress.set_total(CHECK_COUNT as u64);
+        let issues = validate_cancellable(&folder, strict_display, |done| {
+            if done > 0 {
+                progress.advance(1);
+            }
+            progress.check_cancelled().is_ok()
+        })
+        .ok_or(ErrorKind::Interrupted)?;
+
+        Ok(issues.into_iter().map(localize).collect())
+    })
+}
+
+fn localize(issue: Issue) -> ValidationIssue {
+    ValidationIssue {
+        severity: issue.severity,
+        message: message(&issue.problem),
+        fix: issue.fix,
+    }
+}
+
+fn message(problem: &Problem) -> String {
+    match problem {
+        Problem::NameTooLong { max } => tr_args(Message::NameTooLong, &[("max", max)]),
+        Problem::NameInvalid => tr(Message::NameInvalid).to_string(),
+        Problem::NameSuggestion { name } => tr_args(Message::NameSuggestion, &[("name", name)]),
+        Problem::ConfigParseFailed { error } => {
+            tr_args(Message::ConfigParseFailed, &[("err", error)])
+        }
+        Problem::ConfigInvalid { error } => tr_args(Message::ConfigInvalid, &[("err", error)]),
+        Problem::IncludeMissing { file } => tr_args(Message::IncludeMissing, &[("file", file)]),
+        Problem::SaveTooLarge => tr(Message::SaveTooLarge).to_string(),
+        Problem::IconSysMissing => tr(Message::IconSysMissing).to_string(),
+        Problem::IconSysParseFailed { error } => {
+            tr_args(Message::IconSysParseFailed, &[("err", error)])
+        }
+        Problem::TitleMisDecoded => tr(Message::TitleMisDecoded).to_string(),
+        Problem::TitleNotShiftJis => tr(Message::TitleNotShiftJis).to_string(),
+        Problem::TitleTooLong { length, max } => {
+            tr_args(Message::TitleTooLong, &[("length", length), ("max", max)])
+        }
+        Problem::TitleNotDisplayable { chars } => {
+            tr_args(Message::TitleNotDisplayable, &[("chars", chars)])
+        }
+        Problem::IconMissing { icon } => tr_args(Message::IconMissing, &[("icon", icon)]),
+        Problem::TitleCfgEncoding => tr(Message::TitleCfgEncoding).to_string(),
+        Problem::TitleCfgMissingFields => tr(Message::TitleCfgMissingFields).to_string(),
+        Problem::BootFileMissing { boot } => tr_args(Message::BootFileMissing, &[("boot", boot)]),
+        Problem::NoBootFile => tr(Message::NoBootFile).to_string(),
+        Problem::NestedProject { child } => tr_args(Message::NestedProject, &[("child", child)]),
+        Problem::ConfigCaseMismatch { listed, actual } => tr_args(
+            Message::ConfigCaseMismatch,
+            &[("listed", listed), ("actual", actual)],
+        ),
+        Problem::FileNameNotAscii { file, ascii } => tr_args(
+            Message::FileNameNotAscii,
+            &[("file", file), ("ascii", ascii)],
+        ),
+    }
+}
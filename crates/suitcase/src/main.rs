@@ -3,32 +3,51 @@
 mod components;
 mod data;
 mod io;
+mod messages;
 mod rendering;
 mod tabs;
+mod theme;
 mod wizards;
 
-use crate::io::validate::validate;
+use crate::io::import_files::import_files;
+use crate::io::validate::{validate, Fix, ValidationIssue};
+use crate::messages::{tr, tr_args, Language, Message};
 use crate::{
     components::bottom_bar::bottom_bar,
-    components::dialogs::Dialogs,
+    components::command_palette::CommandPalette,
+    components::dialogs::{confirm_unsaved, Dialogs, UnsavedChoice},
     components::file_tree::FileTree,
     components::greeting::greeting,
+    components::job_window::job_window,
     components::menu_bar::{handle_accelerators, menu_bar},
+    components::missing_files::missing_files_bar,
+    components::summary_card::SummaryCard,
     components::tab_viewer::{TabType, TabViewer},
     components::toolbar::toolbar,
+    components::validation_window::validation_window,
+    data::files::Files,
     data::state::{AppEvent, AppState},
     data::virtual_file::VirtualFile,
+    io::build_memcard::build_memcard,
     io::export_psu::export_psu,
     io::file_watcher::FileWatcher,
+    io::generate_icon::{generate_icon, GENERATED_FILES},
+    io::initialize_project::initialize_project,
+    io::job::Job,
+    io::new_file::{create_new_file, NewFile},
     io::read_folder::read_folder,
-    tabs::{ICNViewer, IconSysViewer, TitleCfgViewer},
+    tabs::registry::ViewerRegistry,
     wizards::create_icn::create_icn_wizard,
 };
-use eframe::egui::{Context, Frame, IconData, Margin, ViewportCommand};
+use eframe::egui::{
+    Align2, Color32, Context, Frame, IconData, LayerId, Margin, Order, TextStyle, ThemePreference,
+    ViewportCommand,
+};
 use eframe::{egui, NativeOptions, Storage};
 use egui_dock::{AllowedSplits, DockArea, DockState, NodeIndex, SurfaceIndex, TabIndex};
-use ps2_filetypes::TitleCfg;
-use std::path::PathBuf;
+use ps2_filetypes::{BinReader, PSUEntryKind, PSUParser};
+use psu_packer::is_safe_name;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 fn main() -> eframe::Result<()> {
@@ -67,14 +86,29 @@ struct PSUBuilderApp {
     tree: DockState<Box<TabType>>,
     state: AppState,
     file_tree: FileTree,
+    /// Custom viewers are registered here at startup, see `ViewerFactory`
+    viewers: ViewerRegistry,
+    summary_card: SummaryCard,
     show_create_icn: bool,
+    command_palette: CommandPalette,
+    export_job: Option<Job<PathBuf>>,
+    /// Finishes with a line per save written to the card
+    memcard_job: Option<Job<Vec<String>>>,
     show_settings: bool,
     file_watcher: FileWatcher,
+    validation_issues: Option<Vec<ValidationIssue>>,
+    /// The folder being validated alongside the job checking it
+    validation_job: Option<(PathBuf, Job<Vec<ValidationIssue>>)>,
+    allow_close: bool,
+    /// Where the opened PSU was extracted, deleted when dropped
+    extracted_psu: Option<tempfile::TempDir>,
 }
 
 #[derive(Default, serde::Deserialize, serde::Serialize)]
 struct WorkspaceSave {
     opened_folder: Option<PathBuf>,
+    #[serde(default)]
+    open_files: Vec<PathBuf>,
 }
 
 impl PSUBuilderApp {
@@ -84,30 +118,114 @@ impl PSUBuilderApp {
             .storage
             .and_then(|s| eframe::get_value::<String>(s, "pcsx2_path"))
             .unwrap_or_default();
+        state.pcsx2_memcard = cc
+            .storage
+            .and_then(|s| eframe::get_value::<Option<PathBuf>>(s, "pcsx2_memcard"))
+            .flatten();
+        state.theme = cc
+            .storage
+            .and_then(|s| eframe::get_value::<ThemePreference>(s, "theme"))
+            .unwrap_or(ThemePreference::System);
+        state.high_contrast = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "high_contrast"))
+            .unwrap_or_default();
+        theme::apply(&cc.egui_ctx, state.theme, state.high_contrast);
+        cc.storage
+            .and_then(|s| eframe::get_value::<Language>(s, "language"))
+            .unwrap_or(Language::English)
+            .set_current();
+        state.watch_files = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "watch_files"))
+            .unwrap_or(true);
+        state.show_summary = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "show_summary"))
+            .unwrap_or(true);
+        state.strict_display = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "strict_display"))
+            .unwrap_or_default();
+        state.normalize_line_endings = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "normalize_line_endings"))
+            .unwrap_or_default();
+        state.guide_missing_files = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "guide_missing_files"))
+            .unwrap_or(true);
+        state.write_config = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "write_config"))
+            .unwrap_or_default();
+        state.compress_export = cc
+            .storage
+            .and_then(|s| eframe::get_value::<bool>(s, "compress_export"))
+            .unwrap_or_default();
+
+        let mut slf = Self::with_state(state);
+        slf.try_open_saved_folder(cc.storage);
+
+        slf
+    }
 
-        let mut slf = Self {
+    fn with_state(state: AppState) -> Self {
+        Self {
             tree: DockState::new(Vec::new()),
             state,
             file_tree: FileTree::new(),
+            viewers: ViewerRegistry::default(),
+            summary_card: SummaryCard::default(),
             show_create_icn: false,
+            command_palette: CommandPalette::default(),
+            export_job: None,
+            memcard_job: None,
             show_settings: false,
             file_watcher: FileWatcher::new(),
-        };
-
-        slf.try_open_saved_folder(cc.storage);
-
-        slf
+            validation_issues: None,
+            validation_job: None,
+            allow_close: false,
+            extracted_psu: None,
+        }
     }
 
     fn try_open_saved_folder(&mut self, storage: Option<&dyn Storage>) -> Option<()> {
         let config = eframe::get_value::<WorkspaceSave>(storage?, eframe::APP_KEY)?;
         let folder = config.opened_folder?;
 
-        if folder.exists() {
-            self.do_open_folder(folder).ok()
-        } else {
-            None
+        if !folder.exists() {
+            return None;
         }
+        self.do_open_folder(folder, false).ok()?;
+
+        let (existing, missing): (Vec<_>, Vec<_>) = config
+            .open_files
+            .into_iter()
+            .partition(|path| path.is_file());
+        for file_path in existing {
+            self.handle_open(VirtualFile {
+                name: file_path.file_name()?.to_string_lossy().to_string(),
+                file_path,
+                size: 0,
+            });
+        }
+        if !missing.is_empty() {
+            let missing = missing
+                .iter()
+                .map(|path| path.display().to_string())
+                .collect::<Vec<_>>();
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Some files couldn't be reopened")
+                .set_description(format!(
+                    "These files no longer exist:\n{}",
+                    missing.join("\n")
+                ))
+                .show();
+        }
+
+        Some(())
     }
 
     fn handle_events(&mut self, ctx: &Context) {
@@ -122,28 +240,49 @@ impl PSUBuilderApp {
                     ctx.send_viewport_cmd(ViewportCommand::Title(title));
                 }
                 AppEvent::AddFiles => {
-                    self.add_files(ctx).expect("Failed to add files");
+                    self.add_files(ctx);
                 }
                 AppEvent::OpenFolder => {
                     self.open_folder();
                 }
+                AppEvent::CloseFolder => {
+                    self.close_folder();
+                }
                 AppEvent::ExportPSU => {
-                    export_psu(&mut self.state).expect("Failed to export PSU");
+                    if self.export_job.is_none() {
+                        match export_psu(&mut self.state, ctx) {
+                            Ok(job) => self.export_job = job,
+                            Err(err) => export_failed(err),
+                        }
+                    }
+                }
+                AppEvent::BuildMemoryCard => {
+                    if self.export_job.is_none() && self.memcard_job.is_none() {
+                        self.memcard_job = build_memcard(ctx);
+                    }
                 }
                 AppEvent::SaveFile => {
                     self.save_file();
                 }
                 AppEvent::OpenSave => {
-                    if let Some(_) = rfd::FileDialog::new()
-                        .add_filter("PS2 Save Files", &["psu"])
+                    if let Some(file) = rfd::FileDialog::new()
+                        .add_filter("PS2 Save Files", &["psu", "gz"])
                         .pick_file()
-                    {}
+                    {
+                        if let Err(err) = self.open_psu(&file) {
+                            rfd::MessageDialog::new()
+                                .set_level(rfd::MessageLevel::Error)
+                                .set_title("Failed to open save")
+                                .set_description(err.to_string())
+                                .show();
+                        }
+                    }
                 }
                 AppEvent::CreateICN => {
                     self.show_create_icn = true;
                 }
-                AppEvent::CreateTitleCfg => {
-                    self.create_title_cfg();
+                AppEvent::NewFile(kind) => {
+                    self.new_file(kind);
                 }
                 AppEvent::OpenSettings => {
                     self.show_settings = true;
@@ -174,22 +313,67 @@ impl PSUBuilderApp {
                         .expect("Failed to start PCSX2 with ELF");
                 }
                 AppEvent::Validate => {
-                    validate(
-                        self.state
-                            .opened_folder
-                            .clone()
-                            .expect("No opened folder")
-                            .to_str()
-                            .unwrap(),
-                    );
+                    self.start_validation(ctx);
+                }
+                AppEvent::ApplyTheme => {
+                    theme::apply(ctx, self.state.theme, self.state.high_contrast);
+                }
+                AppEvent::InitializeProject => {
+                    self.initialize_project();
+                }
+                AppEvent::GenerateIcon => {
+                    self.show_create_icn = false;
+                    self.generate_icon();
+                }
+                AppEvent::ApplyFix(fix) => {
+                    self.apply_fix(ctx, fix);
+                }
+                AppEvent::OpenCommandPalette => {
+                    self.command_palette.open();
                 }
             }
         }
     }
 
     fn handle_fs_events(&mut self) {
-        while let Ok(_event) = self.file_watcher.event_rx.try_recv() {
-            self.state.files = read_folder(self.state.opened_folder.clone().unwrap()).unwrap();
+        let mut changed = Vec::new();
+        while let Ok(event) = self.file_watcher.event_rx.try_recv() {
+            changed.extend(event.paths);
+        }
+
+        if changed.is_empty() || !self.state.watch_files {
+            return;
+        }
+        let Some(folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+
+        self.state.files = match read_folder(folder.clone()) {
+            Ok(files) => files,
+            // Deleted or renamed from outside, there's nothing left to show
+            Err(err) => {
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("The project folder is gone")
+                    .set_description(format!(
+                        "{} can't be read anymore and was closed: {err}",
+                        folder.display()
+                    ))
+                    .show();
+                self.discard_folder();
+                return;
+            }
+        };
+        self.file_tree.index_folder(&folder);
+
+        for (_, tab) in self.tree.iter_all_tabs_mut() {
+            if !changed.iter().any(|path| path == tab.get_path()) {
+                continue;
+            }
+            if tab.get_modified() && !confirm_reload(&tab.get_title()) {
+                continue;
+            }
+            tab.reload();
         }
     }
 
@@ -208,72 +392,182 @@ impl PSUBuilderApp {
     }
 
     fn tab_for_file(&mut self, file: VirtualFile) {
-        let name: PathBuf = file.name.clone().into();
-        if let Some(extension) = name.extension() {
-            let editor: Option<TabType> = match extension.to_ascii_lowercase().to_str().unwrap() {
-                "icn" | "ico" => Some(TabType::ICNViewer(ICNViewer::new(&file, &self.state))),
-                "sys" => Some(TabType::IconSysViewer(IconSysViewer::new(
-                    &file,
-                    &self.state,
-                ))),
-                "cfg" | "cnf" | "dat" | "txt" => Some(TabType::TitleCfgViewer(
-                    TitleCfgViewer::new(&file, &self.state),
-                )),
-                _ => None,
-            };
-
-            if let Some(editor) = editor {
-                self.tree.push_to_focused_leaf(Box::new(editor));
-                if let Some(position) = self.tree.focused_leaf() {
-                    self.tree.set_focused_node_and_surface(position);
-                } else {
-                    self.tree
-                        .set_focused_node_and_surface((SurfaceIndex::main(), NodeIndex::root()));
-                }
+        if let Some(editor) = self.viewers.create(&file, &self.state) {
+            self.tree.push_to_focused_leaf(Box::new(editor));
+            if let Some(position) = self.tree.focused_leaf() {
+                self.tree.set_focused_node_and_surface(position);
+            } else {
+                self.tree
+                    .set_focused_node_and_surface((SurfaceIndex::main(), NodeIndex::root()));
             }
         }
     }
 
     fn save_file(&mut self) {
+        if self.state.read_only {
+            return;
+        }
         if let Some((_, tab)) = self.tree.find_active_focused() {
             tab.save();
         }
     }
 
-    fn add_files(&mut self, ctx: &Context) -> std::io::Result<()> {
+    fn add_files(&mut self, ctx: &Context) {
         if let Some(files) = ctx.open_files() {
-            let opened_folder = self
-                .state
-                .opened_folder
-                .clone()
-                .expect("Should only be called after folder is opened");
-
-            for file in files {
-                let name = file.file_name().unwrap();
-                let new_path = opened_folder.join(name);
-                std::fs::copy(&file, &new_path)?;
-
-                self.state.files.add_file(new_path)?;
+            self.import_files(files);
+        }
+    }
+
+    /// Copies files into the opened folder, reporting any that can't be added
+    fn import_files(&mut self, files: Vec<PathBuf>) {
+        let Some(opened_folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+        if self.state.read_only {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Files weren't added")
+                .set_description("The opened save is read-only")
+                .show();
+            return;
+        }
+
+        let (added, mut rejected) = import_files(&opened_folder, files);
+        for path in added {
+            if let Err(err) = self.state.files.add_file(&path) {
+                rejected.push(format!("{}: {err}", path.display()));
             }
         }
-        Ok(())
+
+        if !rejected.is_empty() {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Some files weren't added")
+                .set_description(rejected.join("\n"))
+                .show();
+        }
+    }
+
+    fn handle_dropped_files(&mut self, ctx: &Context) {
+        if self.state.opened_folder.is_none() {
+            return;
+        }
+
+        let (hovering, dropped) = ctx.input(|i| {
+            (
+                !i.raw.hovered_files.is_empty(),
+                i.raw
+                    .dropped_files
+                    .iter()
+                    .filter_map(|file| file.path.clone())
+                    .collect::<Vec<_>>(),
+            )
+        });
+
+        if hovering && !self.state.read_only {
+            let painter = ctx.layer_painter(LayerId::new(
+                Order::Foreground,
+                egui::Id::new("file_drop_overlay"),
+            ));
+            let screen_rect = ctx.screen_rect();
+            painter.rect_filled(screen_rect, 0.0, Color32::from_black_alpha(160));
+            painter.text(
+                screen_rect.center(),
+                Align2::CENTER_CENTER,
+                "Drop files to add them to the project",
+                TextStyle::Heading.resolve(&ctx.style()),
+                Color32::WHITE,
+            );
+        }
+
+        if !dropped.is_empty() {
+            self.import_files(dropped);
+        }
     }
 
     fn open_folder(&mut self) {
         if let Some(folder) = rfd::FileDialog::new().pick_folder() {
-            self.do_open_folder(folder).expect("Failed to open folder");
+            self.do_open_folder(folder, false)
+                .expect("Failed to open folder");
         }
     }
 
-    fn do_open_folder(&mut self, folder: PathBuf) -> std::io::Result<()> {
+    /// Extracts the PSU into a temporary folder and opens it read-only, exporting repacks it
+    fn open_psu(&mut self, file: &Path) -> std::io::Result<()> {
+        let psu = PSUParser::read(&std::fs::read(file)?)?;
+        let root = psu
+            .entries
+            .iter()
+            .find(|entry| matches!(entry.kind, PSUEntryKind::Directory))
+            .ok_or(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                "PSU has no root directory",
+            ))?;
+
+        if !is_safe_name(&root.name) {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::InvalidData,
+                format!("PSU root directory has an unsafe name: {}", root.name),
+            ));
+        }
+
+        // A fresh directory every time, the previous one is removed when it's replaced
+        let extracted = tempfile::Builder::new().prefix("ps2suitcase").tempdir()?;
+        let folder = extracted.path().join(&root.name);
+        std::fs::create_dir(&folder)?;
+
+        for entry in &psu.entries {
+            let Some(contents) = &entry.contents else {
+                continue;
+            };
+            if !is_safe_name(&entry.name) {
+                continue;
+            }
+            std::fs::write(folder.join(&entry.name), contents)?;
+        }
+
+        self.do_open_folder(folder, true)?;
+        self.extracted_psu = Some(extracted);
+
+        Ok(())
+    }
+
+    fn close_folder(&mut self) {
+        if !self.resolve_unsaved_tabs() {
+            return;
+        }
+        self.discard_folder();
+    }
+
+    /// Closes the opened folder and its tabs without asking about unsaved changes
+    fn discard_folder(&mut self) {
+        self.tree = DockState::new(Vec::new());
+        self.file_tree = FileTree::new();
+        self.validation_issues = None;
+        self.cancel_validation();
+        self.state.opened_folder = None;
+        self.extracted_psu = None;
+        self.state.files = Files::default();
+        self.state.read_only = false;
+        self.state.set_title("PS2Suitcase".to_string());
+    }
+
+    fn do_open_folder(&mut self, folder: PathBuf, read_only: bool) -> std::io::Result<()> {
+        let name = folder
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+
+        self.cancel_validation();
+        self.extracted_psu = None;
         self.state.opened_folder = Some(folder.clone());
-        self.state.set_title(
-            folder
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy()
-                .to_string(),
-        );
+        self.state.read_only = read_only;
+        self.state.set_title(if read_only {
+            tr_args(Message::ReadOnlyTitle, &[("name", &name)])
+        } else {
+            name
+        });
         self.file_watcher.change_path(&folder);
         self.file_tree.index_folder(&folder);
         self.state.files = read_folder(folder)?;
@@ -281,31 +575,273 @@ impl PSUBuilderApp {
         Ok(())
     }
 
-    fn create_title_cfg(&mut self) {
-        if let Some(filepath) = rfd::FileDialog::new()
-            .set_title("Select a folder to create title.cfg in")
-            .set_file_name("title.cfg")
-            .add_filter("title.cfg", &["cfg"])
-            .set_directory(self.state.opened_folder.clone().unwrap())
-            .save_file()
+    fn initialize_project(&mut self) {
+        let Some(folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+
+        let description = match initialize_project(&folder) {
+            Ok(created) if created.is_empty() => "All project files already exist.".to_string(),
+            Ok(created) => format!("Created {}.", created.join(", ")),
+            Err(err) => format!("Failed to initialize project: {err}"),
+        };
+
+        self.state.files = read_folder(folder.clone()).unwrap_or_default();
+        self.file_tree.index_folder(&folder);
+
+        rfd::MessageDialog::new()
+            .set_title("Initialize Project")
+            .set_description(description)
+            .show();
+    }
+
+    fn poll_export(&mut self, ctx: &Context) {
+        let Some(job) = &mut self.export_job else {
+            return;
+        };
+        job_window(ctx, job);
+
+        let Some(result) = job.try_finish() else {
+            return;
+        };
+        self.export_job = None;
+        match result {
+            Err(err) if err.kind() != std::io::ErrorKind::Interrupted => export_failed(err),
+            _ => {}
+        }
+    }
+
+    fn poll_memcard(&mut self, ctx: &Context) {
+        let Some(job) = &mut self.memcard_job else {
+            return;
+        };
+        job_window(ctx, job);
+
+        let Some(result) = job.try_finish() else {
+            return;
+        };
+        self.memcard_job = None;
+        match result {
+            Ok(report) => {
+                rfd::MessageDialog::new()
+                    .set_title("Build Memory Card")
+                    .set_description(report.join("\n"))
+                    .show();
+            }
+            Err(err) if err.kind() != std::io::ErrorKind::Interrupted => {
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Failed to build memory card")
+                    .set_description(err.to_string())
+                    .show();
+            }
+            _ => {}
+        }
+    }
+
+    fn apply_fix(&mut self, ctx: &Context, fix: Fix) {
+        let Some(folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+
+        if let Err(err) = fix.apply(&folder) {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Failed to apply fix")
+                .set_description(err.to_string())
+                .show();
+        }
+        self.start_validation(ctx);
+    }
+
+    /// Replaces any validation that's still running with a fresh one
+    fn start_validation(&mut self, ctx: &Context) {
+        let Some(folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+
+        self.cancel_validation();
+        let job = validate(ctx, folder.clone(), self.state.strict_display);
+        self.validation_job = Some((folder, job));
+    }
+
+    fn cancel_validation(&mut self) {
+        if let Some((_, job)) = self.validation_job.take() {
+            job.cancel();
+        }
+    }
+
+    fn poll_validation(&mut self) {
+        let Some((folder, job)) = &mut self.validation_job else {
+            return;
+        };
+        let Some(result) = job.try_finish() else {
+            return;
+        };
+
+        // Results for a folder that has since been closed or swapped out don't apply anymore
+        let current = self.state.opened_folder.as_ref() == Some(folder);
+        self.validation_job = None;
+        match result {
+            Ok(issues) if current => self.validation_issues = Some(issues),
+            Err(err) if err.kind() != std::io::ErrorKind::Interrupted => {
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("Failed to validate")
+                    .set_description(err.to_string())
+                    .show();
+            }
+            _ => {}
+        }
+    }
+
+    fn generate_icon(&mut self) {
+        let Some(folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+        let Some(image) = rfd::FileDialog::new()
+            .set_title("Select an image to generate the icon from")
+            .add_filter("Image", &["png", "jpg", "jpeg", "bmp"])
+            .pick_file()
+        else {
+            return;
+        };
+
+        let existing = GENERATED_FILES
+            .into_iter()
+            .filter(|name| folder.join(name).exists())
+            .collect::<Vec<_>>();
+        if !existing.is_empty()
+            && rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Warning)
+                .set_title("Generate Icon")
+                .set_description(format!("Overwrite {}?", existing.join(" and ")))
+                .set_buttons(rfd::MessageButtons::YesNo)
+                .show()
+                != rfd::MessageDialogResult::Yes
         {
-            std::fs::write(
-                filepath.clone(),
-                TitleCfg::new("".to_string())
-                    .add_missing_fields()
-                    .to_string()
-                    .into_bytes(),
-            )
-            .expect("Failed to title.cfg");
-            self.handle_open(VirtualFile {
-                name: filepath.file_name().unwrap().to_str().unwrap().to_string(),
-                size: 0,
-                file_path: filepath,
-            });
-            self.file_tree
-                .index_folder(&self.state.opened_folder.clone().unwrap());
+            return;
+        }
+
+        if let Err(err) = generate_icon(&folder, &image) {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("Generate Icon")
+                .set_description(format!("Failed to generate icon: {err}"))
+                .show();
+        }
+
+        self.state.files = read_folder(folder.clone()).unwrap_or_default();
+        self.file_tree.index_folder(&folder);
+    }
+
+    fn new_file(&mut self, kind: NewFile) {
+        let Some(folder) = self.state.opened_folder.clone() else {
+            return;
+        };
+        if self.state.read_only {
+            return;
+        }
+
+        let path = match create_new_file(&folder, kind) {
+            Ok(path) => path,
+            Err(err) => {
+                let description = if err.kind() == std::io::ErrorKind::AlreadyExists {
+                    format!("{} already exists in this folder.", kind.file_name())
+                } else {
+                    format!("Failed to create {}: {err}", kind.file_name())
+                };
+                rfd::MessageDialog::new()
+                    .set_level(rfd::MessageLevel::Error)
+                    .set_title("New File")
+                    .set_description(description)
+                    .show();
+                return;
+            }
+        };
+
+        self.state.files = read_folder(folder.clone()).unwrap_or_default();
+        self.file_tree.index_folder(&folder);
+        self.handle_open(VirtualFile {
+            name: path.file_name().unwrap().to_str().unwrap().to_string(),
+            size: std::fs::metadata(&path).map_or(0, |metadata| metadata.len()),
+            file_path: path,
+        });
+    }
+}
+
+impl PSUBuilderApp {
+    /// Holds the window open while any tab has unsaved changes until the user decides what to do
+    fn handle_close_request(&mut self, ctx: &Context) {
+        if self.allow_close || !ctx.input(|i| i.viewport().close_requested()) {
+            return;
         }
+        if !self.tree.iter_all_tabs().any(|(_, tab)| tab.get_modified()) {
+            return;
+        }
+
+        ctx.send_viewport_cmd(ViewportCommand::CancelClose);
+        if self.resolve_unsaved_tabs() {
+            self.allow_close = true;
+            ctx.send_viewport_cmd(ViewportCommand::Close);
+        }
+    }
+
+    /// Asks what to do with modified tabs, returns false if the user cancelled
+    fn resolve_unsaved_tabs(&mut self) -> bool {
+        let tabs = self.tree.iter_all_tabs_mut().map(|(_, tab)| &mut **tab);
+        resolve_unsaved(tabs, |modified| {
+            confirm_unsaved(tr_args(
+                Message::UnsavedFiles,
+                &[("files", &modified.join("\n"))],
+            ))
+        })
+    }
+}
+
+/// Asks `choose` what to do with the titles of the modified tabs and saves them if it says
+/// so. Nothing is asked when no tab is modified. Returns false if `choose` cancelled.
+fn resolve_unsaved<'a>(
+    tabs: impl Iterator<Item = &'a mut TabType>,
+    choose: impl FnOnce(&[String]) -> UnsavedChoice,
+) -> bool {
+    let modified = tabs.filter(|tab| tab.get_modified()).collect::<Vec<_>>();
+    if modified.is_empty() {
+        return true;
     }
+
+    let titles = modified.iter().map(|tab| tab.get_title()).collect::<Vec<_>>();
+    match choose(&titles) {
+        UnsavedChoice::Save => {
+            for tab in modified {
+                tab.save();
+            }
+            true
+        }
+        UnsavedChoice::Discard => true,
+        UnsavedChoice::Cancel => false,
+    }
+}
+
+fn confirm_reload(title: &str) -> bool {
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Warning)
+        .set_title(tr(Message::FileChangedTitle))
+        .set_description(tr_args(
+            Message::FileChangedDescription,
+            &[("title", &title)],
+        ))
+        .set_buttons(rfd::MessageButtons::YesNo)
+        .show()
+        == rfd::MessageDialogResult::Yes
+}
+
+fn export_failed(err: std::io::Error) {
+    rfd::MessageDialog::new()
+        .set_level(rfd::MessageLevel::Error)
+        .set_title("Failed to export PSU")
+        .set_description(err.to_string())
+        .show();
 }
 
 impl eframe::App for PSUBuilderApp {
@@ -322,9 +858,16 @@ impl eframe::App for PSUBuilderApp {
             bottom_bar(ui, &mut self.state);
         });
 
+        missing_files_bar(ctx, &mut self.state);
+
         if self.state.opened_folder.is_some() {
             egui::SidePanel::left("side_panel").show(ctx, |ui| {
                 ui.set_min_width(200.0);
+                if self.state.show_summary {
+                    egui::TopBottomPanel::bottom("summary_card")
+                        .frame(Frame::NONE)
+                        .show_inside(ui, |ui| self.summary_card.show(ui, &mut self.state));
+                }
                 self.file_tree.show(ui, &mut self.state);
             });
         }
@@ -392,8 +935,20 @@ impl eframe::App for PSUBuilderApp {
         // }
 
         handle_accelerators(ctx, &mut self.state);
+        self.handle_dropped_files(ctx);
+        self.handle_close_request(ctx);
 
-        create_icn_wizard(ctx, &mut self.show_create_icn);
+        create_icn_wizard(ctx, &mut self.show_create_icn, &mut self.state);
+        validation_window(
+            ctx,
+            &mut self.validation_issues,
+            self.validation_job.as_ref().map(|(_, job)| job),
+            &mut self.state,
+        );
+        self.command_palette.show(ctx, &mut self.state);
+        self.poll_export(ctx);
+        self.poll_memcard(ctx);
+        self.poll_validation();
         self.handle_events(ctx);
         self.handle_fs_events();
     }
@@ -403,11 +958,142 @@ impl eframe::App for PSUBuilderApp {
             storage,
             eframe::APP_KEY,
             &WorkspaceSave {
-                opened_folder: self.state.opened_folder.clone(),
+                // Extracted PSUs live in a temporary folder, don't reopen them next time
+                opened_folder: self
+                    .state
+                    .opened_folder
+                    .clone()
+                    .filter(|_| !self.state.read_only),
+                open_files: self
+                    .tree
+                    .iter_all_tabs()
+                    .filter(|_| !self.state.read_only)
+                    .map(|(_, tab)| tab.get_path().to_path_buf())
+                    .collect(),
             },
         );
         eframe::set_value(storage, "pcsx2_path", &self.state.pcsx2_path);
+        eframe::set_value(storage, "pcsx2_memcard", &self.state.pcsx2_memcard);
+        eframe::set_value(storage, "theme", &self.state.theme);
+        eframe::set_value(storage, "high_contrast", &self.state.high_contrast);
+        eframe::set_value(storage, "watch_files", &self.state.watch_files);
+        eframe::set_value(storage, "show_summary", &self.state.show_summary);
+        eframe::set_value(storage, "strict_display", &self.state.strict_display);
+        eframe::set_value(
+            storage,
+            "normalize_line_endings",
+            &self.state.normalize_line_endings,
+        );
+        eframe::set_value(
+            storage,
+            "guide_missing_files",
+            &self.state.guide_missing_files,
+        );
+        eframe::set_value(storage, "write_config", &self.state.write_config);
+        eframe::set_value(storage, "compress_export", &self.state.compress_export);
+        eframe::set_value(storage, "language", &Language::current());
     }
 
     fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {}
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tabs::registry::Viewer;
+    use crate::tabs::Tab;
+    use std::path::Path;
+
+    struct Draft {
+        title: &'static str,
+        modified: bool,
+    }
+
+    impl Tab for Draft {
+        fn get_id(&self) -> &str {
+            self.title
+        }
+        fn get_title(&self) -> String {
+            self.title.to_string()
+        }
+        fn get_path(&self) -> &Path {
+            Path::new(self.title)
+        }
+        fn get_modified(&self) -> bool {
+            self.modified
+        }
+        fn save(&mut self) {
+            self.modified = false;
+        }
+        fn reload(&mut self) {}
+    }
+
+    impl Viewer for Draft {
+        fn show(&mut self, _ui: &mut egui::Ui, _app: &mut AppState) {}
+    }
+
+    fn tabs() -> Vec<TabType> {
+        [("title.cfg", true), ("icon.sys", false), ("psu.toml", true)]
+            .map(|(title, modified)| TabType::Custom(Box::new(Draft { title, modified })))
+            .into()
+    }
+
+    fn modified(tabs: &[TabType]) -> Vec<String> {
+        tabs.iter()
+            .filter(|tab| tab.get_modified())
+            .map(|tab| tab.get_title())
+            .collect()
+    }
+
+    #[test]
+    fn only_modified_tabs_are_asked_about_and_saved() {
+        let mut tabs = tabs();
+        let mut asked = vec![];
+
+        let resolved = resolve_unsaved(tabs.iter_mut(), |titles| {
+            asked = titles.to_vec();
+            UnsavedChoice::Save
+        });
+
+        assert!(resolved);
+        assert_eq!(asked, ["title.cfg", "psu.toml"]);
+        assert!(modified(&tabs).is_empty());
+        // Nothing left to ask about
+        assert!(resolve_unsaved(tabs.iter_mut(), |_| unreachable!()));
+    }
+
+    #[test]
+    fn discarding_the_folder_clears_its_state() {
+        let folder = tempfile::tempdir().unwrap();
+        std::fs::write(folder.path().join("icon.sys"), b"icon").unwrap();
+        let extracted = tempfile::tempdir().unwrap();
+        let extracted_path = extracted.path().to_path_buf();
+        let mut app = PSUBuilderApp::with_state(AppState::new());
+        let opened = folder.path().to_path_buf();
+        app.do_open_folder(opened, true).unwrap();
+        app.extracted_psu = Some(extracted);
+        app.validation_issues = Some(vec![]);
+        for tab in tabs() {
+            app.tree.push_to_focused_leaf(Box::new(tab));
+        }
+        assert_eq!(app.state.files.0.len(), 1);
+
+        app.discard_folder();
+
+        assert!(app.state.opened_folder.is_none());
+        assert!(app.state.files.0.is_empty());
+        assert!(!app.state.read_only);
+        assert!(app.validation_issues.is_none());
+        assert_eq!(app.tree.iter_all_tabs().count(), 0);
+        assert!(!extracted_path.exists());
+    }
+
+    #[test]
+    fn discard_and_cancel_leave_the_tabs_modified() {
+        let mut tabs = tabs();
+
+        assert!(resolve_unsaved(tabs.iter_mut(), |_| UnsavedChoice::Discard));
+        assert!(!resolve_unsaved(tabs.iter_mut(), |_| UnsavedChoice::Cancel));
+        assert_eq!(modified(&tabs), ["title.cfg", "psu.toml"]);
+    }
+}
@@ -0,0 +1,188 @@
+use std::fmt::Display;
+use std::sync::atomic::{AtomicU8, Ordering};
+
+static LANGUAGE: AtomicU8 = AtomicU8::new(Language::English as u8);
+
+#[derive(Clone, Copy, PartialEq, Eq, serde::Deserialize, serde::Serialize)]
+#[repr(u8)]
+pub enum Language {
+    English,
+    Spanish,
+}
+
+impl Language {
+    pub const ALL: [Language; 2] = [Language::English, Language::Spanish];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Language::English => "English",
+            Language::Spanish => "Español",
+        }
+    }
+
+    pub fn current() -> Self {
+        match LANGUAGE.load(Ordering::Relaxed) {
+            1 => Language::Spanish,
+            _ => Language::English,
+        }
+    }
+
+    pub fn set_current(self) {
+        LANGUAGE.store(self as u8, Ordering::Relaxed);
+    }
+}
+
+/// User-facing strings, placeholders in braces are filled in by [`tr_args`]
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Message {
+    OpenFolderToStart,
+    NoOpenEditors,
+    ReadOnlySave,
+    ReadOnlyTitle,
+    FileChangedTitle,
+    FileChangedDescription,
+    UnsavedTitle,
+    UnsavedTab,
+    UnsavedFiles,
+    ValidationTitle,
+    ValidationPassed,
+    ValidationRunning,
+    Cancel,
+    Error,
+    Warning,
+    NameTooLong,
+    NameInvalid,
+    NameSuggestion,
+    ConfigParseFailed,
+    ConfigInvalid,
+    IncludeMissing,
+    SaveTooLarge,
+    IconSysMissing,
+    IconSysParseFailed,
+    TitleNotShiftJis,
+    TitleTooLong,
+    TitleNotDisplayable,
+    TitleMisDecoded,
+    IconMissing,
+    TitleCfgEncoding,
+    TitleCfgMissingFields,
+    BootFileMissing,
+    NoBootFile,
+    NestedProject,
+    UseIconForAll,
+    ConfigCaseMismatch,
+    CorrectConfigCase,
+    FileNameNotAscii,
+}
+
+pub fn tr(message: Message) -> &'static str {
+    match Language::current() {
+        Language::English => english(message),
+        Language::Spanish => spanish(message).unwrap_or_else(|| english(message)),
+    }
+}
+
+pub fn tr_args(message: Message, args: &[(&str, &dyn Display)]) -> String {
+    args.iter()
+        .fold(tr(message).to_string(), |text, (key, value)| {
+            text.replace(&format!("{{{key}}}"), &value.to_string())
+        })
+}
+
+fn english(message: Message) -> &'static str {
+    match message {
+        Message::OpenFolderToStart => "Open a folder to get started ({shortcut})",
+        Message::NoOpenEditors => "No open editors",
+        Message::ReadOnlySave => "Saves opened from a PSU are read-only",
+        Message::ReadOnlyTitle => "{name} (read-only)",
+        Message::FileChangedTitle => "File changed on disk",
+        Message::FileChangedDescription => {
+            "{title} was changed outside of PS2Suitcase. Discard your unsaved changes and reload it?"
+        }
+        Message::UnsavedTitle => "Unsaved changes",
+        Message::UnsavedTab => "{title} has unsaved changes. Save them before closing?",
+        Message::UnsavedFiles => {
+            "These files have unsaved changes:\n{files}\n\nSave them before quitting?"
+        }
+        Message::ValidationTitle => "Validation",
+        Message::ValidationPassed => "No issues found, the save is ready to export.",
+        Message::ValidationRunning => "Checking the save…",
+        Message::Cancel => "Cancel",
+        Message::Error => "Error",
+        Message::Warning => "Warning",
+        Message::NameTooLong => "Folder name is longer than {max} characters",
+        Message::NameInvalid => "Folder name must match [a-zA-Z0-9_- ]+",
+        Message::NameSuggestion => "Use \"{name}\" as the folder name to fix it",
+        Message::ConfigParseFailed => "Failed to parse psu.toml: {err}",
+        Message::ConfigInvalid => "psu.toml is invalid: {err}",
+        Message::IncludeMissing => "psu.toml includes {file}, which doesn't exist",
+        Message::SaveTooLarge => "Save is larger than a standard 8MB memory card",
+        Message::IconSysMissing => "icon.sys is missing, the save won't show up in the browser",
+        Message::IconSysParseFailed => "Failed to parse icon.sys: {err}",
+        Message::TitleNotShiftJis => {
+            "icon.sys title contains characters that can't be encoded as Shift-JIS"
+        }
+        Message::TitleTooLong => "icon.sys title is {length} bytes, the limit is {max}",
+        Message::TitleNotDisplayable => {
+            "icon.sys title contains {chars}, which the PS2 browser can't display"
+        }
+        Message::TitleMisDecoded => {
+            "icon.sys title may be mis-decoded, unrecognized characters show up as ? and are saved that way"
+        }
+        Message::IconMissing => "icon.sys references {icon}, which doesn't exist",
+        Message::TitleCfgEncoding => "title.cfg isn't valid UTF-8 or Shift-JIS",
+        Message::TitleCfgMissingFields => "title.cfg is missing mandatory fields",
+        Message::BootFileMissing => "title.cfg boots {boot}, which doesn't exist",
+        Message::NoBootFile => "title.cfg has no boot file",
+        Message::NestedProject => {
+            "{child} has its own psu.toml, did you mean to open that folder instead?"
+        }
+        Message::UseIconForAll => "Use {icon} for all icons",
+        Message::ConfigCaseMismatch => "psu.toml lists {listed}, but the file is named {actual}",
+        Message::CorrectConfigCase => "Correct psu.toml",
+        Message::FileNameNotAscii => "{file} can't be displayed on a PS2, rename it to {ascii}",
+    }
+}
+
+/// Sample catalog, anything missing falls back to English
+fn spanish(message: Message) -> Option<&'static str> {
+    Some(match message {
+        Message::OpenFolderToStart => "Abre una carpeta para empezar ({shortcut})",
+        Message::NoOpenEditors => "No hay editores abiertos",
+        Message::ReadOnlySave => "Las partidas abiertas desde un PSU son de solo lectura",
+        Message::ReadOnlyTitle => "{name} (solo lectura)",
+        Message::FileChangedTitle => "Archivo modificado en el disco",
+        Message::FileChangedDescription => {
+            "{title} se modificó fuera de PS2Suitcase. ¿Descartar los cambios sin guardar y recargarlo?"
+        }
+        Message::UnsavedTitle => "Cambios sin guardar",
+        Message::UnsavedTab => "{title} tiene cambios sin guardar. ¿Guardarlos antes de cerrar?",
+        Message::ValidationTitle => "Validación",
+        Message::ValidationPassed => "No se encontraron problemas, la partida está lista para exportar.",
+        Message::ValidationRunning => "Comprobando la partida…",
+        Message::Cancel => "Cancelar",
+        Message::Error => "Error",
+        Message::Warning => "Aviso",
+        Message::NameSuggestion => "Usa \"{name}\" como nombre de carpeta para corregirlo",
+        Message::ConfigParseFailed => "No se pudo leer psu.toml: {err}",
+        Message::ConfigInvalid => "psu.toml no es válido: {err}",
+        Message::IncludeMissing => "psu.toml incluye {file}, que no existe",
+        Message::IconSysMissing => {
+            "Falta icon.sys, la partida no aparecerá en el navegador"
+        }
+        Message::IconSysParseFailed => "No se pudo leer icon.sys: {err}",
+        Message::TitleTooLong => "El título de icon.sys ocupa {length} bytes, el límite es {max}",
+        Message::TitleNotDisplayable => {
+            "El título de icon.sys contiene {chars}, que el navegador de PS2 no puede mostrar"
+        }
+        Message::IconMissing => "icon.sys hace referencia a {icon}, que no existe",
+        Message::BootFileMissing => "title.cfg arranca {boot}, que no existe",
+        Message::NestedProject => {
+            "{child} tiene su propio psu.toml, ¿querías abrir esa carpeta?"
+        }
+        Message::UseIconForAll => "Usar {icon} para todos los iconos",
+        Message::ConfigCaseMismatch => "psu.toml incluye {listed}, pero el archivo se llama {actual}",
+        Message::CorrectConfigCase => "Corregir psu.toml",
+        _ => return None,
+    })
+}
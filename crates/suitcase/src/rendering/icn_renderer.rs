@@ -1,8 +1,6 @@
 use cgmath::{vec3, Matrix4, Vector3, Vector4};
 use eframe::glow;
-use ps2_filetypes::color::Color;
-use ps2_filetypes::{ColorF, Key, Vector, ICN};
-use crate::rendering::animation::Timeline;
+use ps2_filetypes::{ColorF, Vector, ICN};
 use crate::rendering::buffer::Buffer;
 use crate::rendering::orbit_camera::OrbitCamera;
 use crate::rendering::program::Program;
@@ -16,8 +14,6 @@ pub struct ICNRenderer {
     lines: VertexArray,
     grid: VertexArray,
     lines_shader: Program,
-    shapes: Vec<Vec<f32>>,
-    timelines: Vec<Timeline>,
 }
 
 impl ICNRenderer {
@@ -34,41 +30,9 @@ impl ICNRenderer {
                 include_str!("../shaders/outline.fsh"),
             );
 
-            let pixels: Vec<u8> = icn
-                .texture
-                .pixels
-                .into_iter()
-                .flat_map(|pixel| {
-                    let color: Color = pixel.into();
-                    let bytes: [u8; 4] = color.into();
-                    [bytes[0], bytes[1], bytes[2], 255]
-                })
-                .collect();
-
-            let mut shapes = vec![];
-            let mut timelines = vec![];
+            let pixels = icn.decode_texture().into_raw();
 
-            for (i, frame) in icn.frames.iter().enumerate() {
-                let mut keys = frame.keys.clone();
-                if i == 0 {
-                    keys.insert(0, Key{time: 0.0, value: 1.0});
-                }
-                timelines.push(Timeline::new(keys))
-            }
-
-            for shape in icn.animation_shapes.iter() {
-                let vertices = shape
-                    .iter()
-                    .flat_map(|vertex|
-                        [
-                            vertex.x as f32 / 4096.0,
-                            -vertex.y as f32 / 4096.0,
-                            -vertex.z as f32 / 4096.0,
-                        ]
-                    )
-                    .collect::<Vec<f32>>();
-                shapes.push(vertices);
-            }
+            let vertices = shape_vertices(&icn.interpolated_shape(0.0));
 
             let data = (0..icn.animation_shapes[0].len())
                 .flat_map(|i| {
@@ -93,7 +57,7 @@ impl ICNRenderer {
                 gl,
                 &model_shader,
                 [(
-                    Buffer::new(gl, &shapes[0]),
+                    Buffer::new(gl, &vertices),
                     attributes()
                         .float("position", 3)
                 ), (
@@ -130,60 +94,28 @@ impl ICNRenderer {
             let model_texture = Texture::new(gl, &pixels);
 
             Ok(Self {
-                shapes,
                 model_shader,
                 lines_shader,
                 model,
                 lines,
                 grid,
                 model_texture,
-                timelines,
             })
         }
     }
 
     pub fn replace_texture(&mut self, gl: &glow::Context, icn: &ICN) {
-        let image = icn
-            .texture
-            .pixels
-            .iter()
-            .flat_map(|&color| {
-                let color: Color = color.into();
-                let bytes: [u8; 4] = color.into();
-                bytes
-            })
-            .collect::<Vec<u8>>();
-        self.model_texture.set(gl, &image);
+        self.model_texture.set(gl, &icn.decode_texture().into_raw());
     }
 
-    pub fn paint(&mut self, gl: &glow::Context, aspect_ratio: f32, orbit_camera: OrbitCamera, frame: u32, light_colors: [ColorF; 3], light_positions: [Vector; 3], ambient_color: ColorF) {
+    pub fn paint(&mut self, gl: &glow::Context, aspect_ratio: f32, orbit_camera: OrbitCamera, shape: &[[f32; 3]], light_colors: [ColorF; 3], light_positions: [Vector; 3], ambient_color: ColorF) {
         use glow::HasContext as _;
 
         let projection = cgmath::perspective(cgmath::Deg(45.0), aspect_ratio, 0.1, 100.0);
         let view = orbit_camera.view_matrix();
         let model: Matrix4<f32> = Matrix4::from_translation(vec3(0.0, 0.0, 0.0));
 
-
-        let mut vertices = vec![0f32; self.shapes[0].len()];
-
-        let mut sum = 0.0;
-        let mut weights = vec![];
-
-        for timeline in self.timelines.iter() {
-            let y = timeline.evaluate(frame as f32);
-            sum += y;
-            weights.push(y);
-        }
-
-        if !self.timelines.is_empty() {
-            for (j, shape) in self.shapes.iter().enumerate() {
-                for (i, vertex) in shape.iter().enumerate() {
-                    vertices[i] += *vertex * weights[j]/sum;
-                }
-            }
-            self.model.buffer(0).set(gl, &vertices);
-        }
-
+        self.model.buffer(0).set(gl, &shape_vertices(shape));
 
         unsafe {
             gl.enable(glow::DEPTH_TEST);
@@ -269,6 +201,13 @@ pub fn generate_wireframe_box(size: Vector3<f32>, center: Vector3<f32>) -> Vec<f
         .collect::<Vec<_>>()
 }
 
+fn shape_vertices(shape: &[[f32; 3]]) -> Vec<f32> {
+    shape
+        .iter()
+        .flat_map(|[x, y, z]| [x / 4096.0, -y / 4096.0, -z / 4096.0])
+        .collect()
+}
+
 fn generate_grid_lines(size: i32, step: f32) -> Vec<f32> {
     let mut lines = Vec::new();
     let half = size as f32 * step;
@@ -1,4 +1,3 @@
-pub mod animation;
 pub mod buffer;
 pub mod gl;
 pub mod orbit_camera;
@@ -1,3 +1,4 @@
+use crate::components::gradient::paint_gradient;
 use crate::data::state::AppState;
 use crate::rendering::icn_renderer::ICNRenderer;
 use crate::tabs::PS2RgbaInterface;
@@ -17,13 +18,15 @@ use eframe::{
 };
 use egui_dock::{DockArea, DockState, NodeIndex, SurfaceIndex, TabViewer};
 use image::ImageReader;
-use ps2_filetypes::{color::Color, BinReader, BinWriter, ColorF, ICNWriter, IconSys, Vector, ICN};
+use ps2_filetypes::{
+    color::Color, BinReader, BinWriter, ColorF, ICNParser, ICNWriter, IconSys, Vector, ICN,
+};
 use relative_path::PathExt;
-use std::time::Instant;
+use std::time::{Duration, Instant};
 use std::{
     fs::File,
     io::Write,
-    path::PathBuf,
+    path::{Path, PathBuf},
     sync::{Arc, Mutex},
 };
 
@@ -108,6 +111,7 @@ pub struct ICNViewer {
     icn: ICN,
     dark_mode: bool,
     needs_update: bool,
+    needs_rebuild: bool,
     modified: bool,
     pub closing: bool,
     frame: u32,
@@ -179,6 +183,7 @@ impl ICNViewer {
             icn,
             dark_mode: true,
             needs_update: false,
+            needs_rebuild: false,
             modified: false,
             closing: false,
             frame: 0,
@@ -193,13 +198,11 @@ impl ICNViewer {
     }
 
     fn make_texture(&mut self, ui: &mut Ui) {
-        let mut pixels = vec![];
-        for pixel in self.icn.texture.pixels {
-            let color: Color = pixel.into();
-            pixels.extend_from_slice(&[color.r, color.g, color.b]);
-        }
-
-        let image_data = ImageData::from(ColorImage::from_rgb([128, 128], &pixels));
+        let image = self.icn.decode_texture();
+        let image_data = ImageData::from(ColorImage::from_rgba_unmultiplied(
+            [image.width() as usize, image.height() as usize],
+            image.as_raw(),
+        ));
 
         let id = ui.ctx().tex_manager().write().alloc(
             self.file.clone(),
@@ -235,10 +238,11 @@ impl ICNViewer {
 
         let icn = self.icn.clone();
         let needs_update = self.needs_update;
+        let needs_rebuild = self.needs_rebuild;
         let closing = self.closing;
         let camera = self.camera.clone();
         let aspect_ratio = rect.width() / rect.height();
-        let frame = self.frame;
+        let shape = self.icn.interpolated_shape(self.frame as f32);
         let light_colors = self.light_colors.clone();
         let light_positions = self.light_positions.clone();
         let ambient_color = self.ambient_color.clone();
@@ -247,6 +251,11 @@ impl ICNViewer {
             rect,
             callback: Arc::new(egui_glow::CallbackFn::new(move |_, painter| {
                 let mut renderer = renderer.lock().unwrap();
+                if needs_rebuild {
+                    if let Some(old) = renderer.take() {
+                        old.drop(painter.gl());
+                    }
+                }
                 let renderer = renderer.get_or_insert_with(|| {
                     ICNRenderer::new(painter.gl(), &icn).expect("Failed to create ICNRenderer")
                 });
@@ -257,7 +266,7 @@ impl ICNViewer {
                 if closing {
                     renderer.drop(painter.gl())
                 } else {
-                    renderer.paint(painter.gl(), aspect_ratio, camera, frame, light_colors, light_positions, ambient_color);
+                    renderer.paint(painter.gl(), aspect_ratio, camera, &shape, light_colors, light_positions, ambient_color);
                 }
             })),
         };
@@ -265,6 +274,7 @@ impl ICNViewer {
         if needs_update {
             self.needs_update = false;
         }
+        self.needs_rebuild = false;
 
         ui.painter().add(callback);
     }
@@ -364,24 +374,27 @@ impl ICNViewer {
                 });
             });
 
-            if self.icn.animation_header.frame_length > 1 {
+            if self.icn.is_animated() {
                 ui.add_space(4.0);
                 ui.horizontal(|ui| {
-                    if ui.button("Play/Stop").clicked() {
+                    let label = if self.playing { "Pause" } else { "Play" };
+                    if ui.button(label).clicked() {
+                        self.playing = !self.playing;
                         if self.playing {
-                            self.playing = false;
-                            self.frame = 0;
-                        } else {
-                            self.playing = true;
-                            self.start_time = Instant::now();
+                            // Resume from the frame the scrubber was left at
+                            self.start_time = Instant::now()
+                                - Duration::from_secs_f32(self.frame as f32 / 60.0);
                         }
                     }
                     ui.spacing_mut().slider_width =
                         ui.available_width() - ui.spacing().interact_size.x - 9.0;
-                    ui.add(egui::Slider::new(
+                    let scrubber = ui.add(egui::Slider::new(
                         &mut self.frame,
                         0..=self.icn.animation_header.frame_length,
                     ));
+                    if scrubber.dragged() {
+                        self.playing = false;
+                    }
                 });
                 ui.add_space(4.0);
             }
@@ -402,6 +415,10 @@ impl Tab for ICNViewer {
         self.file.clone()
     }
 
+    fn get_path(&self) -> &Path {
+        &self.path
+    }
+
     fn get_modified(&self) -> bool {
         self.modified
     }
@@ -414,43 +431,21 @@ impl Tab for ICNViewer {
         file.write_all(&bytes).expect("Failed to write to file");
         self.modified = false;
     }
+
+    fn reload(&mut self) {
+        if let Ok(icn) = std::fs::read(&self.path).and_then(|buf| ICNParser::read(&buf)) {
+            self.icn = icn;
+            self.frame = 0;
+            self.texture = None;
+            self.needs_rebuild = true;
+            self.modified = false;
+        }
+    }
 }
 
 fn draw_background(ui: &mut Ui, colors: &[Color32; 4]) {
     let rect = ui.available_rect_before_wrap();
     let painter = ui.painter_at(rect);
 
-    let top_left = rect.left_top();
-    let top_right = rect.right_top();
-    let bottom_left = rect.left_bottom();
-    let bottom_right = rect.right_bottom();
-
-    let mut mesh = egui::epaint::Mesh::default();
-
-    let i0 = mesh.vertices.len() as u32;
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: top_left,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[0],
-    });
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: top_right,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[1],
-    });
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: bottom_right,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[3],
-    });
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: bottom_left,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[2],
-    });
-
-    mesh.indices
-        .extend_from_slice(&[i0, i0 + 1, i0 + 2, i0, i0 + 2, i0 + 3]);
-
-    painter.add(egui::Shape::mesh(mesh));
+    paint_gradient(&painter, rect, colors, 1.0);
 }
@@ -1,16 +1,21 @@
+use crate::components::gradient::paint_gradient;
+use crate::messages::{tr, Message};
 use crate::tabs::Tab;
 use crate::{AppState, VirtualFile};
 use eframe::egui;
 use eframe::egui::{
-    vec2, Color32, CornerRadius, Grid, Id, PopupCloseBehavior, Response, Rgba, TextEdit, Ui,
+    vec2, Button, Color32, CornerRadius, Grid, Id, PopupCloseBehavior, Rect, Response, Rgba, Sense,
+    StrokeKind, TextEdit, Ui,
 };
 use ps2_filetypes::color::Color;
-use ps2_filetypes::{ColorF, IconSys, Vector};
+use ps2_filetypes::sjis::encode_sjis;
+use ps2_filetypes::{split_title, ColorF, IconSys, Vector, TITLE_SIZE};
+use psu_packer::sjis_title;
 use relative_path::PathExt;
 use std::ops::Add;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct PS2RgbaInterface {
     pub rgb: [f32; 3],
     pub alpha: f32,
@@ -81,6 +86,57 @@ impl Light {
     }
 }
 
+/// Sections of the editor that are tracked and reverted on their own
+#[derive(Clone, Copy)]
+enum Group {
+    Title,
+    Icons,
+    Background,
+    Lights,
+}
+
+/// A built-in background and lighting combination, colors are in the same 0..=255 range the
+/// file stores backgrounds in and 0..=1 for lights
+struct Template {
+    name: &'static str,
+    background: [[u8; 3]; 4],
+    ambient: [f32; 3],
+    lights: [[f32; 3]; 3],
+}
+
+const TEMPLATES: [Template; 5] = [
+    Template {
+        name: "Midnight",
+        background: [[0, 0, 64], [0, 0, 64], [0, 0, 16], [0, 0, 16]],
+        ambient: [0.25, 0.25, 0.25],
+        lights: [[0.5, 0.5, 0.5], [0.3, 0.3, 0.3], [0.2, 0.2, 0.2]],
+    },
+    Template {
+        name: "Sunset",
+        background: [[128, 48, 16], [96, 16, 64], [32, 8, 32], [16, 0, 32]],
+        ambient: [0.3, 0.2, 0.2],
+        lights: [[0.8, 0.5, 0.3], [0.4, 0.2, 0.4], [0.2, 0.1, 0.1]],
+    },
+    Template {
+        name: "Forest",
+        background: [[16, 80, 32], [32, 96, 48], [0, 32, 16], [8, 24, 8]],
+        ambient: [0.2, 0.3, 0.2],
+        lights: [[0.5, 0.7, 0.4], [0.2, 0.4, 0.3], [0.1, 0.2, 0.1]],
+    },
+    Template {
+        name: "Crimson",
+        background: [[96, 0, 0], [64, 0, 16], [24, 0, 0], [8, 0, 8]],
+        ambient: [0.3, 0.15, 0.15],
+        lights: [[0.9, 0.4, 0.4], [0.4, 0.1, 0.1], [0.2, 0.1, 0.1]],
+    },
+    Template {
+        name: "Silver",
+        background: [[112, 112, 120], [96, 96, 104], [48, 48, 56], [32, 32, 40]],
+        ambient: [0.35, 0.35, 0.35],
+        lights: [[0.7, 0.7, 0.75], [0.4, 0.4, 0.45], [0.2, 0.2, 0.25]],
+    },
+];
+
 pub struct IconSysViewer {
     title: String,
     file: String,
@@ -93,6 +149,9 @@ pub struct IconSysViewer {
     pub lights: [Light; 3],
     pub sys: IconSys,
     pub file_path: PathBuf,
+    pub backup_on_save: bool,
+    read_only: bool,
+    compare: bool,
 }
 
 impl IconSysViewer {
@@ -101,6 +160,18 @@ impl IconSysViewer {
 
         let sys = IconSys::new(buf);
 
+        Self::from_sys(
+            sys,
+            file.file_path
+                .relative_to(state.opened_folder.clone().unwrap())
+                .unwrap()
+                .to_string(),
+            file.file_path.clone(),
+            state.read_only,
+        )
+    }
+
+    fn from_sys(sys: IconSys, file: String, file_path: PathBuf, read_only: bool) -> Self {
         Self {
             title: sys.title.clone(),
             icon_file: sys.icon_file.clone(),
@@ -120,12 +191,11 @@ impl IconSysViewer {
                 Light::new(sys.light_colors[2], sys.light_directions[2]),
             ],
             sys,
-            file_path: file.file_path.clone(),
-            file: file
-                .file_path
-                .relative_to(state.opened_folder.clone().unwrap())
-                .unwrap()
-                .to_string(),
+            file_path,
+            backup_on_save: false,
+            read_only,
+            compare: false,
+            file,
         }
     }
 
@@ -154,14 +224,35 @@ impl IconSysViewer {
             // eframe::egui::Grid::new(Id::from("IconSysEditor"))
             //     .num_columns(2)
             //     .show(ui, |ui| {
-            ui.heading("Icon Configuration");
+            self.group_heading(ui, "Icon Configuration", Group::Title);
             ui.add_space(4.0);
             ui.horizontal(|ui| {
                 ui.label("Title");
                 ui.add(TextEdit::singleline(&mut self.title));
+                title_length_label(ui, &self.title);
+                let safe = sjis_title(&self.title);
+                if safe != self.title
+                    && ui
+                        .add_enabled(!self.read_only, Button::new("Transliterate"))
+                        .on_hover_text(format!("Replace what Shift-JIS can't encode: {safe}"))
+                        .clicked()
+                {
+                    self.title = safe;
+                }
+            });
+            if !self.sys.title_decoded_cleanly {
+                ui.colored_label(ui.visuals().warn_fg_color, tr(Message::TitleMisDecoded));
+            }
+            ui.horizontal(|ui| {
+                let (first_line, second_line) = split_title(&self.title, self.sys.linebreak_pos);
+                ui.label("Displayed as");
+                ui.vertical(|ui| {
+                    ui.weak(first_line);
+                    ui.weak(second_line);
+                });
             });
 
-            ui.heading("Icons");
+            self.group_heading(ui, "Icons", Group::Icons);
             ui.add_space(4.0);
 
             Grid::new("icons").num_columns(2).show(ui, |ui| {
@@ -175,13 +266,13 @@ impl IconSysViewer {
                 file_select(ui, "delete_icon", &mut self.icon_delete_file, &files);
             });
 
-            ui.heading("Background");
+            self.group_heading(ui, "Background", Group::Background);
             ui.add_space(4.0);
 
             const SPACING: f32 = 40.0;
 
             ui.add_sized(vec2(SPACING * 3.0, SPACING * 3.0), |ui: &mut Ui| {
-                draw_background(ui, &self.background_colors);
+                draw_background(ui, &self.background_colors, self.background_transparency);
                 ui.spacing_mut().interact_size = vec2(SPACING, SPACING);
                 ui.spacing_mut().item_spacing = vec2(0.0, 0.0);
 
@@ -213,6 +304,15 @@ impl IconSysViewer {
                 ui.response()
             });
 
+            ui.label("Templates");
+            self.template_gallery(ui);
+
+            ui.checkbox(&mut self.compare, "Compare with saved")
+                .on_hover_text("Show the saved colors next to the edited ones");
+            if self.compare {
+                self.compare_preview(ui);
+            }
+
             Grid::new("background").num_columns(2).show(ui, |ui| {
                 ui.label("Background Transparency").on_hover_ui(|ui| {
                     ui.label(
@@ -230,7 +330,7 @@ impl IconSysViewer {
                 ui.end_row();
             });
 
-            ui.heading("Lights");
+            self.group_heading(ui, "Lights", Group::Lights);
             ui.add_space(4.0);
 
             for (index, light) in self.lights.iter_mut().enumerate() {
@@ -256,14 +356,134 @@ impl IconSysViewer {
             }
 
             // });
-            ui.button("Save")
-                .on_hover_text("Save changes")
+            ui.horizontal(|ui| {
+                ui.add_enabled(!self.read_only, Button::new("Save"))
+                    .on_hover_text("Save changes")
+                    .on_disabled_hover_text(tr(Message::ReadOnlySave))
+                    .clicked()
+                    .then(|| {
+                        self.save();
+                    });
+                ui.checkbox(&mut self.backup_on_save, "Back up original")
+                    .on_hover_text("Copy the current file to icon.sys.bak before overwriting it");
+            });
+        });
+    }
+}
+
+impl IconSysViewer {
+    /// Draws a section heading, marked with an asterisk and a revert button once it differs
+    /// from the file on disk
+    fn group_heading(&mut self, ui: &mut Ui, heading: &str, group: Group) {
+        ui.horizontal(|ui| {
+            if !self.group_modified(group) {
+                ui.heading(heading);
+                return;
+            }
+
+            ui.heading(format!("{heading} *"))
+                .on_hover_text("Changed since the file was loaded");
+            if ui
+                .small_button("Revert")
+                .on_hover_text("Restore this section from the file on disk")
                 .clicked()
-                .then(|| {
-                    self.save();
+            {
+                self.revert(group);
+            }
+        });
+    }
+
+    /// The editor as it was when the file was loaded or last saved
+    fn loaded(&self) -> Self {
+        Self::from_sys(self.sys.clone(), String::new(), PathBuf::new(), true)
+    }
+
+    fn group_modified(&self, group: Group) -> bool {
+        let loaded = self.loaded();
+        match group {
+            Group::Title => self.title != loaded.title,
+            Group::Icons => {
+                self.icon_file != loaded.icon_file
+                    || self.icon_copy_file != loaded.icon_copy_file
+                    || self.icon_delete_file != loaded.icon_delete_file
+            }
+            Group::Background => {
+                self.background_colors != loaded.background_colors
+                    || self.background_transparency != loaded.background_transparency
+                    || self.ambient_color != loaded.ambient_color
+            }
+            Group::Lights => {
+                let changed = |(light, loaded): (&Light, &Light)| {
+                    light.color != loaded.color || light.direction != loaded.direction
+                };
+                self.lights.iter().zip(&loaded.lights).any(changed)
+            }
+        }
+    }
+
+    fn revert(&mut self, group: Group) {
+        let loaded = self.loaded();
+        match group {
+            Group::Title => self.title = loaded.title,
+            Group::Icons => {
+                self.icon_file = loaded.icon_file;
+                self.icon_copy_file = loaded.icon_copy_file;
+                self.icon_delete_file = loaded.icon_delete_file;
+            }
+            Group::Background => {
+                self.background_colors = loaded.background_colors;
+                self.background_transparency = loaded.background_transparency;
+                self.ambient_color = loaded.ambient_color;
+            }
+            Group::Lights => self.lights = loaded.lights,
+        }
+    }
+
+    fn compare_preview(&self, ui: &mut Ui) {
+        let loaded = self.loaded();
+        ui.horizontal(|ui| {
+            for (label, viewer) in [("Saved", &loaded), ("Edited", self)] {
+                ui.vertical(|ui| {
+                    ui.label(label);
+                    color_preview(ui, viewer);
                 });
+            }
+        });
+    }
+
+    fn template_gallery(&mut self, ui: &mut Ui) {
+        ui.horizontal_wrapped(|ui| {
+            for template in &TEMPLATES {
+                let (rect, response) = ui.allocate_exact_size(vec2(64.0, 48.0), Sense::click());
+                let corners = template
+                    .background
+                    .map(|[r, g, b]| Color32::from_rgb(r, g, b));
+                paint_gradient(&ui.painter_at(rect), rect, &corners, 1.0);
+                if response.hovered() {
+                    ui.painter().rect_stroke(
+                        rect,
+                        0.0,
+                        ui.visuals().widgets.hovered.fg_stroke,
+                        StrokeKind::Inside,
+                    );
+                }
+
+                if response.on_hover_text(template.name).clicked() {
+                    self.apply_template(template);
+                }
+            }
         });
     }
+
+    fn apply_template(&mut self, template: &Template) {
+        for (color, [r, g, b]) in self.background_colors.iter_mut().zip(template.background) {
+            color.rgb = [r, g, b].map(PS2RgbaInterface::convert_color_to_float);
+        }
+        self.ambient_color.rgb = template.ambient;
+        for (light, color) in self.lights.iter_mut().zip(template.lights) {
+            light.color.rgb = color;
+        }
+    }
 }
 
 impl Tab for IconSysViewer {
@@ -275,11 +495,14 @@ impl Tab for IconSysViewer {
         self.file.clone()
     }
 
+    fn get_path(&self) -> &Path {
+        &self.file_path
+    }
+
     fn get_modified(&self) -> bool {
-        self.sys.title != self.title
-            || self.sys.icon_file != self.icon_file
-            || self.sys.icon_copy_file != self.icon_copy_file
-            || self.sys.icon_delete_file != self.icon_delete_file
+        [Group::Title, Group::Icons, Group::Background, Group::Lights]
+            .into_iter()
+            .any(|group| self.group_modified(group))
     }
 
     fn save(&mut self) {
@@ -306,11 +529,54 @@ impl Tab for IconSysViewer {
                 self.lights[1].direction,
                 self.lights[2].direction,
             ],
+            title_decoded_cleanly: true,
             ..self.sys.clone()
         };
-        std::fs::write(&self.file_path, new_sys.to_bytes().unwrap()).expect("Failed to save icon");
+        let backup_path = self.file_path.with_extension("sys.bak");
+        let backup = self.backup_on_save.then_some(backup_path.as_path());
+        let result = new_sys
+            .to_bytes()
+            .and_then(|bytes| write_with_backup(&self.file_path, &bytes, backup));
+        // The tab stays modified, so the edits can be saved again once the problem is fixed
+        if let Err(err) = result {
+            rfd::MessageDialog::new()
+                .set_level(rfd::MessageLevel::Error)
+                .set_title("icon.sys wasn't saved")
+                .set_description(format!("{}: {err}", self.file_path.display()))
+                .show();
+            return;
+        }
         self.sys = new_sys;
     }
+
+    fn reload(&mut self) {
+        if let Ok(sys) = std::fs::read(&self.file_path).and_then(IconSys::try_new) {
+            *self = Self {
+                backup_on_save: self.backup_on_save,
+                compare: self.compare,
+                ..Self::from_sys(
+                    sys,
+                    self.file.clone(),
+                    self.file_path.clone(),
+                    self.read_only,
+                )
+            };
+        }
+    }
+}
+
+fn title_length_label(ui: &mut Ui, title: &str) {
+    // The on-card limit is in Shift-JIS bytes, not characters
+    let length = encode_sjis(title).len();
+    ui.colored_label(
+        if length > TITLE_SIZE {
+            ui.visuals().error_fg_color
+        } else {
+            ui.visuals().strong_text_color()
+        },
+        format!("{length}/{TITLE_SIZE} bytes"),
+    )
+    .on_hover_text("Encoded Shift-JIS length of the title");
 }
 
 fn set_border_radius(ui: &mut Ui, radius: CornerRadius) {
@@ -370,41 +636,111 @@ fn file_select(ui: &mut Ui, name: impl Into<String>, value: &mut String, files:
     });
 }
 
-fn draw_background(ui: &mut Ui, colors: &[PS2RgbaInterface; 4]) {
+/// The background with the ambient and light colors as swatches underneath
+fn color_preview(ui: &mut Ui, viewer: &IconSysViewer) {
+    const SIZE: f32 = 96.0;
+
+    let (rect, _) = ui.allocate_exact_size(vec2(SIZE, SIZE), Sense::hover());
+    let opacity = 1.0 - viewer.background_transparency.min(100) as f32 / 100.0;
+    paint_gradient(
+        &ui.painter_at(rect),
+        rect,
+        &viewer.background_colors.map(Color32::from),
+        opacity,
+    );
+
+    let swatches = std::iter::once(viewer.ambient_color)
+        .chain(viewer.lights.iter().map(|light| light.color))
+        .collect::<Vec<_>>();
+    let (strip, _) = ui.allocate_exact_size(vec2(SIZE, SIZE / 4.0), Sense::hover());
+    let width = SIZE / swatches.len() as f32;
+    for (index, color) in swatches.into_iter().enumerate() {
+        let swatch = Rect::from_min_size(
+            strip.min + vec2(width * index as f32, 0.0),
+            vec2(width, strip.height()),
+        );
+        ui.painter().rect_filled(swatch, 0.0, Color32::from(color));
+    }
+}
+
+fn draw_background(ui: &mut Ui, colors: &[PS2RgbaInterface; 4], transparency: u32) {
     let rect = ui.available_rect_before_wrap();
     let painter = ui.painter_at(rect);
 
-    let top_left = rect.left_top();
-    let top_right = rect.right_top();
-    let bottom_left = rect.left_bottom();
-    let bottom_right = rect.right_bottom();
+    // Transparency is the inverse of opacity, 100 lets the browser's background show through
+    let opacity = 1.0 - transparency.min(100) as f32 / 100.0;
+    paint_gradient(&painter, rect, &colors.map(Color32::from), opacity);
+}
 
-    let mut mesh = egui::epaint::Mesh::default();
+/// Writes `bytes` to `path`, first copying the file that's there to `backup` if there is one.
+/// A failed write puts the backup back, so a half-written file isn't left behind.
+fn write_with_backup(path: &Path, bytes: &[u8], backup: Option<&Path>) -> std::io::Result<()> {
+    let backup = backup.filter(|_| path.exists());
+    if let Some(backup) = backup {
+        std::fs::copy(path, backup)?;
+    }
 
-    let i0 = mesh.vertices.len() as u32;
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: top_left,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[0].into(),
-    });
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: top_right,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[1].into(),
-    });
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: bottom_right,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[3].into(),
-    });
-    mesh.vertices.push(egui::epaint::Vertex {
-        pos: bottom_left,
-        uv: egui::epaint::WHITE_UV,
-        color: colors[2].into(),
-    });
+    std::fs::write(path, bytes).inspect_err(|_| {
+        if let Some(backup) = backup {
+            let _ = std::fs::copy(backup, path);
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn backup_keeps_the_previous_icon_sys() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("icon.sys");
+        let backup = folder.path().join("icon.sys.bak");
 
-    mesh.indices
-        .extend_from_slice(&[i0, i0 + 1, i0 + 2, i0, i0 + 2, i0 + 3]);
+        write_with_backup(&path, b"first", Some(&backup)).unwrap();
+        assert!(!backup.exists());
+        write_with_backup(&path, b"second", Some(&backup)).unwrap();
+        assert_eq!(std::fs::read(&path).unwrap(), b"second");
+        assert_eq!(std::fs::read(&backup).unwrap(), b"first");
 
-    painter.add(egui::Shape::mesh(mesh));
+        write_with_backup(&path, b"third", None).unwrap();
+        assert_eq!(std::fs::read(&backup).unwrap(), b"first");
+    }
+
+    #[test]
+    fn each_section_is_tracked_and_reverted_on_its_own() {
+        let mut viewer =
+            IconSysViewer::from_sys(IconSys::default(), String::new(), PathBuf::new(), false);
+        let modified = |viewer: &IconSysViewer| {
+            [Group::Title, Group::Icons, Group::Background, Group::Lights]
+                .map(|group| viewer.group_modified(group))
+        };
+        assert_eq!(modified(&viewer), [false; 4]);
+
+        viewer.icon_delete_file = "delete.icn".to_string();
+        assert_eq!(modified(&viewer), [false, true, false, false]);
+
+        viewer.apply_template(&TEMPLATES[1]);
+        assert_eq!(modified(&viewer), [false, true, true, true]);
+
+        viewer.revert(Group::Icons);
+        assert_eq!(viewer.icon_delete_file, IconSys::default().icon_delete_file);
+        assert_eq!(modified(&viewer), [false, false, true, true]);
+        assert!(viewer.get_modified());
+
+        viewer.revert(Group::Background);
+        viewer.revert(Group::Lights);
+        assert!(!viewer.get_modified());
+    }
+
+    #[test]
+    fn failed_backup_leaves_the_icon_sys_alone() {
+        let folder = tempfile::tempdir().unwrap();
+        let path = folder.path().join("icon.sys");
+        std::fs::write(&path, b"original").unwrap();
+        let backup = folder.path().join("missing").join("icon.sys.bak");
+
+        assert!(write_with_backup(&path, b"new", Some(&backup)).is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"original");
+    }
 }
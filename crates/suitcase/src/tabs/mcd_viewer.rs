@@ -0,0 +1,141 @@
+use crate::data::state::AppState;
+use crate::tabs::Tab;
+use crate::VirtualFile;
+use bytesize::ByteSize;
+use eframe::egui::{menu, Grid, ScrollArea, Ui};
+use memcard::fat::Memcard;
+use memcard::save::Save;
+use ps2_filetypes::PSUWriter;
+use relative_path::PathExt;
+use std::path::{Path, PathBuf};
+
+pub struct MCDViewer {
+    file: String,
+    file_path: PathBuf,
+    card: std::io::Result<Card>,
+}
+
+/// What the viewer shows of a card, read once when it's opened
+struct Card {
+    saves: Vec<Save>,
+    free_bytes: usize,
+    total_bytes: usize,
+}
+
+impl MCDViewer {
+    pub fn new(file: &VirtualFile, state: &AppState) -> Self {
+        Self {
+            file: file
+                .file_path
+                .relative_to(state.opened_folder.clone().unwrap())
+                .unwrap()
+                .to_string(),
+            file_path: file.file_path.clone(),
+            card: read_card(&file.file_path),
+        }
+    }
+
+    pub fn show(&mut self, ui: &mut Ui) {
+        let card = match &self.card {
+            Ok(card) => card,
+            Err(err) => {
+                ui.colored_label(
+                    ui.visuals().error_fg_color,
+                    format!("Failed to read memory card: {err}"),
+                );
+                return;
+            }
+        };
+
+        ui.vertical(|ui| {
+            menu::bar(ui, |ui| {
+                ui.set_height(25.0);
+                ui.label(format!("{} saves", card.saves.len()));
+                ui.separator();
+                ui.label(format!(
+                    "{} free of {}",
+                    ByteSize::b(card.free_bytes as u64),
+                    ByteSize::b(card.total_bytes as u64)
+                ));
+            });
+            ui.separator();
+
+            ScrollArea::vertical().show(ui, |ui| {
+                Grid::new("mcd_saves")
+                    .num_columns(4)
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Name");
+                        ui.strong("Size");
+                        ui.strong("Modified");
+                        ui.end_row();
+
+                        for save in &card.saves {
+                            ui.label(&save.name);
+                            ui.label(ByteSize::b(save.size()).to_string());
+                            ui.label(save.modified.to_string());
+                            if ui.button("Extract PSU").clicked() {
+                                extract_save(save);
+                            }
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+    }
+}
+
+fn read_card(path: &Path) -> std::io::Result<Card> {
+    let mut card = Memcard::new(std::fs::read(path)?)?;
+
+    Ok(Card {
+        saves: card.saves()?,
+        free_bytes: card.free_bytes(),
+        total_bytes: card.total_bytes(),
+    })
+}
+
+fn extract_save(save: &Save) {
+    let Some(path) = rfd::FileDialog::new()
+        .set_file_name(format!("{}.psu", save.name))
+        .add_filter("PSU", &["psu"])
+        .save_file()
+    else {
+        return;
+    };
+
+    let result = PSUWriter::new(save.to_psu())
+        .to_bytes()
+        .and_then(|bytes| std::fs::write(path, bytes));
+    if let Err(err) = result {
+        rfd::MessageDialog::new()
+            .set_level(rfd::MessageLevel::Error)
+            .set_title("Failed to extract save")
+            .set_description(err.to_string())
+            .show();
+    }
+}
+
+impl Tab for MCDViewer {
+    fn get_id(&self) -> &str {
+        &self.file
+    }
+
+    fn get_title(&self) -> String {
+        self.file.to_string()
+    }
+
+    fn get_path(&self) -> &Path {
+        &self.file_path
+    }
+
+    fn get_modified(&self) -> bool {
+        false
+    }
+
+    fn save(&mut self) {}
+
+    fn reload(&mut self) {
+        self.card = read_card(&self.file_path);
+    }
+}
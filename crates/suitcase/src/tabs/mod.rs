@@ -1,9 +1,12 @@
 pub mod icn_viewer;
 pub mod icon_sys_viewer;
+pub mod mcd_viewer;
+pub mod registry;
 pub mod tab;
 pub mod title_cfg_viewer;
 
 pub use icn_viewer::*;
 pub use icon_sys_viewer::*;
+pub use mcd_viewer::*;
 pub use tab::*;
 pub use title_cfg_viewer::*;
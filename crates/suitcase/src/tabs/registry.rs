@@ -0,0 +1,162 @@
+use crate::components::tab_viewer::TabType;
+use crate::data::state::AppState;
+use crate::data::virtual_file::VirtualFile;
+use crate::tabs::{ICNViewer, IconSysViewer, MCDViewer, Tab, TitleCfgViewer};
+use eframe::egui::Ui;
+use std::path::Path;
+
+/// A tab for a file type suitcase doesn't know about, shown through `TabType::Custom`
+pub trait Viewer: Tab {
+    fn show(&mut self, ui: &mut Ui, app: &mut AppState);
+}
+
+/// Opens files with the extensions it claims. Register one with
+/// `ViewerRegistry::register` to open a file type in a tab of your own,
+/// or to replace one of the built-in viewers.
+pub trait ViewerFactory {
+    /// Lowercase extensions without the leading dot
+    fn extensions(&self) -> &[&str];
+    fn create(&self, file: &VirtualFile, state: &AppState) -> TabType;
+}
+
+struct BuiltinViewer {
+    extensions: &'static [&'static str],
+    create: fn(&VirtualFile, &AppState) -> TabType,
+}
+
+impl ViewerFactory for BuiltinViewer {
+    fn extensions(&self) -> &[&str] {
+        self.extensions
+    }
+
+    fn create(&self, file: &VirtualFile, state: &AppState) -> TabType {
+        (self.create)(file, state)
+    }
+}
+
+const BUILTIN_VIEWERS: [BuiltinViewer; 4] = [
+    BuiltinViewer {
+        extensions: &["icn", "ico"],
+        create: |file, state| TabType::ICNViewer(ICNViewer::new(file, state)),
+    },
+    BuiltinViewer {
+        extensions: &["sys"],
+        create: |file, state| TabType::IconSysViewer(IconSysViewer::new(file, state)),
+    },
+    BuiltinViewer {
+        extensions: &["cfg", "cnf", "dat", "txt"],
+        create: |file, state| TabType::TitleCfgViewer(TitleCfgViewer::new(file, state)),
+    },
+    BuiltinViewer {
+        extensions: &["mcd", "mc2", "ps2"],
+        create: |file, state| TabType::MCDViewer(MCDViewer::new(file, state)),
+    },
+];
+
+/// Picks the viewer a file opens in by its extension
+pub struct ViewerRegistry {
+    factories: Vec<Box<dyn ViewerFactory>>,
+}
+
+impl Default for ViewerRegistry {
+    /// Just the built-in viewers
+    fn default() -> Self {
+        let mut registry = Self { factories: vec![] };
+        for viewer in BUILTIN_VIEWERS {
+            registry.register(viewer);
+        }
+
+        registry
+    }
+}
+
+impl ViewerRegistry {
+    /// Later registrations take precedence, so an extension can be taken over
+    /// from a built-in viewer
+    pub fn register(&mut self, factory: impl ViewerFactory + 'static) {
+        self.factories.push(Box::new(factory));
+    }
+
+    pub fn create(&self, file: &VirtualFile, state: &AppState) -> Option<TabType> {
+        let extension = Path::new(&file.name)
+            .extension()?
+            .to_string_lossy()
+            .to_ascii_lowercase();
+        let factory = self
+            .factories
+            .iter()
+            .rev()
+            .find(|factory| factory.extensions().contains(&extension.as_str()))?;
+
+        Some(factory.create(file, state))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    struct HexViewer {
+        path: PathBuf,
+    }
+
+    impl Tab for HexViewer {
+        fn get_id(&self) -> &str {
+            "hex"
+        }
+        fn get_title(&self) -> String {
+            "Hex".to_string()
+        }
+        fn get_path(&self) -> &Path {
+            &self.path
+        }
+        fn get_modified(&self) -> bool {
+            false
+        }
+        fn save(&mut self) {}
+        fn reload(&mut self) {}
+    }
+
+    impl Viewer for HexViewer {
+        fn show(&mut self, _ui: &mut Ui, _app: &mut AppState) {}
+    }
+
+    struct HexViewerFactory;
+
+    impl ViewerFactory for HexViewerFactory {
+        fn extensions(&self) -> &[&str] {
+            &["bin", "sys"]
+        }
+
+        fn create(&self, file: &VirtualFile, _state: &AppState) -> TabType {
+            TabType::Custom(Box::new(HexViewer {
+                path: file.file_path.clone(),
+            }))
+        }
+    }
+
+    fn file(name: &str) -> VirtualFile {
+        VirtualFile {
+            name: name.to_string(),
+            file_path: PathBuf::from("save").join(name),
+            size: 0,
+        }
+    }
+
+    #[test]
+    fn registered_viewer_opens_its_extensions() {
+        let mut registry = ViewerRegistry::default();
+        registry.register(HexViewerFactory);
+        let state = AppState::new();
+
+        for name in ["DATA.BIN", "icon.sys"] {
+            let Some(TabType::Custom(tab)) = registry.create(&file(name), &state) else {
+                panic!("{name} should open in the registered viewer");
+            };
+            assert_eq!(tab.get_path(), Path::new("save").join(name));
+        }
+        assert!(registry.create(&file("notes.xyz"), &state).is_none());
+        assert!(registry.create(&file("README"), &state).is_none());
+    }
+}
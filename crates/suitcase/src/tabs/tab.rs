@@ -1,7 +1,12 @@
+use std::path::Path;
+
 pub trait Tab {
     fn get_id(&self) -> &str;
     fn get_title(&self) -> String;
+    fn get_path(&self) -> &Path;
     fn get_modified(&self) -> bool;
 
     fn save(&mut self);
+    /// Re-reads the file from disk, discarding unsaved changes but keeping view settings
+    fn reload(&mut self);
 }
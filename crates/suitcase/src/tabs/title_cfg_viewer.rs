@@ -1,11 +1,17 @@
 use crate::data::state::AppState;
+use crate::messages::{tr, Message};
 use crate::tabs::Tab;
 use crate::VirtualFile;
-use eframe::egui::{menu, CornerRadius, Id, PopupCloseBehavior, Response, TextEdit, Ui};
+use eframe::egui::text::LayoutJob;
+use eframe::egui::{
+    menu, pos2, vec2, Align2, Button, CornerRadius, Id, PopupCloseBehavior, Response, ScrollArea, Sense,
+    TextEdit, TextStyle, Ui,
+};
+use ps2_filetypes::sjis::{decode_text, encode_text, LineEnding, TextEncoding};
 use ps2_filetypes::TitleCfg;
 use relative_path::PathExt;
 use std::ops::Add;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use toml::Value;
 
 const MAXIMUM_DESCRIPTION_LENGTH: usize = 250;
@@ -16,15 +22,28 @@ pub struct TitleCfgViewer {
     title_cfg: TitleCfg,
     modified: bool,
     encoding_error: bool,
+    encoding: TextEncoding,
+    /// What the file was loaded with, the editors always work in LF
+    line_ending: LineEnding,
+    normalize_line_endings: bool,
     is_raw_editor: bool,
+    split_view: bool,
+    invalid_lines: Vec<usize>,
+    show_line_numbers: bool,
+    word_wrap: bool,
+    read_only: bool,
 }
 
 impl TitleCfgViewer {
     pub fn new(file: &VirtualFile, state: &AppState) -> Self {
         let buf = std::fs::read(&file.file_path).expect("Failed to read file");
 
-        let contents = String::from_utf8(buf).ok();
-        let encoding_error = contents.is_none();
+        let decoded = decode_text(&buf);
+        let encoding_error = decoded.is_none();
+        let (contents, encoding) = decoded.unwrap_or((String::new(), TextEncoding::Utf8));
+        let line_ending = LineEnding::detect(&contents);
+
+        let title_cfg = TitleCfg::new(LineEnding::Lf.apply(&contents));
 
         Self {
             file: file
@@ -33,121 +52,280 @@ impl TitleCfgViewer {
                 .unwrap()
                 .to_string(),
             file_path: file.file_path.clone(),
-            title_cfg: TitleCfg::new(contents.unwrap_or_default()),
+            invalid_lines: title_cfg.invalid_lines(),
+            title_cfg,
             encoding_error,
+            encoding,
+            line_ending,
+            normalize_line_endings: state.normalize_line_endings,
             modified: false,
             is_raw_editor: false,
+            split_view: false,
+            show_line_numbers: true,
+            word_wrap: true,
+            read_only: state.read_only,
+        }
+    }
+
+    fn raw_editor(&mut self, ui: &mut Ui) {
+        if !self.invalid_lines.is_empty() {
+            let lines = self
+                .invalid_lines
+                .iter()
+                .map(usize::to_string)
+                .collect::<Vec<_>>()
+                .join(", ");
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                format!("Not a key=value pair on line(s) {lines}, fix them to use the form."),
+            );
         }
+
+        let font_id = TextStyle::Monospace.resolve(ui.style());
+        let word_wrap = self.word_wrap;
+        let mut layouter = |ui: &Ui, text: &str, wrap_width: f32| {
+            let job = LayoutJob::simple(
+                text.to_string(),
+                font_id.clone(),
+                ui.visuals().text_color(),
+                if word_wrap { wrap_width } else { f32::INFINITY },
+            );
+            ui.fonts(|fonts| fonts.layout_job(job))
+        };
+
+        ScrollArea::both().auto_shrink(false).show(ui, |ui| {
+            ui.horizontal_top(|ui| {
+                let line_count = self.title_cfg.contents.split('\n').count();
+                let gutter = self.show_line_numbers.then(|| {
+                    let digit_width = ui.fonts(|fonts| fonts.glyph_width(&font_id, '0'));
+                    let width = digit_width * line_count.to_string().len() as f32 + 8.0;
+                    ui.allocate_exact_size(vec2(width, 0.0), Sense::hover()).0
+                });
+
+                let output = TextEdit::multiline(&mut self.title_cfg.contents)
+                    .code_editor()
+                    .desired_width(if word_wrap {
+                        ui.available_width()
+                    } else {
+                        f32::INFINITY
+                    })
+                    .layouter(&mut layouter)
+                    .show(ui);
+                if output.response.changed() {
+                    self.raw_changed();
+                }
+
+                if let Some(gutter) = gutter {
+                    // Only number rows that start a new line, wrapped continuations stay blank
+                    let color = ui.visuals().weak_text_color();
+                    let mut starts_line = true;
+                    let mut line = 1;
+                    for row in &output.galley.rows {
+                        if starts_line {
+                            ui.painter().text(
+                                pos2(gutter.right() - 4.0, output.galley_pos.y + row.rect.top()),
+                                Align2::RIGHT_TOP,
+                                line.to_string(),
+                                font_id.clone(),
+                                color,
+                            );
+                            line += 1;
+                        }
+                        starts_line = row.ends_with_newline;
+                    }
+                }
+            });
+        });
     }
 
-    pub fn show(&mut self, ui: &mut Ui) {
+    pub fn show(&mut self, ui: &mut Ui, app: &AppState) {
+        self.normalize_line_endings = app.normalize_line_endings;
+
         ui.vertical(|ui| {
             menu::bar(ui, |ui| {
                 ui.set_height(25.0);
-                ui.button("Save").clicked().then(|| self.save());
-                ui.button("Toggle Raw Editor").clicked().then(|| {
-                    self.toggle_editors();
-                });
+                ui.add_enabled(!self.read_only, Button::new("Save"))
+                    .on_disabled_hover_text(tr(Message::ReadOnlySave))
+                    .clicked()
+                    .then(|| self.save());
+                ui.add_enabled(!self.split_view, Button::new("Toggle Raw Editor"))
+                    .clicked()
+                    .then(|| self.toggle_editors());
+                let mut split_view = self.split_view;
+                if ui.checkbox(&mut split_view, "Split View").changed() {
+                    self.sync_editors();
+                    self.split_view = split_view;
+                }
+                if ui.button("Copy as JSON").clicked() {
+                    self.sync_editors();
+                    ui.ctx().copy_text(self.title_cfg.to_json());
+                }
+                if self.is_raw_editor || self.split_view {
+                    ui.separator();
+                    ui.checkbox(&mut self.show_line_numbers, "Line Numbers");
+                    ui.checkbox(&mut self.word_wrap, "Word Wrap");
+                }
+                ui.separator();
+                ui.weak(self.encoding.to_string());
+                self.line_ending_ui(ui);
             });
             ui.separator();
 
-            if self.is_raw_editor {
-                eframe::egui::Grid::new(Id::from("TitleCfgEditor"))
-                    .num_columns(1)
-                    .min_col_width(ui.available_width())
-                    .show(ui, |ui| {
-                        ui.add(
-                            TextEdit::multiline(&mut self.title_cfg.contents)
-                                .desired_width(ui.available_width()),
-                        )
-                            .changed()
-                            .then(|| self.modified = true);
-                    });
+            if self.split_view {
+                ui.columns(2, |columns| {
+                    self.form_editor(&mut columns[0]);
+                    self.raw_editor(&mut columns[1]);
+                });
+            } else if self.is_raw_editor {
+                self.raw_editor(ui);
             } else {
-                eframe::egui::Grid::new(Id::from("TitleCfgEditor"))
-                    .num_columns(3)
-                    .min_col_width(200.0)
-                    .max_col_width(ui.available_width())
-                    .show(ui, |ui| {
-                        if self.encoding_error {
-                            ui.colored_label(
-                                eframe::egui::Color32::RED,
-                                "Encoding error, please use valid ASCII or UTF-8 encoding.",
-                            );
-                            return;
-                        }
+                self.form_editor(ui);
+            }
+        });
+    }
+
+    fn form_editor(&mut self, ui: &mut Ui) {
+        if !self.invalid_lines.is_empty() {
+            ui.colored_label(
+                ui.visuals().error_fg_color,
+                "The raw text has lines that aren't key=value pairs, fix them to use the form.",
+            );
+            return;
+        }
+
+        let mut changed = false;
+        eframe::egui::Grid::new(Id::from("TitleCfgEditor"))
+            .num_columns(3)
+            .min_col_width(200.0)
+            .max_col_width(ui.available_width())
+            .show(ui, |ui| {
+                if self.encoding_error {
+                    ui.colored_label(
+                        ui.visuals().error_fg_color,
+                        "Encoding error, please use valid ASCII, UTF-8 or Shift-JIS encoding.",
+                    );
+                    return;
+                }
 
-                        if !self.title_cfg.has_mandatory_fields() {
+                if !self.title_cfg.has_mandatory_fields() {
+                    ui.colored_label(ui.visuals().error_fg_color, "Missing mandatory fields.");
+                    ui.button("Fix").clicked().then(|| {
+                        self.title_cfg.add_missing_fields();
+                        changed = true;
+                    });
+                    ui.end_row();
+                }
+
+                for (key, value) in self.title_cfg.index_map.iter_mut() {
+                    let key_helper = self.title_cfg.helper.get(key);
+
+                    let mut tooltip_content = "".to_string();
+                    if key_helper.is_some_and(|key| key.get("tooltip").is_some()) {
+                        tooltip_content = key_helper.unwrap().get("tooltip").unwrap().to_string();
+                    }
+
+                    let key_label = ui.label(key.to_string());
+                    if !tooltip_content.is_empty() {
+                        key_label.on_hover_ui(|ui| {
+                            ui.label(tooltip_content);
+                        });
+                    }
+
+                    if key == "Description" {
+                        ui.add(TextEdit::multiline(value).desired_rows(6))
+                            .changed()
+                            .then(|| changed = true);
+                        if value.len() > MAXIMUM_DESCRIPTION_LENGTH {
                             ui.colored_label(
-                                eframe::egui::Color32::RED,
-                                "Missing mandatory fields.",
+                                ui.visuals().error_fg_color,
+                                format!(
+                                    "Description too long, it will be truncated in OPL. {}/{}",
+                                    value.len(),
+                                    MAXIMUM_DESCRIPTION_LENGTH,
+                                ),
                             );
-                            ui.button("Fix").clicked().then(|| {
-                                self.title_cfg.add_missing_fields();
-                                self.modified = true;
-                            });
-                            ui.end_row();
                         }
+                    } else if key_helper.is_some_and(is_bool_field) {
+                        bool_select(ui, value, key_helper.unwrap())
+                            .changed()
+                            .then(|| changed = true);
+                    } else if key_helper.is_some_and(|key| key.get("values").is_some()) {
+                        value_select(ui, key, value, key_helper.unwrap().get("values").unwrap())
+                            .changed()
+                            .then(|| changed = true);
+                    } else {
+                        ui.text_edit_singleline(value)
+                            .changed()
+                            .then(|| changed = true);
+                    }
 
-                        for (key, value) in self.title_cfg.index_map.iter_mut() {
-                            let key_helper = self.title_cfg.helper.get(key);
-
-                            let mut tooltip_content = "".to_string();
-                            if key_helper.is_some_and(|key| key.get("tooltip").is_some()) {
-                                tooltip_content =
-                                    key_helper.unwrap().get("tooltip").unwrap().to_string();
-                            }
-
-                            let key_label = ui.label(key.to_string());
-                            if !tooltip_content.is_empty() {
-                                key_label.on_hover_ui(|ui| {
-                                    ui.label(tooltip_content);
-                                });
-                            }
-
-                            if key == "Description" {
-                                ui.add(TextEdit::multiline(value).desired_rows(6))
-                                    .changed()
-                                    .then(|| self.modified = true);
-                                if value.len() > MAXIMUM_DESCRIPTION_LENGTH {
-                                    ui.colored_label(
-                                        eframe::egui::Color32::RED,
-                                        format!(
-                                            "Description too long, it will be truncated in OPL. {}/{}",
-                                            value.len(),
-                                            MAXIMUM_DESCRIPTION_LENGTH,
-                                        ),
-                                    );
-                                }
-                            } else if key_helper.is_some_and(|key| key.get("values").is_some()) {
-                                value_select(
-                                    ui,
-                                    key,
-                                    value,
-                                    key_helper.unwrap().get("values").unwrap(),
-                                )
-                                    .changed()
-                                    .then(|| self.modified = true);
-                            } else {
-                                ui.text_edit_singleline(value)
-                                    .changed()
-                                    .then(|| self.modified = true);
-                            }
-
-                            ui.end_row();
-                        }
-                    });
-            }
-        });
+                    ui.end_row();
+                }
+            });
+
+        if changed {
+            self.form_changed();
+        }
+    }
+
+    fn raw_changed(&mut self) {
+        self.modified = true;
+        self.invalid_lines = self.title_cfg.invalid_lines();
+        if self.split_view && self.invalid_lines.is_empty() {
+            self.title_cfg.sync_contents_to_index_map();
+        }
+    }
+
+    fn form_changed(&mut self) {
+        self.modified = true;
+        if self.split_view {
+            self.title_cfg.sync_index_map_to_contents();
+        }
+    }
+
+    fn line_ending_ui(&mut self, ui: &mut Ui) {
+        let hover = if self.saved_line_ending() == self.line_ending {
+            "Line endings are kept as they are when saving"
+        } else {
+            "Saved as LF, see View > Normalize Line Endings"
+        };
+        ui.weak(self.line_ending.to_string()).on_hover_text(hover);
+        if self.saved_line_ending() != LineEnding::Lf
+            && ui
+                .add_enabled(!self.read_only, Button::new("Convert to LF"))
+                .on_hover_text("Some PS2 loaders misread title.cfg files with CRLF endings")
+                .clicked()
+        {
+            self.line_ending = LineEnding::Lf;
+            self.modified = true;
+        }
+    }
+
+    fn saved_line_ending(&self) -> LineEnding {
+        if self.normalize_line_endings {
+            LineEnding::Lf
+        } else {
+            self.line_ending
+        }
     }
 
     pub fn toggle_editors(&mut self) {
+        self.sync_editors();
+        self.is_raw_editor ^= true;
+    }
+
+    /// Brings the side that isn't being edited up to date with the one that is.
+    /// The split view keeps both in sync as you type, and while the raw text has invalid
+    /// lines the form is locked, so there's nothing to bring over.
+    fn sync_editors(&mut self) {
+        if self.split_view || !self.invalid_lines.is_empty() {
+            return;
+        }
         if self.is_raw_editor {
             self.title_cfg.sync_contents_to_index_map();
         } else {
             self.title_cfg.sync_index_map_to_contents()
         }
-        self.is_raw_editor ^= true;
     }
 }
 
@@ -159,17 +337,48 @@ impl Tab for TitleCfgViewer {
         self.file.to_string()
     }
 
+    fn get_path(&self) -> &Path {
+        &self.file_path
+    }
+
     fn get_modified(&self) -> bool {
         self.modified
     }
 
     fn save(&mut self) {
-        if self.is_raw_editor {
-            self.title_cfg.sync_contents_to_index_map();
-        }
-        std::fs::write(&self.file_path, self.title_cfg.to_string().into_bytes())
-            .expect("Failed to title.cfg");
+        // Lines the form can't represent are only kept by writing the raw text as is
+        let contents = if self.invalid_lines.is_empty() {
+            if self.is_raw_editor {
+                self.title_cfg.sync_contents_to_index_map();
+            }
+            self.title_cfg.to_string()
+        } else {
+            self.title_cfg.contents.clone()
+        };
+        let contents = self.saved_line_ending().apply(&contents);
+        let bytes = encode_text(&contents, self.encoding).unwrap_or_else(|| {
+            // Characters outside Shift-JIS were added, keep them by switching to UTF-8
+            self.encoding = TextEncoding::Utf8;
+            contents.into_bytes()
+        });
+        std::fs::write(&self.file_path, bytes).expect("Failed to title.cfg");
+        self.line_ending = self.saved_line_ending();
+
+        self.modified = false;
+    }
+
+    fn reload(&mut self) {
+        let Ok(buf) = std::fs::read(&self.file_path) else {
+            return;
+        };
 
+        let decoded = decode_text(&buf);
+        self.encoding_error = decoded.is_none();
+        let (contents, encoding) = decoded.unwrap_or((String::new(), TextEncoding::Utf8));
+        self.line_ending = LineEnding::detect(&contents);
+        self.title_cfg = TitleCfg::new(LineEnding::Lf.apply(&contents));
+        self.invalid_lines = self.title_cfg.invalid_lines();
+        self.encoding = encoding;
         self.modified = false;
     }
 }
@@ -241,6 +450,30 @@ fn value_select(
     layout_response.inner.0
 }
 
+fn is_bool_field(helper: &Value) -> bool {
+    helper.get("type").and_then(Value::as_str) == Some("bool")
+}
+
+/// On/off fields are written as the helper's `true` and `false` strings, `1` and `0` by default
+fn bool_strings(helper: &Value) -> (&str, &str) {
+    (
+        helper.get("true").and_then(Value::as_str).unwrap_or("1"),
+        helper.get("false").and_then(Value::as_str).unwrap_or("0"),
+    )
+}
+
+fn bool_select(ui: &mut Ui, value: &mut String, helper: &Value) -> Response {
+    let (on, off) = bool_strings(helper);
+
+    let mut checked = value == on;
+    let response = ui.checkbox(&mut checked, "");
+    if response.changed() {
+        *value = if checked { on } else { off }.to_string();
+    }
+
+    response
+}
+
 fn parse_values(value: &Value) -> Option<Vec<String>> {
     Some(
         value
@@ -250,3 +483,100 @@ fn parse_values(value: &Value) -> Option<Vec<String>> {
             .collect(),
     )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewer(contents: &str) -> (tempfile::TempDir, TitleCfgViewer) {
+        let folder = tempfile::tempdir().unwrap();
+        let file_path = folder.path().join("title.cfg");
+        std::fs::write(&file_path, contents).unwrap();
+        let mut state = AppState::new();
+        state.opened_folder = Some(folder.path().to_path_buf());
+        let file = VirtualFile {
+            name: "title.cfg".to_string(),
+            file_path,
+            size: contents.len() as u64,
+        };
+
+        let viewer = TitleCfgViewer::new(&file, &state);
+        (folder, viewer)
+    }
+
+    #[test]
+    fn split_view_syncs_edits_both_ways() {
+        let (_folder, mut viewer) = viewer("title=Foo\nboot=BOOT.ELF\n");
+        viewer.split_view = true;
+
+        viewer.title_cfg.contents = "title=Bar\nboot=BOOT.ELF\n".to_string();
+        viewer.raw_changed();
+        assert_eq!(viewer.title_cfg.index_map["title"], "Bar");
+
+        viewer.title_cfg.index_map["boot"] = "MAIN.ELF".to_string();
+        viewer.form_changed();
+        assert_eq!(viewer.title_cfg.contents, "title=Bar\nboot=MAIN.ELF\n");
+        assert!(viewer.get_modified());
+    }
+
+    #[test]
+    fn invalid_raw_lines_are_saved_as_written() {
+        let (_folder, mut viewer) = viewer("title=Foo\n");
+        viewer.split_view = true;
+
+        viewer.title_cfg.contents = "title=Bar\nnot a pair\n".to_string();
+        viewer.raw_changed();
+        assert_eq!(viewer.invalid_lines, [2]);
+        assert_eq!(viewer.title_cfg.index_map["title"], "Foo");
+
+        viewer.save();
+        assert_eq!(
+            std::fs::read_to_string(&viewer.file_path).unwrap(),
+            "title=Bar\nnot a pair\n"
+        );
+    }
+
+    #[test]
+    fn crlf_is_kept_on_save_unless_normalized() {
+        let (_folder, mut viewer) = viewer("title=Foo\r\nboot=BOOT.ELF\r\n");
+        assert_eq!(viewer.title_cfg.contents, "title=Foo\nboot=BOOT.ELF\n");
+
+        viewer.save();
+        assert_eq!(
+            std::fs::read_to_string(&viewer.file_path).unwrap(),
+            "title=Foo\r\nboot=BOOT.ELF\r\n"
+        );
+
+        viewer.normalize_line_endings = true;
+        viewer.save();
+        assert_eq!(
+            std::fs::read_to_string(&viewer.file_path).unwrap(),
+            "title=Foo\nboot=BOOT.ELF\n"
+        );
+        assert_eq!(viewer.line_ending, LineEnding::Lf);
+    }
+
+    #[test]
+    fn bool_fields_write_their_own_strings_or_one_and_zero() {
+        let helpers = toml::from_str::<Value>(
+            r#"
+            [Vmode]
+            values = ["NTSC", "PAL"]
+
+            [Compat]
+            type = "bool"
+
+            [Cheats]
+            type = "bool"
+            true = "on"
+            false = "off"
+            "#,
+        )
+        .unwrap();
+
+        assert!(!is_bool_field(&helpers["Vmode"]));
+        assert!(is_bool_field(&helpers["Compat"]));
+        assert_eq!(bool_strings(&helpers["Compat"]), ("1", "0"));
+        assert_eq!(bool_strings(&helpers["Cheats"]), ("on", "off"));
+    }
+}
@@ -0,0 +1,49 @@
+use eframe::egui::{Color32, Context, Stroke, Theme, ThemePreference, Visuals};
+
+/// `ThemePreference::System` follows the OS as it switches between dark and light
+pub fn apply(ctx: &Context, preference: ThemePreference, high_contrast: bool) {
+    ctx.set_theme(preference);
+    if high_contrast {
+        ctx.set_visuals_of(Theme::Dark, high_contrast_visuals());
+        ctx.set_visuals_of(Theme::Light, high_contrast_visuals());
+    } else {
+        ctx.set_visuals_of(Theme::Dark, Visuals::dark());
+        ctx.set_visuals_of(Theme::Light, Visuals::light());
+    }
+}
+
+/// Pure black surfaces with white text and outlines, every interactive widget gets a visible border
+fn high_contrast_visuals() -> Visuals {
+    let mut visuals = Visuals::dark();
+
+    visuals.override_text_color = Some(Color32::WHITE);
+    visuals.panel_fill = Color32::BLACK;
+    visuals.window_fill = Color32::BLACK;
+    visuals.extreme_bg_color = Color32::BLACK;
+    visuals.faint_bg_color = Color32::from_gray(32);
+    visuals.window_stroke = Stroke::new(1.0, Color32::WHITE);
+    visuals.hyperlink_color = Color32::from_rgb(0x00, 0xFF, 0xFF);
+    visuals.warn_fg_color = Color32::from_rgb(0xFF, 0xFF, 0x00);
+    visuals.error_fg_color = Color32::from_rgb(0xFF, 0x60, 0x60);
+    visuals.selection.bg_fill = Color32::from_rgb(0xFF, 0xFF, 0x00);
+    visuals.selection.stroke = Stroke::new(1.0, Color32::BLACK);
+
+    let widgets = &mut visuals.widgets;
+    widgets.noninteractive.bg_stroke = Stroke::new(1.0, Color32::WHITE);
+    widgets.noninteractive.fg_stroke = Stroke::new(1.0, Color32::WHITE);
+    for widget in [
+        &mut widgets.inactive,
+        &mut widgets.hovered,
+        &mut widgets.active,
+        &mut widgets.open,
+    ] {
+        widget.bg_fill = Color32::BLACK;
+        widget.weak_bg_fill = Color32::BLACK;
+        widget.bg_stroke = Stroke::new(1.0, Color32::WHITE);
+        widget.fg_stroke = Stroke::new(1.5, Color32::WHITE);
+    }
+    widgets.hovered.bg_stroke = Stroke::new(2.0, Color32::from_rgb(0xFF, 0xFF, 0x00));
+    widgets.active.bg_stroke = Stroke::new(2.0, Color32::from_rgb(0xFF, 0xFF, 0x00));
+
+    visuals
+}
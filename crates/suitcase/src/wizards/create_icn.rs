@@ -1,5 +1,6 @@
+use crate::data::state::AppState;
 use crate::wizards::wizard::Wizard;
-use eframe::egui::{Context, Response, Ui, Widget};
+use eframe::egui::{self, Context, Response, Ui, Widget};
 use ps2_filetypes::{
     AnimationHeader,
     AnimationShape,
@@ -18,14 +19,24 @@ use std::io::{Read, Write};
 use wavefront_obj::obj::Primitive::Triangle;
 use ps2_filetypes::color::Color;
 
-pub fn create_icn_wizard(ctx: &Context, show: &mut bool) {
-    CreateICN {}.show_modal(ctx, show);
+pub fn create_icn_wizard(ctx: &Context, show: &mut bool, app: &mut AppState) {
+    CreateICN { app }.show_modal(ctx, show);
+}
+pub struct CreateICN<'a> {
+    app: &'a mut AppState,
 }
-pub struct CreateICN {}
 
-impl Widget for &mut CreateICN {
+impl Widget for &mut CreateICN<'_> {
     fn ui(self, ui: &mut Ui) -> Response {
         ui.vertical(|ui| {
+            let writable = self.app.opened_folder.is_some() && !self.app.read_only;
+            if ui
+                .add_enabled(writable, egui::Button::new("Generate From Image"))
+                .on_hover_text("Create a flat icon.icn and a matching icon.sys from a picture")
+                .clicked()
+            {
+                self.app.generate_icon();
+            }
             if ui.button("Create ICN").clicked() {
                 let mut file = File::open("teapot.obj").unwrap();
                 let mut data = vec![];
@@ -92,7 +103,7 @@ impl Widget for &mut CreateICN {
     }
 }
 
-impl Wizard for &mut CreateICN {
+impl Wizard for &mut CreateICN<'_> {
     fn get_id(&self) -> impl Hash {
         "create_icn"
     }